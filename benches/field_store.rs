@@ -0,0 +1,245 @@
+//! Compares candidate backing stores for record fields: `HashMap` (current),
+//! `IndexMap` (insertion ordered), and a `Vec<(String, String)>` sorted by key.
+//!
+//! For records of 10, 20 and 30 fields this measures:
+//! - parse throughput: parsing a full log line into the store. The `HashMap`
+//!   row is [`parse_line`] itself; the other stores are filled from
+//!   [`parse_line_borrowed`], which splits the payload with the same rules,
+//! - memory: heap bytes allocated per record (via a counting allocator),
+//! - lookup cost: average time per `get`, half hits and half misses.
+//!
+//! A second table times [`read_to_fields`] on the payload alone, to show how
+//! much of a `parse_line` call is spent building the field map.
+//!
+//! Run with `cargo bench --bench field_store`. Results are printed as a table;
+//! the field store default should only change when the numbers justify it.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use auditrs::core::parser::parser::{
+    DEFAULT_MAX_FIELD_LEN,
+    parse_line,
+    parse_line_borrowed,
+    read_to_fields,
+};
+use auditrs::core::parser::{ParseOptions, ParsedAuditRecord};
+use indexmap::IndexMap;
+
+/// Global allocator wrapper that counts bytes allocated.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size.saturating_sub(layout.size()), Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Field names from typical SYSCALL/PATH records, in emission order.
+const FIELD_NAMES: &[&str] = &[
+    "arch", "syscall", "success", "exit", "a0", "a1", "a2", "a3", "items", "ppid", "pid", "auid",
+    "uid", "gid", "euid", "suid", "fsuid", "egid", "sgid", "fsgid", "tty", "ses", "comm", "exe",
+    "subj", "key", "name", "inode", "dev", "mode",
+];
+
+/// Records parsed per timing sample.
+const PARSE_ITERATIONS: usize = 20_000;
+/// Lookups per timing sample.
+const LOOKUP_ITERATIONS: usize = 200_000;
+
+/// A field store under test.
+trait FieldStore: Sized {
+    const NAME: &'static str;
+    fn parse(line: &str) -> Self;
+    fn get(&self, key: &str) -> Option<&str>;
+}
+
+/// The current store: a `HashMap` inside the record built by `parse_line`.
+impl FieldStore for ParsedAuditRecord {
+    const NAME: &'static str = "HashMap";
+
+    fn parse(line: &str) -> Self {
+        parse_line(line, &ParseOptions::default()).unwrap()
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields().get(key).map(String::as_str)
+    }
+}
+
+impl FieldStore for IndexMap<String, String> {
+    const NAME: &'static str = "IndexMap";
+
+    fn parse(line: &str) -> Self {
+        pairs(line)
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        IndexMap::get(self, key).map(String::as_str)
+    }
+}
+
+/// Key-sorted vector, searched with binary search.
+struct SortedVec(Vec<(String, String)>);
+
+impl FieldStore for SortedVec {
+    const NAME: &'static str = "sorted Vec";
+
+    fn parse(line: &str) -> Self {
+        let mut fields: Vec<(String, String)> = pairs(line)
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+        fields.dedup_by(|a, b| a.0 == b.0);
+        SortedVec(fields)
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0
+            .binary_search_by(|(k, _)| k.as_str().cmp(key))
+            .ok()
+            .map(|i| self.0[i].1.as_str())
+    }
+}
+
+/// Splits a log line into its fields with the parser's own rules.
+fn pairs(line: &str) -> impl Iterator<Item = (&str, &str)> {
+    parse_line_borrowed(line).unwrap().fields()
+}
+
+/// Builds a payload with the first `count` field names.
+fn payload(count: usize) -> String {
+    FIELD_NAMES[..count]
+        .iter()
+        .enumerate()
+        .map(|(i, name)| format!("{name}=value{i:04}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Builds a SYSCALL log line carrying `payload`.
+fn line(payload: &str) -> String {
+    format!("type=SYSCALL msg=audit(1700000000.123:4242): {payload}")
+}
+
+/// Measurements for one store and record size.
+struct Measurement {
+    parse_ns: f64,
+    heap_bytes: usize,
+    lookup_ns: f64,
+}
+
+fn measure<S: FieldStore>(line: &str, lookups: &[&str]) -> Measurement {
+    let parse_ns = time_parse(|| S::parse(black_box(line)));
+
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    let store = black_box(S::parse(line));
+    let heap_bytes = ALLOCATED.load(Ordering::Relaxed) - before;
+
+    let start = Instant::now();
+    for i in 0..LOOKUP_ITERATIONS {
+        black_box(store.get(black_box(lookups[i % lookups.len()])));
+    }
+    let lookup_ns = per_op(start.elapsed(), LOOKUP_ITERATIONS);
+
+    Measurement {
+        parse_ns,
+        heap_bytes,
+        lookup_ns,
+    }
+}
+
+/// Times `parse` over `PARSE_ITERATIONS` runs, in nanoseconds per run.
+fn time_parse<T>(parse: impl Fn() -> T) -> f64 {
+    // Warm up so first-touch allocation costs do not skew the timings.
+    for _ in 0..1_000 {
+        black_box(parse());
+    }
+
+    let start = Instant::now();
+    for _ in 0..PARSE_ITERATIONS {
+        black_box(parse());
+    }
+    per_op(start.elapsed(), PARSE_ITERATIONS)
+}
+
+fn per_op(elapsed: Duration, ops: usize) -> f64 {
+    elapsed.as_nanos() as f64 / ops as f64
+}
+
+fn main() {
+    println!(
+        "{:>6}  {:<10}  {:>14}  {:>14}  {:>13}",
+        "fields", "store", "parse ns/rec", "heap B/rec", "lookup ns/op"
+    );
+    for count in [10, 20, 30] {
+        let line = line(&payload(count));
+        // Half hits (spread over the record), half misses.
+        let lookups: Vec<&str> = FIELD_NAMES[..count]
+            .iter()
+            .step_by(2)
+            .copied()
+            .chain(
+                ["cwd", "proctitle", "saddr", "obj", "nametype"]
+                    .into_iter()
+                    .cycle(),
+            )
+            .take(count)
+            .collect();
+
+        let rows = [
+            (
+                ParsedAuditRecord::NAME,
+                measure::<ParsedAuditRecord>(&line, &lookups),
+            ),
+            (
+                <IndexMap<String, String>>::NAME,
+                measure::<IndexMap<String, String>>(&line, &lookups),
+            ),
+            (SortedVec::NAME, measure::<SortedVec>(&line, &lookups)),
+        ];
+        for (name, m) in rows {
+            println!(
+                "{:>6}  {:<10}  {:>14.1}  {:>14}  {:>13.1}",
+                count, name, m.parse_ns, m.heap_bytes, m.lookup_ns
+            );
+        }
+    }
+
+    println!();
+    println!(
+        "{:>6}  {:>20}  {:>21}",
+        "fields", "parse_line ns/rec", "read_to_fields ns/rec"
+    );
+    for count in [10, 20, 30] {
+        let payload = payload(count);
+        let line = line(&payload);
+        let parse_line_ns =
+            time_parse(|| parse_line(black_box(&line), &ParseOptions::default()).unwrap());
+        let read_to_fields_ns =
+            time_parse(|| read_to_fields(black_box(&payload), DEFAULT_MAX_FIELD_LEN));
+        println!(
+            "{:>6}  {:>20.1}  {:>21.1}",
+            count, parse_line_ns, read_to_fields_ns
+        );
+    }
+}
@@ -0,0 +1,125 @@
+//! Compares the owning `parse_line` with the zero-copy `parse_line_borrowed`
+//! on typical SYSCALL, PATH and USER_LOGIN lines.
+//!
+//! For each line this measures:
+//! - parse time, including a read of every field so the borrowed parse pays
+//!   for splitting the payload,
+//! - heap allocations and bytes per parse (via a counting allocator).
+//!
+//! Run with `cargo bench --bench parse_borrowed`. Results are printed as a
+//! table; the borrowed parse is expected to allocate nothing.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use auditrs::core::parser::ParseOptions;
+use auditrs::core::parser::parser::{parse_line, parse_line_borrowed};
+
+/// Global allocator wrapper that counts allocations and bytes allocated.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(new_size.saturating_sub(layout.size()), Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Lines parsed per timing sample.
+const ITERATIONS: usize = 50_000;
+
+/// Sample lines, as written by auditd.
+const LINES: &[(&str, &str)] = &[
+    (
+        "SYSCALL",
+        "type=SYSCALL msg=audit(1700000000.123:4242): arch=c000003e syscall=59 success=yes \
+         exit=0 a0=55d0c0a1b2c0 a1=55d0c0a1b3f0 a2=55d0c0a1b450 a3=0 items=2 ppid=1234 \
+         pid=5678 auid=1000 uid=1000 gid=1000 euid=1000 suid=1000 fsuid=1000 egid=1000 \
+         sgid=1000 fsgid=1000 tty=pts0 ses=3 comm=\"ls\" exe=\"/usr/bin/ls\" \
+         subj=unconfined_u:unconfined_r:unconfined_t:s0-s0:c0.c1023 key=\"exec\"",
+    ),
+    (
+        "PATH",
+        "type=PATH msg=audit(1700000000.123:4242): item=0 name=\"/usr/bin/ls\" inode=1311 \
+         dev=fd:00 mode=0100755 ouid=0 ogid=0 rdev=00:00 nametype=NORMAL cap_fp=0 cap_fi=0 \
+         cap_fe=0 cap_fver=0",
+    ),
+    (
+        "USER_LOGIN",
+        "type=USER_LOGIN msg=audit(1700000001.500:4243): pid=901 uid=0 auid=1000 ses=3 \
+         msg='op=login id=1000 exe=\"/usr/sbin/sshd\" hostname=10.0.0.5 addr=10.0.0.5 \
+         terminal=ssh res=success'",
+    ),
+];
+
+/// Measurements for one parser and line.
+struct Measurement {
+    parse_ns: f64,
+    allocations: f64,
+    heap_bytes: f64,
+}
+
+/// Times `parse` over `ITERATIONS` runs and counts its allocations.
+fn measure(line: &str, parse: impl Fn(&str) -> usize) -> Measurement {
+    // Warm up so first-touch costs do not skew the timings.
+    for _ in 0..1_000 {
+        black_box(parse(black_box(line)));
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(parse(black_box(line)));
+    }
+    let elapsed = start.elapsed();
+
+    Measurement {
+        parse_ns: elapsed.as_nanos() as f64 / ITERATIONS as f64,
+        allocations: (ALLOCATIONS.load(Ordering::Relaxed) - allocations) as f64
+            / ITERATIONS as f64,
+        heap_bytes: (ALLOCATED.load(Ordering::Relaxed) - allocated) as f64 / ITERATIONS as f64,
+    }
+}
+
+fn main() {
+    println!(
+        "{:<10}  {:<8}  {:>12}  {:>12}  {:>12}",
+        "line", "parse", "ns/line", "allocs/line", "heap B/line"
+    );
+    for (name, line) in LINES {
+        let owned = measure(line, |line| {
+            let record = parse_line(line, &ParseOptions::default()).unwrap();
+            record.fields().values().map(String::len).sum()
+        });
+        let borrowed = measure(line, |line| {
+            let record = parse_line_borrowed(line).unwrap();
+            record.fields().map(|(_, value)| value.len()).sum()
+        });
+        for (parse, m) in [("owned", owned), ("borrowed", borrowed)] {
+            println!(
+                "{:<10}  {:<8}  {:>12.1}  {:>12.1}  {:>12.1}",
+                name, parse, m.parse_ns, m.allocations, m.heap_bytes
+            );
+        }
+    }
+}
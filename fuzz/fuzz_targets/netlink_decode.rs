@@ -0,0 +1,15 @@
+//! Fuzzes decoding of raw netlink frames into `RawAuditRecord`s.
+//!
+//! Run with `cargo +nightly fuzz run netlink_decode` from the repository root.
+//! The seed corpus in `fuzz/corpus/netlink_decode` is the binary form of
+//! `tests/test-source.log`.
+
+#![no_main]
+
+use auditrs::core::netlink::RawAuditRecord;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // Malformed frames must surface as errors, never panics.
+    let _ = RawAuditRecord::from_netlink_bytes(data);
+});
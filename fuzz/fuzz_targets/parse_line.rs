@@ -0,0 +1,19 @@
+//! Fuzzes the text parsers: the key–value payload parser, the netlink record
+//! parser, and the legacy log line parser.
+//!
+//! Run with `cargo +nightly fuzz run parse_line` from the repository root.
+
+#![no_main]
+
+use auditrs::core::netlink::RawAuditRecord;
+use auditrs::core::parser::ParsedAuditRecord;
+use auditrs::core::parser::parser::{DEFAULT_MAX_FIELD_LEN, read_to_fields};
+use auditrs::utils::parse_legacy_primary_line;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let line = String::from_utf8_lossy(data);
+    let _ = read_to_fields(&line, DEFAULT_MAX_FIELD_LEN);
+    let _ = ParsedAuditRecord::try_from(RawAuditRecord::new(1300, line.to_string()));
+    let _ = parse_legacy_primary_line(&line);
+});
@@ -26,7 +26,20 @@ pub fn build_cli() -> ClapCommand {
         .subcommand(
             ClapCommand::new("start")
                 .about("Start the auditrs daemon and event pipeline")
-                .arg(daemon_auditd_force_arg()),
+                .arg(daemon_auditd_force_arg())
+                .arg(
+                    Arg::new("stats")
+                        .long("stats")
+                        .value_name("SECONDS")
+                        .value_parser(clap::value_parser!(u64))
+                        .help("Periodically log a pipeline metrics summary every SECONDS (0 disables)"),
+                )
+                .arg(
+                    Arg::new("debug_correlation")
+                        .long("debug-correlation")
+                        .action(ArgAction::SetTrue)
+                        .help("Log the correlation key of every record and the trigger that flushed each event (verbose)"),
+                ),
         )
         .subcommand(ClapCommand::new("stop").about("Stop the running auditrs daemon"))
         .subcommand(
@@ -39,6 +52,7 @@ pub fn build_cli() -> ClapCommand {
         .subcommand(build_watch())
         .subcommand(build_search())
         .subcommand(build_report())
+        .subcommand(build_convert())
         .subcommand(build_config())
 }
 
@@ -146,8 +160,8 @@ Possible keys: <uid, auid, euid, gid, egid, ouid, fsuid, loginuid, suid, ses>. "
             Arg::new("format")
                 .long("format")
                 .value_name("FORMAT")
-                .value_parser(["simple", "json"])
-                .help("Output as a human-readable table or JSON"),
+                .value_parser(["simple", "json", "csv"])
+                .help("Output as a human-readable table, JSON or CSV"),
         )
         .arg(
             Arg::new("limit")
@@ -220,7 +234,7 @@ Examples:
             Arg::new("format")
                 .long("format")
                 .value_name("FORMAT")
-                .value_parser(["legacy", "simple", "json"])
+                .value_parser(["legacy", "simple", "json", "ecs"])
                 .help("Report output format"),
         )
         .arg(
@@ -244,6 +258,82 @@ Examples:
         )
 }
 
+/// Builds the `convert` subcommand.
+///
+/// The `convert` command rewrites an existing audit log into another auditrs
+/// output format, optionally checkpointing its progress so an interrupted run
+/// can be resumed. The input format is detected unless `--input-format` is
+/// given. `--output-dir` replaces `--output` with one dated file per day.
+/// `--merge` combines several inputs into one time-sorted output.
+fn build_convert() -> ClapCommand {
+    ClapCommand::new("convert")
+        .about("Convert an existing audit log into another output format")
+        .arg(
+            Arg::new("input")
+                .value_name("INPUT")
+                .required(true)
+                .num_args(1..)
+                .help("Audit log to read (e.g. /var/log/audit/audit.log); may be gzip-compressed"),
+        )
+        .arg(
+            Arg::new("merge")
+                .long("merge")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("checkpoint")
+                .help("Merge several inputs into one output sorted by time"),
+        )
+        .arg(
+            Arg::new("input_format")
+                .long("input-format")
+                .value_name("FORMAT")
+                .value_parser(["legacy", "jsonl", "capture"])
+                .help("Input format (default: detected from the first line)"),
+        )
+        .arg(
+            Arg::new("output")
+                .short('o')
+                .long("output")
+                .value_name("PATH")
+                .required_unless_present("output_dir")
+                .help("File to write the converted events to"),
+        )
+        .arg(
+            Arg::new("output_dir")
+                .long("output-dir")
+                .value_name("DIR")
+                .conflicts_with_all(["output", "checkpoint"])
+                .help("Directory to write one file per day to (created if missing)"),
+        )
+        .arg(
+            Arg::new("file_pattern")
+                .long("file-pattern")
+                .value_name("PATTERN")
+                .requires("output_dir")
+                .help("strftime pattern for the daily file names (default: audit-%Y-%m-%d.log)"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .value_parser(["legacy", "simple", "json", "ecs"])
+                .help("Output format (default: legacy)"),
+        )
+        .arg(
+            Arg::new("checkpoint")
+                .long("checkpoint")
+                .value_name("PATH")
+                .help("Record progress in PATH and resume from it if it already exists"),
+        )
+        .arg(
+            Arg::new("checkpoint_interval")
+                .long("checkpoint-interval")
+                .value_name("EVENTS")
+                .value_parser(clap::value_parser!(usize))
+                .requires("checkpoint")
+                .help("Number of events converted between checkpoint updates (default: 1000)"),
+        )
+}
+
 /// Builds the `config` subcommand.
 ///
 /// The `config` command provides nested subcommands for reading and updating
@@ -285,7 +375,7 @@ fn build_config() -> ClapCommand {
                             Arg::new("value")
                                 .value_name("FORMAT")
                                 .required(false)
-                                .value_parser(["legacy", "simple", "json"])
+                                .value_parser(["legacy", "simple", "json", "ecs"])
                                 .help("New log format; omit for interactive selection"),
                         ),
                 )
@@ -532,6 +622,140 @@ mod tests {
         assert!(sub_m.get_flag("force"));
     }
 
+    #[test]
+    fn parses_start_with_stats_interval() {
+        let cmd = build_cli();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["auditrs", "start", "--stats", "30"])
+            .expect("arguments should parse");
+
+        let ("start", sub_m) = matches.subcommand().expect("expected start subcommand") else {
+            unreachable!();
+        };
+
+        assert_eq!(sub_m.get_one::<u64>("stats"), Some(&30));
+        assert!(!sub_m.get_flag("debug_correlation"));
+    }
+
+    #[test]
+    fn parses_start_with_debug_correlation() {
+        let cmd = build_cli();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from(["auditrs", "start", "--debug-correlation"])
+            .expect("arguments should parse");
+
+        let ("start", sub_m) = matches.subcommand().expect("expected start subcommand") else {
+            unreachable!();
+        };
+
+        assert!(sub_m.get_flag("debug_correlation"));
+    }
+
+    #[test]
+    fn parses_convert_with_checkpoint() {
+        let cmd = build_cli();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from([
+                "auditrs",
+                "convert",
+                "audit.log",
+                "-o",
+                "out.slog",
+                "--format",
+                "simple",
+                "--checkpoint",
+                "convert.ckpt",
+                "--checkpoint-interval",
+                "500",
+            ])
+            .expect("arguments should parse");
+
+        let ("convert", sub_m) = matches.subcommand().expect("expected convert subcommand") else {
+            unreachable!();
+        };
+
+        assert_eq!(sub_m.get_one::<String>("input").unwrap(), "audit.log");
+        assert_eq!(
+            sub_m.get_one::<String>("checkpoint").unwrap(),
+            "convert.ckpt"
+        );
+        assert_eq!(sub_m.get_one::<usize>("checkpoint_interval"), Some(&500));
+    }
+
+    #[test]
+    fn parses_convert_with_input_format() {
+        let cmd = build_cli();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from([
+                "auditrs",
+                "convert",
+                "audit.jsonl",
+                "-o",
+                "out.log",
+                "--input-format",
+                "jsonl",
+            ])
+            .expect("arguments should parse");
+
+        let ("convert", sub_m) = matches.subcommand().expect("expected convert subcommand") else {
+            unreachable!();
+        };
+
+        assert_eq!(sub_m.get_one::<String>("input_format").unwrap(), "jsonl");
+        assert!(
+            cmd.try_get_matches_from([
+                "auditrs",
+                "convert",
+                "audit.log",
+                "-o",
+                "out.log",
+                "--input-format",
+                "xml",
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parses_convert_with_output_dir() {
+        let cmd = build_cli();
+        let matches = cmd
+            .clone()
+            .try_get_matches_from([
+                "auditrs",
+                "convert",
+                "audit.log",
+                "--output-dir",
+                "daily",
+                "--file-pattern",
+                "%Y%m%d.log",
+            ])
+            .expect("arguments should parse");
+
+        let ("convert", sub_m) = matches.subcommand().expect("expected convert subcommand") else {
+            unreachable!();
+        };
+
+        assert_eq!(sub_m.get_one::<String>("output_dir").unwrap(), "daily");
+        assert_eq!(sub_m.get_one::<String>("file_pattern").unwrap(), "%Y%m%d.log");
+        assert!(
+            cmd.try_get_matches_from([
+                "auditrs",
+                "convert",
+                "audit.log",
+                "--output-dir",
+                "daily",
+                "--checkpoint",
+                "convert.ckpt",
+            ])
+            .is_err()
+        );
+    }
+
     #[test]
     fn parses_config_get_log_directory() {
         let cmd = build_cli();
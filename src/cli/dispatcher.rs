@@ -12,6 +12,7 @@ use clap::ArgMatches;
 use std::str::FromStr;
 
 use crate::config::{GetConfigVariables, SetConfigVariables, get_config, set_config};
+use crate::daemon::WorkerOptions;
 use crate::daemon::control::{
     reboot_auditrs,
     reload_auditrs,
@@ -40,6 +41,7 @@ use crate::rules::{
     update_watch_interactive,
 };
 use crate::state::State;
+use crate::tools::convert::convert_logs;
 use crate::tools::report::generate_report;
 use crate::tools::search::search_events;
 
@@ -51,12 +53,22 @@ use crate::tools::search::search_events;
 pub fn dispatch(matches: &ArgMatches) -> Result<()> {
     let state = State::load_state()?;
     match matches.subcommand() {
-        Some(("start", sub)) => start_auditrs(false, sub.get_flag("force"))?,
+        Some(("start", sub)) => {
+            start_auditrs(
+                false,
+                sub.get_flag("force"),
+                WorkerOptions {
+                    stats_interval: sub.get_one::<u64>("stats").copied(),
+                    correlation_diagnostics: sub.get_flag("debug_correlation"),
+                },
+            )?
+        }
         Some(("stop", _)) => stop_auditrs(false)?,
         Some(("reboot", sub)) => reboot_auditrs(sub.get_flag("force"))?,
         Some(("status", _)) => status_auditrs()?,
         Some(("search", sub_m)) => handle_search(sub_m, &state)?,
         Some(("report", sub_m)) => handle_report(sub_m, &state)?,
+        Some(("convert", sub_m)) => handle_convert(sub_m)?,
         Some(("config", sub_m)) => handle_config(sub_m)?,
         Some(("filter", sub_m)) => handle_filter(sub_m, &state)?,
         Some(("watch", sub_m)) => handle_watch(sub_m, &state)?,
@@ -90,6 +102,16 @@ fn handle_report(matches: &ArgMatches, state: &State) -> Result<()> {
     generate_report(&state, matches)
 }
 
+/// Converts an existing audit log into another output format.
+///
+/// **Parameters:**
+///
+/// * `matches`: CLI argument to match a handling function. Subcommands and
+///   flags of the argument can be used for further options
+fn handle_convert(matches: &ArgMatches) -> Result<()> {
+    convert_logs(matches)
+}
+
 /// Dispatch of config handling commands. Config getters are directly addressed
 /// in this function. Config setters are further propagated to the
 /// `handle_config_set()` function.
@@ -10,13 +10,13 @@
 //! The root command is `auditrs`. A subcommand is required; running `auditrs`
 //! alone shows help. Top-level subcommands are registered in this order:
 //! `start`, `stop`, `reboot`, `status`, `filter`, `watch`, `search`, `report`,
-//! `config`.
+//! `convert`, `config`.
 //!
 //! ## Daemon control
 //!
 //! | Command | Description |
 //! |---------|-------------|
-//! | `start` | Start the auditrs daemon and event pipeline. |
+//! | `start` | Start the auditrs daemon and event pipeline. `--stats SECONDS` periodically logs a pipeline metrics summary; `--debug-correlation` logs each record's correlation key and the trigger that flushed each event. |
 //! | `stop` | Stop the running auditrs daemon. |
 //! | `reboot` | Restart the daemon. |
 //! | `status` | Show whether the daemon is running. |
@@ -50,8 +50,8 @@
 //! **Flags:**
 //!
 //! - `--since` / `--until` — RFC3339 time window.
-//! - `--format legacy|simple|json` — report body format (default: the daemon’s
-//!   configured log format when omitted).
+//! - `--format legacy|simple|json|ecs` — report body format (default: the
+//!   daemon’s configured log format when omitted).
 //! - `--summary combine|separate|exclude` — how to emit summary text (default:
 //!   `combine`).
 //! - `--no-save` — print to stdout instead of writing a file.
@@ -61,6 +61,26 @@
 //! - With no `-o`/`--output` and without `--no-save`, writes
 //!   `./reports/report_<timestamp>.<ext>`.
 //!
+//! ## `convert` — batch log conversion
+//!
+//! Rewrites an existing legacy audit log into another output format.
+//!
+//! **Positional:** `INPUT` — the legacy log to read.
+//!
+//! **Flags:**
+//!
+//! - `-o` / `--output PATH` — file to write converted events to (required
+//!   unless `--output-dir` is given).
+//! - `--output-dir DIR` — write one file per UTC day of the events to `DIR`
+//!   (created if missing) instead. Cannot be combined with `--checkpoint`.
+//! - `--file-pattern PATTERN` — strftime pattern for the daily file names
+//!   (default: `audit-%Y-%m-%d.log`).
+//! - `--format legacy|simple|json|ecs` — output format (default: `legacy`).
+//! - `--checkpoint PATH` — record progress in `PATH`; if it already exists, the
+//!   run resumes from it. Removed once the conversion completes.
+//! - `--checkpoint-interval N` — events between checkpoint updates (default:
+//!   1000).
+//!
 //! ## `config` — read and update settings
 //!
 //! Nested under `config get` and `config set`. Subcommands require a further
@@ -11,14 +11,18 @@ use crate::config::{
     CONFIG_DIR,
     CONFIG_FILE,
     DEFAULT_CONFIG,
+    DEFAULT_MAX_OPEN_SINKS,
     GetConfigVariables,
     LOG_FORMATS,
     LogFormat,
     MINIMUM_JOURNAL_SIZE,
     MINIMUM_LOG_SIZE,
     MINIMUM_PRIMARY_SIZE,
+    RecordSeparator,
     SetConfigVariables,
 };
+use crate::core::parser::parser::DEFAULT_MAX_FIELD_LEN;
+use crate::core::writer::RollupDimension;
 use crate::utils::capitalize_first_letter;
 
 /// Parse a user-facing log format string (e.g. from CLI or config file) into a
@@ -34,11 +38,52 @@ impl std::str::FromStr for LogFormat {
             "legacy" => Ok(LogFormat::Legacy),
             "simple" => Ok(LogFormat::Simple),
             "json" => Ok(LogFormat::Json),
+            "ecs" => Ok(LogFormat::Ecs),
             _ => Err(anyhow!("Unknown format: {}", s)),
         }
     }
 }
 
+/// Serde default for `AuditConfig::max_open_sinks`.
+pub(crate) fn default_max_open_sinks() -> usize {
+    DEFAULT_MAX_OPEN_SINKS
+}
+
+/// Serde default for `AuditConfig::interpret_keep_raw`.
+pub(crate) fn default_interpret_keep_raw() -> bool {
+    true
+}
+
+/// Serde default for `AuditConfig::rollup_dimensions`: record type, key and
+/// uid.
+pub(crate) fn default_rollup_dimensions() -> Vec<RollupDimension> {
+    vec![
+        RollupDimension::RecordType,
+        RollupDimension::Key,
+        RollupDimension::Uid,
+    ]
+}
+
+/// Serde default for `AuditConfig::write_retry_backoff_ms`.
+pub(crate) fn default_write_retry_backoff_ms() -> u64 {
+    100
+}
+
+/// Serde default for `AuditConfig::no_events_warning`: one minute.
+pub(crate) fn default_no_events_warning() -> u64 {
+    60
+}
+
+/// Serde default for `AuditConfig::max_field_len`.
+pub(crate) fn default_max_field_len() -> usize {
+    DEFAULT_MAX_FIELD_LEN
+}
+
+/// Serde default for `AuditConfig::tail_state_path`.
+pub(crate) fn default_tail_state_path() -> String {
+    format!("{}/tail.state", CONFIG_DIR)
+}
+
 /// Implementation of `AuditConfig`. Load, persist, and query the auditrs
 /// configuration.
 impl AuditConfig {
@@ -273,6 +318,7 @@ impl LogFormat {
             LogFormat::Legacy => "legacy".to_string(),
             LogFormat::Simple => "simple".to_string(),
             LogFormat::Json => "json".to_string(),
+            LogFormat::Ecs => "ecs".to_string(),
         }
     }
 
@@ -283,6 +329,33 @@ impl LogFormat {
             LogFormat::Legacy => "log".to_string(),
             LogFormat::Simple => "slog".to_string(), // i like this
             LogFormat::Json => "json".to_string(),
+            LogFormat::Ecs => "ndjson".to_string(),
+        }
+    }
+}
+
+/// Extension methods for `RecordSeparator` (rendering output framing).
+impl RecordSeparator {
+    /// Return the literal separator string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordSeparator::Lf => "\n",
+            RecordSeparator::Crlf => "\r\n",
+            RecordSeparator::Nul => "\0",
+        }
+    }
+
+    /// Re-frame LF-terminated output so every line ends with this separator
+    /// instead. Any newline inside a line is replaced too, so NUL-separated
+    /// output never contains a stray `\n`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `text`: Output whose lines are terminated with `\n`.
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            RecordSeparator::Lf => text.to_string(),
+            _ => text.replace('\n', self.as_str()),
         }
     }
 }
@@ -17,6 +17,11 @@ pub mod config;
 pub use config::{get_config, load_config, set_config};
 
 use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::core::correlator::{CorrelationMode, JoinRule};
+use crate::core::severity::Severity;
+use crate::core::writer::{RollupDimension, WriteDestination};
 
 /// The minimum log size for the auditrs daemon.
 pub const MINIMUM_LOG_SIZE: usize = 20000; // 1 MB
@@ -24,6 +29,9 @@ pub const MINIMUM_LOG_SIZE: usize = 20000; // 1 MB
 pub const MINIMUM_JOURNAL_SIZE: usize = 16; // 16 logs
 /// The minimum primary size for the auditrs daemon.
 pub const MINIMUM_PRIMARY_SIZE: usize = 8388608; // 8 MB
+/// The default cap on simultaneously open sink files (see
+/// `AuditConfig::max_open_sinks`).
+pub const DEFAULT_MAX_OPEN_SINKS: usize = 64;
 /// The configuration directory for the auditrs daemon.
 pub const CONFIG_DIR: &str = "/etc/auditrs";
 /// The configuration file for the auditrs daemon.
@@ -33,7 +41,7 @@ pub const RULES_FILE: &str = "/etc/auditrs/rules.toml";
 /// The file extensions that can be used for importing and dumping filters.
 pub const FILTER_FILE_EXTENSIONS: &[&str] = &["toml", "ars"];
 /// The log formats for the auditrs output logs.
-pub const LOG_FORMATS: &[&str] = &["Legacy", "Simple", "Json"];
+pub const LOG_FORMATS: &[&str] = &["Legacy", "Simple", "Json", "Ecs"];
 /// The default configuration for the auditrs daemon.
 pub const DEFAULT_CONFIG: &str = r#"[meta]
 version = "0.7.3-alpha"
@@ -66,6 +74,125 @@ pub struct AuditConfig {
     pub primary_directory: String,
     /// The primary size for the auditrs daemon.
     pub primary_size: usize,
+    /// Interval, in seconds, between periodic pipeline stats summaries printed
+    /// by the daemon. `0` (the default) disables the reporter.
+    #[serde(default)]
+    pub stats_interval: u64,
+    /// Seconds after startup without a single audit record before the daemon
+    /// warns that no audit rules may be loaded. `0` disables the warning.
+    #[serde(default = "config::default_no_events_warning")]
+    pub no_events_warning: u64,
+    /// Separator written after each record/line in legacy and simple logs.
+    #[serde(default)]
+    pub record_separator: RecordSeparator,
+    /// Maximum number of sink files (primary and routed logs) the writer keeps
+    /// open at once. Least-recently-written files are closed past this cap and
+    /// reopened in append mode when next written.
+    #[serde(default = "config::default_max_open_sinks")]
+    pub max_open_sinks: usize,
+    /// Replace raw values (uids, syscall numbers, arch, mode) with readable
+    /// ones in the written logs, like `ausearch -i`. Legacy logs keep the raw
+    /// values, so they stay parseable, and add the readable ones as enriched
+    /// companion fields (`UID=root`) instead.
+    #[serde(default)]
+    pub interpret: bool,
+    /// In interpret mode, keep each replaced value under `<field>_raw`.
+    #[serde(default = "config::default_interpret_keep_raw")]
+    pub interpret_keep_raw: bool,
+    /// In interpret mode with JSON logs, write each interpreted field as
+    /// `{"raw": ..., "interpreted": ...}` instead of replacing its value.
+    #[serde(default)]
+    pub interpret_side_by_side: bool,
+    /// Per-record-type severities (auditd type name to severity) that replace
+    /// the built-in `RecordType::severity_hint` when scoring events.
+    #[serde(default)]
+    pub severity_overrides: HashMap<String, Severity>,
+    /// Additional destinations events are written to besides the active log,
+    /// e.g. `["journald"]`, or severity-filtered files such as
+    /// `[{ file = { path = "/var/log/auditrs/alerts.log", min_severity = "high"
+    /// } }]`.
+    #[serde(default)]
+    pub destinations: Vec<WriteDestination>,
+    /// Rollup mode: when non-zero, the active log receives per-group event
+    /// counts every `rollup_interval` seconds instead of the events. `0` (the
+    /// default) writes every event.
+    #[serde(default)]
+    pub rollup_interval: u64,
+    /// The event attributes rollup counts are grouped by.
+    #[serde(default = "config::default_rollup_dimensions")]
+    pub rollup_dimensions: Vec<RollupDimension>,
+    /// Whether events are written once complete (`batch`, the default) or
+    /// as soon as their `SYSCALL` record arrives, followed by amendments with
+    /// the same timestamp and serial (`streaming`). Read at daemon start.
+    #[serde(default)]
+    pub correlation_mode: CorrelationMode,
+    /// Truncate the timestamp used to group records into events to whole
+    /// milliseconds, so sources with sub-millisecond jitter between the
+    /// records of one event still group. Read at daemon start.
+    #[serde(default)]
+    pub correlation_quantize_timestamps: bool,
+    /// Time-windowed joins across events, e.g. an `execve` of curl followed
+    /// by a `connect` from the same pid; each match is written to the active
+    /// log as a `JOIN` detection.
+    #[serde(default)]
+    pub joins: Vec<JoinRule>,
+    /// Estimated bytes the correlator may buffer; beyond it the oldest
+    /// incomplete events are shed and counted in the metrics. `0` (the
+    /// default) is unbounded. Read at daemon start.
+    #[serde(default)]
+    pub memory_budget: usize,
+    /// Interleave operational warnings (drops, parse failures, truncations,
+    /// connection changes) into the active log as synthetic `AUDITRS_WARNING`
+    /// records, checked about once a second.
+    #[serde(default)]
+    pub warning_events: bool,
+    /// How enriched companion fields (from auditd `ENRICHED` input) are
+    /// written in legacy logs.
+    #[serde(default)]
+    pub enriched_format: EnrichedFormat,
+    /// Append a synthetic `type=EOE` record after each event in legacy logs
+    /// that has none, for consumers that rely on EOE for event boundaries.
+    #[serde(default)]
+    pub legacy_eoe: bool,
+    /// How many times a failed event write is retried before the event is
+    /// dead-lettered. `0` (the default) does not retry.
+    #[serde(default)]
+    pub write_retries: u32,
+    /// Wait in milliseconds before the first retry of a failed write; doubled
+    /// for each further retry.
+    #[serde(default = "config::default_write_retry_backoff_ms")]
+    pub write_retry_backoff_ms: u64,
+    /// File events are appended to (as JSON lines) when every write attempt
+    /// failed. Without it such events are dropped.
+    #[serde(default)]
+    pub dead_letter_path: Option<String>,
+    /// Longest field value the parser keeps, in bytes; longer values are
+    /// truncated and marked. Read at daemon start.
+    #[serde(default = "config::default_max_field_len")]
+    pub max_field_len: usize,
+    /// Directory of auditctl `*.rules` files (e.g. `/etc/audit/rules.d`)
+    /// loaded and applied to the kernel at daemon start. Unset (the default)
+    /// loads none.
+    #[serde(default)]
+    pub audit_rules_dir: Option<String>,
+    /// Whether a `-D` in the rules directory may flush every existing kernel
+    /// audit rule before loading. Without it the `-D` is ignored.
+    #[serde(default)]
+    pub audit_rules_delete_all: bool,
+    /// auditd-format log file (e.g. `/var/log/audit/audit.log`) the daemon
+    /// tails for records instead of listening on the kernel's audit netlink
+    /// socket. Unset (the default) uses netlink. Read at daemon start.
+    #[serde(default)]
+    pub tail_log: Option<String>,
+    /// Where the read position in `tail_log` is persisted, so a restart
+    /// resumes after the last forwarded line.
+    #[serde(default = "config::default_tail_state_path")]
+    pub tail_state_path: String,
+    /// File the pipeline metrics are written to in the Prometheus text format
+    /// every few seconds, e.g. for the node_exporter textfile collector.
+    /// Unset (the default) writes none. Read at daemon start.
+    #[serde(default)]
+    pub metrics_file: Option<String>,
 }
 
 /// An enum for the different configuration variables that can be retrieved.
@@ -113,8 +240,8 @@ pub enum SetConfigVariables {
     PrimarySize,
     /// Set the log format for the auditrs daemon.
     LogFormat {
-        /// The new log format (legacy, simple, json). If omitted, the CLI may
-        /// fall back to an interactive prompt.
+        /// The new log format (legacy, simple, json, ecs). If omitted, the CLI
+        /// may fall back to an interactive prompt.
         value: Option<String>,
     },
 }
@@ -132,4 +259,37 @@ pub enum LogFormat {
     Simple,
     /// Formats audit events as JSON objects. Produces a `.json` log file.
     Json,
+    /// Formats audit events as Elastic Common Schema documents, one per line.
+    /// Produces a `.ndjson` log file.
+    Ecs,
+}
+
+/// How legacy logs write the enriched companion fields (`AUID="root"`,
+/// `SYSCALL=execve`, ...) of records read from auditd `ENRICHED` logs.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnrichedFormat {
+    /// Write the companion fields after the raw fields, separated by `\x1d`
+    /// as auditd does, so the output stays readable by `ausearch -i`. The
+    /// default.
+    #[default]
+    Preserve,
+    /// Write the companion fields as ordinary fields, without a separator.
+    Flatten,
+}
+
+/// The separator written after each record (legacy) or line (simple) in the
+/// output logs, so downstream consumers can pick the framing they expect.
+///
+/// JSON logs are a single JSON array document and always use LF.
+#[derive(Copy, Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordSeparator {
+    /// Unix line feed (`\n`). The default.
+    #[default]
+    Lf,
+    /// Windows line ending (`\r\n`).
+    Crlf,
+    /// NUL byte (`\0`), for tools such as `xargs -0`.
+    Nul,
 }
@@ -1,62 +1,269 @@
-//! Implementation of the `Correlator` buffer: push records by (timestamp,
-//! serial) and flush expired entries as `AuditEvent`s.
+//! Implementation of the `Correlator` buffer: push records by their grouping
+//! key (by default timestamp and serial) and flush expired entries as
+//! `AuditEvent`s, or in streaming mode emit `EventUpdate`s as records arrive.
 
-use std::collections::{HashMap, hash_map::Entry};
-use std::time::{Duration, Instant, SystemTime};
+use std::collections::{BTreeSet, HashMap, hash_map::Entry};
+use std::fmt;
+use std::time::{Duration, Instant};
 
-use crate::core::correlator::{AuditEvent, Correlator};
-use crate::core::parser::ParsedAuditRecord;
+use crate::core::correlator::{
+    AuditEvent,
+    CorrelationMode,
+    CorrelationTrigger,
+    Correlator,
+    EventKey,
+    EventUpdate,
+    KeyExtractor,
+    StandardKeyExtractor,
+};
+use crate::core::parser::{ParsedAuditRecord, RecordType};
 
 /// Duration after the last record in a buffer entry before that entry is
 /// considered expired.
 const TIMEOUT: Duration = Duration::from_secs(3);
 
-/// Key for a buffer entry: (event timestamp, serial).
-type Identifier = (SystemTime, u16);
-
 impl Correlator {
     /// Construct an empty correlator buffer.
     pub fn new() -> Self {
         Self {
             event_buffer: HashMap::new(),
+            arrivals: BTreeSet::new(),
+            diagnostics: None,
+            mode: CorrelationMode::default(),
+            streamed: HashMap::new(),
+            updates: Vec::new(),
+            key_extractor: Box::new(StandardKeyExtractor::default()),
+            memory_budget: 0,
+            buffered_bytes: 0,
+            shed_records: 0,
         }
     }
 
+    /// Set when events are emitted. Switching to batch mode stops streaming
+    /// new events; groups already streamed keep receiving amendments.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `mode`: The correlation mode.
+    pub fn set_mode(&mut self, mode: CorrelationMode) {
+        self.mode = mode;
+    }
+
+    /// Set how records are grouped into events. Records already buffered keep
+    /// their groups.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `extractor`: Computes the key each pushed record is grouped by.
+    pub fn set_key_extractor(&mut self, extractor: Box<dyn KeyExtractor>) {
+        self.key_extractor = extractor;
+    }
+
+    /// Set the memory budget for buffered records, in estimated bytes. When a
+    /// pushed record takes the buffer over the budget, the groups whose first
+    /// record arrived earliest are discarded until it fits again. `0` (the
+    /// default) means no limit.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `bytes`: The budget in bytes, or `0` for none.
+    pub fn set_memory_budget(&mut self, bytes: usize) {
+        self.memory_budget = bytes;
+        self.shed_to_budget();
+    }
+
+    /// Estimated bytes currently used by the buffered records.
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+
+    /// Take the number of records shed to stay within the memory budget since
+    /// the last call.
+    pub fn take_shed_records(&mut self) -> u64 {
+        std::mem::take(&mut self.shed_records)
+    }
+
+    /// Take the streaming updates emitted since the last call, in the order
+    /// the records arrived. Always empty in batch mode.
+    pub fn drain_updates(&mut self) -> Vec<EventUpdate> {
+        std::mem::take(&mut self.updates)
+    }
+
+    /// Enable or disable correlation diagnostics. While enabled, every pushed
+    /// record is described (grouping key and whether the group was new), as is
+    /// every flushed group (with the [`CorrelationTrigger`] that completed it);
+    /// collect the lines with [`Correlator::drain_diagnostics`]. Diagnostics
+    /// only observe decisions and never change how records are grouped.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `enabled`: Whether to record diagnostics.
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = enabled.then(Vec::new);
+    }
+
+    /// Take the diagnostic lines recorded since the last call.
+    pub fn drain_diagnostics(&mut self) -> Vec<String> {
+        self.diagnostics
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     /// Add a record to the buffer. If an entry for this event exists, append
     /// the record and reset the timeout; otherwise create a new buffer
     /// entry.
     ///
+    /// In streaming mode, a `SYSCALL` record also emits the event so far as an
+    /// initial update, and a record joining an already emitted event emits an
+    /// amendment.
+    ///
     /// **Parameters:**
     ///
-    /// * `record`: The parsed audit record to correlate (grouped by its
-    ///   identifier).
+    /// * `record`: The parsed audit record to correlate (grouped by its key).
     pub fn push(&mut self, record: ParsedAuditRecord) {
-        let id = record.identifier();
+        self.push_received(record, Instant::now());
+    }
+
+    /// Like [`Correlator::push`], for a record the transport received at
+    /// `received_at`. A new group is observed from that instant, so the
+    /// latency reported by [`Correlator::flush_expired_observed`] includes the
+    /// time the record spent in the transport and parser.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record`: The parsed audit record to correlate (grouped by its key).
+    /// * `received_at`: When the transport received the record.
+    pub fn push_received(&mut self, record: ParsedAuditRecord, received_at: Instant) {
+        let id = self.key_extractor.key(&record);
         let now = Instant::now();
+        let record_type = record.record_type;
+        self.buffered_bytes += record.estimated_size();
 
-        match self.event_buffer.entry(id) {
+        let group_len = match self.event_buffer.entry(id.clone()) {
             Entry::Occupied(mut o) => {
-                let (records, last_activity) = o.get_mut();
+                let (records, last_activity, _) = o.get_mut();
                 records.push(record);
                 *last_activity = now;
+                records.len()
             }
             Entry::Vacant(v) => {
-                v.insert((vec![record], now));
+                v.insert((vec![record], now, received_at));
+                self.arrivals.insert((received_at, id.clone()));
+                1
+            }
+        };
+        self.stream_update(&id, record_type);
+
+        if let Some(diagnostics) = self.diagnostics.as_mut() {
+            let group = if group_len == 1 {
+                "new".to_string()
+            } else {
+                format!("existing({} records)", group_len)
+            };
+            diagnostics.push(format!(
+                "correlate: record type={} key={} group={}",
+                record_type.as_audit_str(),
+                id,
+                group
+            ));
+        }
+        self.shed_to_budget();
+    }
+
+    /// Discards the oldest groups (by arrival of their first record) while the
+    /// buffered records exceed the memory budget.
+    fn shed_to_budget(&mut self) {
+        while self.memory_budget > 0 && self.buffered_bytes > self.memory_budget {
+            let Some((_, oldest)) = self.arrivals.pop_first() else {
+                break;
+            };
+            let Some((records, _, _)) = self.event_buffer.remove(&oldest) else {
+                continue;
+            };
+            self.streamed.remove(&oldest);
+            self.buffered_bytes = self.buffered_bytes.saturating_sub(buffered_size(&records));
+            self.shed_records += records.len() as u64;
+            if let Some(diagnostics) = self.diagnostics.as_mut() {
+                diagnostics.push(format!(
+                    "correlate: shed key={} records={} buffered_bytes={}",
+                    oldest,
+                    records.len(),
+                    self.buffered_bytes
+                ));
             }
         }
     }
 
+    /// Queues the streaming update caused by a record of `record_type` joining
+    /// group `id`, if any: the initial update when the group's `SYSCALL`
+    /// arrives (with any records buffered before it), or an amendment with
+    /// the records not yet emitted.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `id`: The group the record joined.
+    /// * `record_type`: The type of the record.
+    fn stream_update(&mut self, id: &EventKey, record_type: RecordType) {
+        let Some((records, _, received_at)) = self.event_buffer.get(id) else {
+            return;
+        };
+        let (amendment, new_records) = match self.streamed.get_mut(id) {
+            Some(emitted) => {
+                let new_records = records[*emitted..].to_vec();
+                *emitted = records.len();
+                (true, new_records)
+            }
+            None if self.mode == CorrelationMode::Streaming
+                && record_type == RecordType::Syscall =>
+            {
+                self.streamed.insert(id.clone(), records.len());
+                (false, records.clone())
+            }
+            None => return,
+        };
+        self.updates.push(EventUpdate {
+            event_id: id.to_string(),
+            amendment,
+            event: AuditEvent {
+                timestamp: id.timestamp,
+                serial: id.serial,
+                record_count: new_records.len() as u16,
+                records: new_records,
+                amendment,
+            },
+            received_at: *received_at,
+        });
+    }
+
+    /// Number of groups currently waiting in the buffer.
+    pub fn pending_groups(&self) -> usize {
+        self.event_buffer.len()
+    }
+
     /// Remove and return all buffer entries whose timeout has elapsed. Call
     /// this periodically (e.g. from a timer task) to flush completed
-    /// events.
+    /// events. Groups already emitted as streaming updates are removed but
+    /// not returned.
     pub fn flush_expired(&mut self) -> Vec<AuditEvent> {
+        self.flush_expired_observed()
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect()
+    }
+
+    /// Like [`Correlator::flush_expired`], but pairs each event with the
+    /// instant its first record was received (see
+    /// [`Correlator::push_received`]), so callers can measure how long the
+    /// event took to emit.
+    pub fn flush_expired_observed(&mut self) -> Vec<(AuditEvent, Instant)> {
         let now = Instant::now();
         // Collect identifiers of entries that have been idle for at least TIMEOUT.
-        let expired: Vec<Identifier> = self
+        let expired: Vec<EventKey> = self
             .event_buffer
             .iter()
-            .filter(|(_, (_, last_activity))| now.duration_since(*last_activity) >= TIMEOUT)
-            .map(|(id, _)| *id)
+            .filter(|(_, (_, last_activity, _))| now.duration_since(*last_activity) >= TIMEOUT)
+            .map(|(id, _)| id.clone())
             .collect();
 
         expired
@@ -64,50 +271,137 @@ impl Correlator {
             .filter_map(|id| {
                 self.event_buffer
                     .remove(&id)
-                    .map(|(records, _)| (id, records))
+                    .map(|(records, _, observed_at)| (id, records, observed_at))
+            })
+            .inspect(|(id, _, observed_at)| {
+                self.arrivals.remove(&(*observed_at, id.clone()));
+            })
+            .inspect(|(_, records, _)| {
+                self.buffered_bytes = self.buffered_bytes.saturating_sub(buffered_size(records));
+            })
+            .inspect(|(id, records, _)| {
+                describe_flush(
+                    &mut self.diagnostics,
+                    id,
+                    records,
+                    CorrelationTrigger::Timeout,
+                );
+            })
+            .filter(|(id, _, _)| self.streamed.remove(id).is_none())
+            .map(|(id, records, observed_at)| {
+                let event = AuditEvent {
+                    timestamp: id.timestamp,
+                    serial: id.serial,
+                    record_count: records.len() as u16,
+                    records,
+                    amendment: false,
+                };
+                (event, observed_at)
             })
-            .map(|(id, records)| {
+            .collect()
+    }
+
+    /// Remove and return every buffered group, whether or not its timeout has
+    /// elapsed, sorted by key, so groups sharing a `(timestamp, serial)` (e.g.
+    /// of different hosts) always come out in the same order. Used where no
+    /// more records
+    /// will arrive, e.g. at the end of a replayed capture. Groups already
+    /// emitted as streaming updates are removed but not returned.
+    pub fn flush_all(&mut self) -> Vec<AuditEvent> {
+        self.buffered_bytes = 0;
+        self.arrivals.clear();
+        let mut groups: Vec<_> = self.event_buffer.drain().collect();
+        groups.sort_by(|(a, _), (b, _)| a.cmp(b));
+        groups
+            .into_iter()
+            .inspect(|(id, (records, _, _))| {
+                describe_flush(
+                    &mut self.diagnostics,
+                    id,
+                    records,
+                    CorrelationTrigger::EndOfInput,
+                );
+            })
+            .filter(|(id, _)| self.streamed.remove(id).is_none())
+            .map(|(id, (records, _, _))| {
                 AuditEvent {
-                    timestamp: id.0,
-                    serial: id.1,
+                    timestamp: id.timestamp,
+                    serial: id.serial,
                     record_count: records.len() as u16,
                     records,
+                    amendment: false,
                 }
             })
             .collect()
     }
 }
 
+impl fmt::Display for CorrelationTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CorrelationTrigger::Timeout => write!(f, "timeout"),
+            CorrelationTrigger::EndOfInput => write!(f, "end_of_input"),
+        }
+    }
+}
+
+/// Returns the estimated size of a group's records.
+///
+/// **Parameters:**
+///
+/// * `records`: The records of the group.
+fn buffered_size(records: &[ParsedAuditRecord]) -> usize {
+    records.iter().map(ParsedAuditRecord::estimated_size).sum()
+}
+
+/// Records a diagnostic line for a flushed group, if diagnostics are enabled.
+///
+/// **Parameters:**
+///
+/// * `diagnostics`: The correlator's diagnostic lines, if enabled.
+/// * `id`: The group's key.
+/// * `records`: The group's records.
+/// * `trigger`: What completed the group.
+fn describe_flush(
+    diagnostics: &mut Option<Vec<String>>,
+    id: &EventKey,
+    records: &[ParsedAuditRecord],
+    trigger: CorrelationTrigger,
+) {
+    if let Some(diagnostics) = diagnostics.as_mut() {
+        let eoe_seen = records.iter().any(|r| r.record_type == RecordType::Eoe);
+        diagnostics.push(format!(
+            "correlate: flush key={} records={} trigger={} eoe_seen={}",
+            id,
+            records.len(),
+            trigger,
+            eoe_seen
+        ));
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::correlator::{NodeKeyExtractor, RecordBuilder};
+    use crate::core::metrics::PipelineMetrics;
+    use std::time::SystemTime;
 
     fn create_record() -> ParsedAuditRecord {
-        let time = SystemTime::now();
-        ParsedAuditRecord {
-            fields: HashMap::<String, String>::new(),
-            record_type: crate::core::parser::RecordType::AddGroup,
-            timestamp: time,
-            serial: 1,
-        }
+        RecordBuilder::new(RecordType::AddGroup)
+            .ts(SystemTime::now())
+            .build()
     }
 
     /// If `grouped` is true, the two records will have the same serial number
     /// and be grouped into the same event.
     fn create_audit_records_for_event(grouped: bool) -> (ParsedAuditRecord, ParsedAuditRecord) {
         let time = SystemTime::now();
-        let record = ParsedAuditRecord {
-            fields: HashMap::<String, String>::new(),
-            record_type: crate::core::parser::RecordType::AddGroup,
-            timestamp: time,
-            serial: 1,
-        };
-        let record_2 = ParsedAuditRecord {
-            fields: HashMap::<String, String>::new(),
-            record_type: crate::core::parser::RecordType::Add,
-            timestamp: time,
-            serial: if grouped { 1 } else { 2 },
-        };
+        let record = RecordBuilder::new(RecordType::AddGroup).ts(time).build();
+        let record_2 = RecordBuilder::new(RecordType::Add)
+            .ts(time)
+            .serial(if grouped { 1 } else { 2 })
+            .build();
         (record, record_2)
     }
 
@@ -135,8 +429,14 @@ mod tests {
         assert!(correlator.event_buffer.len() == 1);
         // Check that the two records are stored under the same identifier
         assert!(
-            correlator.event_buffer.get(&record.identifier()).unwrap()
-                == correlator.event_buffer.get(&record_2.identifier()).unwrap()
+            correlator
+                .event_buffer
+                .get(&record.identifier().into())
+                .unwrap()
+                == correlator
+                    .event_buffer
+                    .get(&record_2.identifier().into())
+                    .unwrap()
         );
     }
 
@@ -153,11 +453,40 @@ mod tests {
         assert!(correlator.event_buffer.len() == 2);
         // Check that the two records are stored under separate identifiers
         assert!(
-            correlator.event_buffer.get(&record.identifier()).unwrap()
-                != correlator.event_buffer.get(&record_2.identifier()).unwrap()
+            correlator
+                .event_buffer
+                .get(&record.identifier().into())
+                .unwrap()
+                != correlator
+                    .event_buffer
+                    .get(&record_2.identifier().into())
+                    .unwrap()
         );
     }
 
+    #[test]
+    /// Interleaved groups are flushed whole and in order without waiting for
+    /// their timeout.
+    fn flush_all_returns_every_group_in_order() {
+        let mut correlator = Correlator::new();
+        let time = SystemTime::UNIX_EPOCH;
+        for serial in [2, 1, 2, 1] {
+            correlator.push(
+                RecordBuilder::new(RecordType::Path)
+                    .ts(time)
+                    .serial(serial)
+                    .build(),
+            );
+        }
+
+        let events = correlator.flush_all();
+        let groups: Vec<(u16, usize)> =
+            events.iter().map(|e| (e.serial, e.records.len())).collect();
+        assert_eq!(groups, [(1, 2), (2, 2)]);
+        assert_eq!(correlator.pending_groups(), 0);
+        assert_eq!(correlator.buffered_bytes(), 0);
+    }
+
     #[test]
     #[ignore] // Doesn't necessarily need to be ignored, but takes up some time
     // Flush the event buffer and check the flushed events
@@ -177,6 +506,293 @@ mod tests {
         assert!(events[0].records[1] == record_2);
     }
 
+    #[test]
+    /// Diagnostics report the grouping key of each record and the trigger
+    /// that flushed the group, which an EOE record alone is not, and stay
+    /// empty when disabled.
+    fn diagnostics_report_key_and_flush_trigger() {
+        let mut correlator = Correlator::new();
+        let (record, _) = create_audit_records_for_event(true);
+        let mut eoe = record.clone();
+        eoe.record_type = crate::core::parser::RecordType::Eoe;
+
+        correlator.push(record.clone());
+        assert!(correlator.drain_diagnostics().is_empty());
+
+        correlator.set_diagnostics(true);
+        correlator.push(eoe);
+        let lines = correlator.drain_diagnostics();
+        let key = EventKey::from(record.identifier());
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains(&format!("key={}", key)), "{}", lines[0]);
+        assert!(
+            lines[0].contains("group=existing(2 records)"),
+            "{}",
+            lines[0]
+        );
+        assert!(!lines[0].contains("trigger="), "{}", lines[0]);
+        assert!(correlator.drain_diagnostics().is_empty());
+
+        assert_eq!(correlator.flush_all().len(), 1);
+        let lines = correlator.drain_diagnostics();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0].contains("trigger=end_of_input eoe_seen=true"),
+            "{}",
+            lines[0]
+        );
+    }
+
+    #[test]
+    /// In streaming mode a SYSCALL record emits the event at once and a later
+    /// record of the same event follows as an amendment with the same ID.
+    fn streaming_emits_initial_event_and_amendment() {
+        let mut correlator = Correlator::new();
+        correlator.set_mode(CorrelationMode::Streaming);
+        let syscall = RecordBuilder::syscall()
+            .ts_secs(1_700_000_000)
+            .field("syscall", "59")
+            .build();
+        let path = RecordBuilder::path()
+            .ts_secs(1_700_000_000)
+            .field("item", "0")
+            .build();
+        let other = RecordBuilder::new(RecordType::AddGroup)
+            .ts_secs(1_700_000_000)
+            .serial(9)
+            .build();
+
+        correlator.push(other);
+        correlator.push(syscall.clone());
+        let initial = correlator.drain_updates();
+        assert_eq!(initial.len(), 1);
+        assert!(!initial[0].amendment);
+        assert!(!initial[0].event.amendment);
+        assert_eq!(initial[0].event.records, std::slice::from_ref(&syscall));
+
+        correlator.push(path.clone());
+        let amendment = correlator.drain_updates();
+        assert_eq!(amendment.len(), 1);
+        assert!(amendment[0].amendment);
+        assert!(amendment[0].event.amendment);
+        assert_eq!(amendment[0].event_id, initial[0].event_id);
+        assert_eq!(
+            amendment[0].event_id,
+            EventKey::from(syscall.identifier()).to_string()
+        );
+        assert_eq!(amendment[0].event.records, [path]);
+        assert_eq!(amendment[0].event.record_count, 1);
+
+        // Only the group without a SYSCALL is left for a batch flush.
+        for (_, last_activity, _) in correlator.event_buffer.values_mut() {
+            *last_activity -= TIMEOUT;
+        }
+        let events = correlator.flush_expired();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].serial, 9);
+        assert!(correlator.streamed.is_empty());
+        assert!(correlator.drain_updates().is_empty());
+    }
+
+    #[test]
+    /// Event latency is measured from when the transport received the first
+    /// record, not from when it reached the correlator.
+    fn flushed_event_latency_starts_at_receipt() {
+        let metrics = PipelineMetrics::new();
+        let mut correlator = Correlator::new();
+        let (record, record_2) = create_audit_records_for_event(true);
+        let received_at = Instant::now() - Duration::from_secs(2);
+        correlator.push_received(record, received_at);
+        correlator.push_received(record_2, Instant::now());
+        for (_, last_activity, _) in correlator.event_buffer.values_mut() {
+            *last_activity -= TIMEOUT;
+        }
+
+        let events = correlator.flush_expired_observed();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0.record_count, 2);
+        for (_, observed_at) in events {
+            metrics.event_latency(observed_at.elapsed());
+        }
+        // Two seconds fall into the bucket up to 2.5 seconds.
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.latency_buckets, [0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+        assert!(snapshot.latency_sum_micros >= 2_000_000);
+    }
+
+    #[test]
+    /// In streaming mode, the initial update carries when the group's first
+    /// record was received, so its latency covers the wait for the `SYSCALL`.
+    fn streamed_event_latency_starts_at_receipt() {
+        let metrics = PipelineMetrics::new();
+        let mut correlator = Correlator::new();
+        correlator.set_mode(CorrelationMode::Streaming);
+        let received_at = Instant::now() - Duration::from_secs(2);
+        correlator.push_received(RecordBuilder::path().build(), received_at);
+        correlator.push_received(RecordBuilder::syscall().build(), Instant::now());
+
+        let updates = correlator.drain_updates();
+        assert_eq!(updates.len(), 1);
+        assert!(!updates[0].amendment);
+        assert_eq!(updates[0].received_at, received_at);
+        metrics.event_latency(updates[0].received_at.elapsed());
+        // Two seconds fall into the bucket up to 2.5 seconds.
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.latency_buckets, [0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    /// Exceeding the memory budget sheds the oldest group and counts its
+    /// records; flushing releases the budget again.
+    fn exceeding_memory_budget_sheds_oldest_group() {
+        let records: Vec<ParsedAuditRecord> = (1..=4)
+            .map(|serial| {
+                RecordBuilder::syscall()
+                    .serial(serial)
+                    .field("syscall", "59")
+                    .build()
+            })
+            .collect();
+        let size = records[0].estimated_size();
+
+        // The groups are shed by arrival, not by key: the first record pushed
+        // (the highest serial) arrived first.
+        let start = Instant::now();
+        let mut correlator = Correlator::new();
+        for (i, record) in records[..3].iter().rev().enumerate() {
+            correlator.push_received(record.clone(), start + Duration::from_millis(i as u64));
+        }
+        assert_eq!(correlator.buffered_bytes(), 3 * size);
+
+        correlator.set_memory_budget(3 * size);
+        assert_eq!(correlator.take_shed_records(), 0);
+        correlator.push_received(records[3].clone(), start + Duration::from_millis(3));
+        assert_eq!(correlator.pending_groups(), 3);
+        assert!(
+            !correlator
+                .event_buffer
+                .contains_key(&records[2].identifier().into())
+        );
+        assert_eq!(correlator.buffered_bytes(), 3 * size);
+        assert_eq!(correlator.take_shed_records(), 1);
+        assert_eq!(correlator.take_shed_records(), 0);
+
+        for (_, last_activity, _) in correlator.event_buffer.values_mut() {
+            *last_activity -= TIMEOUT;
+        }
+        assert_eq!(correlator.flush_expired().len(), 3);
+        assert_eq!(correlator.buffered_bytes(), 0);
+        assert!(correlator.arrivals.is_empty());
+    }
+
+    #[test]
+    /// With quantization on, records of the same serial whose timestamps
+    /// differ by sub-millisecond jitter share a group; with it off they
+    /// do not.
+    fn quantized_key_ignores_sub_millisecond_jitter() {
+        let base = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        let syscall = RecordBuilder::syscall()
+            .ts(base + Duration::from_micros(100))
+            .serial(7)
+            .build();
+        let path = RecordBuilder::path()
+            .ts(base + Duration::from_micros(850))
+            .serial(7)
+            .build();
+
+        let mut correlator = Correlator::new();
+        correlator.push(syscall.clone());
+        correlator.push(path.clone());
+        assert_eq!(correlator.pending_groups(), 2);
+
+        let mut correlator = Correlator::new();
+        correlator.set_key_extractor(Box::new(StandardKeyExtractor {
+            quantize_timestamps: true,
+        }));
+        correlator.push(syscall.clone());
+        correlator.push(path.clone());
+        assert_eq!(correlator.pending_groups(), 1);
+        assert_eq!(
+            correlator.event_buffer.get(&(base, 7).into()).unwrap().0,
+            [syscall, path]
+        );
+    }
+
+    #[test]
+    /// Keyed by node, records of two hosts sharing a timestamp and serial form
+    /// separate events; by default they form one.
+    fn node_key_keeps_hosts_apart() {
+        let record = |node: &str| {
+            RecordBuilder::syscall()
+                .ts_secs(1_700_000_000)
+                .serial(7)
+                .field("node", node)
+                .build()
+        };
+
+        let mut correlator = Correlator::new();
+        correlator.push(record("host1"));
+        correlator.push(record("host2"));
+        assert_eq!(correlator.pending_groups(), 1);
+
+        let mut correlator = Correlator::new();
+        correlator.set_key_extractor(Box::new(NodeKeyExtractor::default()));
+        correlator.push(record("host2"));
+        correlator.push(record("host1"));
+        correlator.push(record("host2"));
+        assert_eq!(correlator.pending_groups(), 2);
+        // Groups of one (timestamp, serial) come out ordered by node.
+        let events = correlator.flush_all();
+        let nodes: Vec<(Option<&str>, u16)> = events
+            .iter()
+            .map(|event| (event.records[0].get("node"), event.record_count))
+            .collect();
+        assert_eq!(nodes, [(Some("host1"), 1), (Some("host2"), 2)]);
+    }
+
+    /// Groups records by pid alone, ignoring timestamp and serial.
+    struct PidKeyExtractor;
+
+    impl KeyExtractor for PidKeyExtractor {
+        fn key(&self, record: &ParsedAuditRecord) -> EventKey {
+            EventKey {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 0,
+                discriminator: record.get("pid").map(str::to_string),
+            }
+        }
+    }
+
+    #[test]
+    /// A custom key extractor decides the grouping: records of one pid form an
+    /// event whatever their serials.
+    fn custom_key_extractor_groups_by_pid() {
+        let record = |serial: u16, pid: &str| {
+            RecordBuilder::syscall()
+                .ts_secs(1_700_000_000)
+                .serial(serial)
+                .field("pid", pid)
+                .build()
+        };
+        let mut correlator = Correlator::new();
+        correlator.set_key_extractor(Box::new(PidKeyExtractor));
+        correlator.push(record(1, "100"));
+        correlator.push(record(2, "200"));
+        correlator.push(record(3, "100"));
+        assert_eq!(correlator.pending_groups(), 2);
+
+        for (_, last_activity, _) in correlator.event_buffer.values_mut() {
+            *last_activity -= TIMEOUT;
+        }
+        let mut events = correlator.flush_expired();
+        events.sort_by_key(|event| event.record_count);
+        let serials: Vec<Vec<u16>> = events
+            .iter()
+            .map(|event| event.records.iter().map(|r| r.serial).collect())
+            .collect();
+        assert_eq!(serials, [vec![2], vec![1, 3]]);
+    }
+
     #[test]
     /// Check that the event buffer is not flushed if the timeout has not
     /// elapsed.
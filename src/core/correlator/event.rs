@@ -1,10 +1,30 @@
-//! `Display` and `Debug` formatting for `AuditEvent`.
+//! `Display` and `Debug` formatting for `AuditEvent`, `Extend` for growing an
+//! event with more records, and [`AuditEvent::primary_record`].
 
 use std::fmt;
 
 use crate::core::correlator::AuditEvent;
+use crate::core::parser::{ParsedAuditRecord, RecordType};
 use crate::utils::systemtime_to_utc_string;
 
+impl AuditEvent {
+    /// Returns the record that represents the event, e.g. for its severity,
+    /// its rollup record type and the ECS `event.action`:
+    ///
+    /// 1. the `SYSCALL` record, wherever it is in the event (auxiliary records
+    ///    such as `PATH` or `CWD` may arrive before it);
+    /// 2. otherwise the first record, which for a single-record event such as
+    ///    `USER_LOGIN` is the only one.
+    ///
+    /// Returns `None` only for an event without records.
+    pub fn primary_record(&self) -> Option<&ParsedAuditRecord> {
+        self.records
+            .iter()
+            .find(|record| record.record_type == RecordType::Syscall)
+            .or_else(|| self.records.first())
+    }
+}
+
 impl fmt::Debug for AuditEvent {
     /// Format the event for debug output (timestamp, record count, and each
     /// record).
@@ -34,16 +54,35 @@ impl fmt::Display for AuditEvent {
             self.serial
         ));
         for record in self.records.iter() {
-            output.push_str(&format!("\tRecord: {:?}\n", record));
+            output.push_str(&format!("\tRecord: {}\n", record.simple_string()));
         }
         write!(f, "{}", output)
     }
 }
 
+impl Extend<ParsedAuditRecord> for AuditEvent {
+    /// Appends records to the event (e.g. late records merged into an event
+    /// that was already emitted) and updates `record_count`, saturating at
+    /// `u16::MAX`.
+    ///
+    /// The records' `(timestamp, serial)` key is **not** validated against the
+    /// event's: `Extend` cannot report an error, and silently dropping records
+    /// would lose audit data. Callers are expected to have grouped records by
+    /// [`ParsedAuditRecord::identifier`] first, as the correlator does.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `iter`: The records to append, in order.
+    fn extend<I: IntoIterator<Item = ParsedAuditRecord>>(&mut self, iter: I) {
+        self.records.extend(iter);
+        self.record_count = u16::try_from(self.records.len()).unwrap_or(u16::MAX);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::core::parser::{ParsedAuditRecord, RecordType};
+    use crate::core::parser::RecordType;
     use std::{collections::HashMap, time::SystemTime};
 
     fn create_event() -> AuditEvent {
@@ -57,7 +96,11 @@ mod tests {
                 serial: 1,
                 record_type: RecordType::AddGroup,
                 fields: HashMap::new(),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
             }],
+            amendment: false,
         }
     }
 
@@ -81,4 +124,84 @@ mod tests {
         );
         assert_eq!(format!("{event}"), expected);
     }
+
+    #[test]
+    fn extend_builds_compound_event() {
+        let mut event = create_event();
+        let record = |record_type, key: &str, value: &str| {
+            ParsedAuditRecord {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_type,
+                fields: HashMap::from([(key.to_string(), value.to_string())]),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
+            }
+        };
+
+        event.extend(vec![
+            record(RecordType::Cwd, "cwd", "/root"),
+            record(RecordType::Path, "name", "/etc/group"),
+        ]);
+
+        assert_eq!(event.record_count, 3);
+        assert_eq!(event.records.len(), 3);
+        assert_eq!(event.records[0].record_type, RecordType::AddGroup);
+        assert_eq!(event.records[1].record_type, RecordType::Cwd);
+        assert_eq!(event.records[2].fields["name"], "/etc/group");
+        assert!(
+            event
+                .records
+                .iter()
+                .all(|r| r.identifier() == (event.timestamp, event.serial))
+        );
+    }
+
+    #[test]
+    fn primary_record_prefers_syscall_then_first() {
+        let record = |record_type| {
+            ParsedAuditRecord {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_type,
+                fields: HashMap::new(),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
+            }
+        };
+        let event = |types: &[RecordType]| {
+            AuditEvent {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_count: types.len() as u16,
+                records: types.iter().map(|t| record(*t)).collect(),
+                amendment: false,
+            }
+        };
+        let primary = |event: &AuditEvent| event.primary_record().map(|r| r.record_type);
+
+        // Simple event: its only record.
+        assert_eq!(
+            primary(&event(&[RecordType::UserLogin])),
+            Some(RecordType::UserLogin)
+        );
+        // Compound event: the SYSCALL, even when auxiliary records come first.
+        assert_eq!(
+            primary(&event(&[
+                RecordType::Cwd,
+                RecordType::Path,
+                RecordType::Syscall,
+                RecordType::Proctitle,
+            ])),
+            Some(RecordType::Syscall)
+        );
+        // USER event without a SYSCALL: the first record.
+        assert_eq!(
+            primary(&event(&[RecordType::UserAcct, RecordType::CredAcq])),
+            Some(RecordType::UserAcct)
+        );
+        assert_eq!(primary(&event(&[])), None);
+    }
 }
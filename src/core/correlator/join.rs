@@ -0,0 +1,288 @@
+//! Implementation of time-windowed joins across events.
+//!
+//! Events are matched in the order they are observed, and the window is
+//! measured between the events' own timestamps. A detection is written as a
+//! `type=JOIN` line in legacy and simple logs, such as
+//!
+//! ```text
+//! type=JOIN msg=audit(1700000003.000:57): rule="curl_connect" pid=4242 first=1700000000.123:42 then=1700000003.000:57
+//! ```
+
+use std::time::Duration;
+
+use anyhow::Result;
+
+use crate::core::correlator::{
+    AuditEvent,
+    EventJoiner,
+    JoinDetection,
+    JoinKey,
+    JoinRule,
+    JoinStep,
+};
+use crate::core::parser::RecordType;
+use crate::utils::{systemtime_to_timestamp_string, systemtime_to_utc_string};
+
+impl JoinKey {
+    /// Returns the key's field name, e.g. `pid`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JoinKey::Pid => "pid",
+            JoinKey::Ses => "ses",
+        }
+    }
+
+    /// Returns the key's value in `event`: the field from the primary record,
+    /// or from the first record that has it.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to read.
+    fn value_of<'a>(&self, event: &'a AuditEvent) -> Option<&'a String> {
+        event
+            .primary_record()
+            .and_then(|record| record.fields.get(self.as_str()))
+            .or_else(|| {
+                event
+                    .records
+                    .iter()
+                    .find_map(|record| record.fields.get(self.as_str()))
+            })
+    }
+}
+
+impl JoinStep {
+    /// Returns whether `event` has a record of the step's type and every
+    /// field value the step requires (on any of its records).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to check.
+    fn matches(&self, event: &AuditEvent) -> bool {
+        let record_type = RecordType::from_audit_str(&self.record_type);
+        event.records.iter().any(|r| r.record_type == record_type)
+            && self.fields.iter().all(|(name, value)| {
+                event
+                    .records
+                    .iter()
+                    .any(|record| record.fields.get(name) == Some(value))
+            })
+    }
+}
+
+impl EventJoiner {
+    /// Creates a joiner for `rules`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `rules`: The join rules to match.
+    pub fn new(rules: Vec<JoinRule>) -> Self {
+        Self {
+            rules,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns whether this joiner was built from `rules`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `rules`: The join rules to compare with.
+    pub fn has_rules(&self, rules: &[JoinRule]) -> bool {
+        self.rules == rules
+    }
+
+    /// Matches `event` against the rules and returns the joins it completes.
+    /// Remembered events whose window has passed at `event`'s timestamp are
+    /// forgotten first.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The next event.
+    pub fn observe(&mut self, event: &AuditEvent) -> Vec<JoinDetection> {
+        let rules = &self.rules;
+        self.pending.retain(|(rule, _, first)| {
+            event
+                .timestamp
+                .duration_since(first.timestamp)
+                .is_ok_and(|elapsed| elapsed <= Duration::from_secs(rules[*rule].window))
+                || event.timestamp < first.timestamp
+        });
+
+        let mut detections = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            let Some(key_value) = rule.key.value_of(event) else {
+                continue;
+            };
+            if rule.then.matches(event)
+                && let Some(position) = self.pending.iter().position(|(r, value, first)| {
+                    *r == index && value == key_value && first.timestamp <= event.timestamp
+                })
+            {
+                let (_, _, first) = self.pending.swap_remove(position);
+                detections.push(JoinDetection {
+                    rule: rule.name.clone(),
+                    key: rule.key,
+                    key_value: key_value.clone(),
+                    first,
+                    then: event.clone(),
+                });
+            }
+            if rule.first.matches(event) {
+                self.pending
+                    .retain(|(r, value, _)| !(*r == index && value == key_value));
+                self.pending.push((index, key_value.clone(), event.clone()));
+            }
+        }
+        detections
+    }
+}
+
+impl JoinDetection {
+    /// Formats the detection as a legacy `type=JOIN` line (with a trailing
+    /// newline), timestamped like the event that completed the join.
+    pub fn to_legacy_line(&self) -> Result<String> {
+        Ok(format!(
+            "type=JOIN msg=audit({}): rule=\"{}\" {}={} first={} then={}\n",
+            event_id(&self.then)?,
+            self.rule,
+            self.key.as_str(),
+            self.key_value,
+            event_id(&self.first)?,
+            event_id(&self.then)?
+        ))
+    }
+
+    /// Returns the detection as a JSON object with `type: "JOIN"`, the rule,
+    /// the key and both events' timestamps and serials.
+    pub fn to_json(&self) -> serde_json::Value {
+        let event = |e: &AuditEvent| {
+            serde_json::json!({
+                "timestamp": systemtime_to_utc_string(e.timestamp),
+                "serial": e.serial,
+            })
+        };
+        let mut json = serde_json::json!({
+            "type": "JOIN",
+            "rule": self.rule,
+            "first": event(&self.first),
+            "then": event(&self.then),
+        });
+        json[self.key.as_str()] = serde_json::Value::from(self.key_value.as_str());
+        json
+    }
+}
+
+/// Returns `<timestamp>:<serial>` for `event`, as in the legacy
+/// `msg=audit(...)` header.
+///
+/// **Parameters:**
+///
+/// * `event`: The event to identify.
+fn event_id(event: &AuditEvent) -> Result<String> {
+    Ok(format!(
+        "{}:{}",
+        systemtime_to_timestamp_string(event.timestamp)?,
+        event.serial
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use std::collections::HashMap;
+    use std::time::SystemTime;
+
+    fn event(serial: u16, secs: u64, records: Vec<RecordBuilder>) -> AuditEvent {
+        let timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let records: Vec<_> = records
+            .into_iter()
+            .map(|r| r.serial(serial).ts(timestamp).build())
+            .collect();
+        AuditEvent {
+            timestamp,
+            serial,
+            record_count: records.len() as u16,
+            records,
+            amendment: false,
+        }
+    }
+
+    fn curl_exec(serial: u16, secs: u64, pid: &str) -> AuditEvent {
+        event(
+            serial,
+            secs,
+            vec![
+                RecordBuilder::syscall()
+                    .field("syscall", "59")
+                    .field("pid", pid)
+                    .field("exe", "/usr/bin/curl"),
+                RecordBuilder::new(RecordType::Execve).field("a0", "curl"),
+            ],
+        )
+    }
+
+    fn connect(serial: u16, secs: u64, pid: &str) -> AuditEvent {
+        event(
+            serial,
+            secs,
+            vec![
+                RecordBuilder::syscall()
+                    .field("syscall", "42")
+                    .field("pid", pid),
+                RecordBuilder::new(RecordType::Sockaddr).field("saddr", "0200"),
+            ],
+        )
+    }
+
+    fn rule() -> JoinRule {
+        JoinRule {
+            name: "curl_connect".to_string(),
+            first: JoinStep {
+                record_type: "EXECVE".to_string(),
+                fields: HashMap::from([("exe".to_string(), "/usr/bin/curl".to_string())]),
+            },
+            then: JoinStep {
+                record_type: "SOCKADDR".to_string(),
+                fields: HashMap::from([("syscall".to_string(), "42".to_string())]),
+            },
+            window: 5,
+            key: JoinKey::Pid,
+        }
+    }
+
+    #[test]
+    fn two_step_join_fires_on_matching_events() {
+        let mut joiner = EventJoiner::new(vec![rule()]);
+
+        assert!(joiner.observe(&curl_exec(1, 100, "4242")).is_empty());
+        // Another process connecting does not complete the join.
+        assert!(joiner.observe(&connect(2, 101, "9999")).is_empty());
+
+        let detections = joiner.observe(&connect(3, 103, "4242"));
+        assert_eq!(detections.len(), 1);
+        assert_eq!(detections[0].rule, "curl_connect");
+        assert_eq!(detections[0].key_value, "4242");
+        assert_eq!(detections[0].first.serial, 1);
+        assert_eq!(detections[0].then.serial, 3);
+        assert_eq!(
+            detections[0].to_legacy_line().unwrap(),
+            "type=JOIN msg=audit(103.000:3): rule=\"curl_connect\" pid=4242 \
+             first=100.000:1 then=103.000:3\n"
+        );
+        assert_eq!(detections[0].to_json()["pid"], "4242");
+
+        // The first event is consumed by the join.
+        assert!(joiner.observe(&connect(4, 104, "4242")).is_empty());
+    }
+
+    #[test]
+    fn join_outside_the_window_does_not_fire() {
+        let mut joiner = EventJoiner::new(vec![rule()]);
+
+        assert!(joiner.observe(&curl_exec(1, 100, "4242")).is_empty());
+        assert!(joiner.observe(&connect(2, 106, "4242")).is_empty());
+        assert!(joiner.pending.is_empty());
+    }
+}
@@ -0,0 +1,64 @@
+//! Implementation of the grouping keys: [`EventKey`], the
+//! [`StandardKeyExtractor`] and the [`NodeKeyExtractor`].
+
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::core::correlator::{EventKey, KeyExtractor, NodeKeyExtractor, StandardKeyExtractor};
+use crate::core::parser::ParsedAuditRecord;
+use crate::utils::systemtime_to_timestamp_string;
+
+impl From<(SystemTime, u16)> for EventKey {
+    /// Builds the standard key from a `(timestamp, serial)` pair.
+    fn from((timestamp, serial): (SystemTime, u16)) -> Self {
+        Self {
+            timestamp,
+            serial,
+            discriminator: None,
+        }
+    }
+}
+
+impl fmt::Display for EventKey {
+    /// Renders the key as `<timestamp>:<serial>`, matching the legacy
+    /// `msg=audit(...)` header, followed by `/<discriminator>` if set.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match systemtime_to_timestamp_string(self.timestamp) {
+            Ok(timestamp) => write!(f, "{}:{}", timestamp, self.serial)?,
+            Err(_) => write!(f, "{:?}:{}", self.timestamp, self.serial)?,
+        }
+        match &self.discriminator {
+            Some(discriminator) => write!(f, "/{}", discriminator),
+            None => Ok(()),
+        }
+    }
+}
+
+impl KeyExtractor for StandardKeyExtractor {
+    /// Returns the record's identifier, with the timestamp truncated to
+    /// milliseconds when quantization is on.
+    fn key(&self, record: &ParsedAuditRecord) -> EventKey {
+        let (mut timestamp, serial) = record.identifier();
+        if self.quantize_timestamps {
+            timestamp = timestamp
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|since_epoch| {
+                    let millis = Duration::from_millis(since_epoch.as_millis() as u64);
+                    SystemTime::UNIX_EPOCH + millis
+                })
+                .unwrap_or(timestamp);
+        }
+        EventKey::from((timestamp, serial))
+    }
+}
+
+impl KeyExtractor for NodeKeyExtractor {
+    /// Returns the standard key with the record's `node` field, if any, as
+    /// the discriminator.
+    fn key(&self, record: &ParsedAuditRecord) -> EventKey {
+        EventKey {
+            discriminator: record.get("node").map(str::to_string),
+            ..self.inner.key(record)
+        }
+    }
+}
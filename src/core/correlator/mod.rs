@@ -4,11 +4,33 @@
 //! The auditd system does not guarantee that the set of records that make up an
 //! event will occur atomically; the stream may have interleaved records from
 //! different events.
+//!
+//! By default ([`CorrelationMode::Batch`]) an event is emitted once complete.
+//! In [`CorrelationMode::Streaming`] an event is emitted as soon as its
+//! `SYSCALL` record arrives and every later record of the event follows as an
+//! amendment carrying the same event ID; consumers merge updates by that ID.
+//!
+//! Beyond single events, an [`EventJoiner`] matches pairs of events across a
+//! time window (configured as `joins`), e.g. an `execve` of `/usr/bin/curl`
+//! followed within five seconds by a `connect` from the same pid, and reports
+//! each match as a [`JoinDetection`].
+//!
+//! Records are grouped by the [`EventKey`] a [`KeyExtractor`] computes for
+//! them. The [`StandardKeyExtractor`] uses `(timestamp, serial)`, and the
+//! [`NodeKeyExtractor`] adds the `node=` host, for logs that combine several
+//! machines; a custom extractor can group by other fields, e.g. also by pid.
+//!
+//! Tests build records with [`RecordBuilder`], which is also available to
+//! integration tests with the `test-util` feature.
 
 mod correlator;
 mod event;
+mod join;
+mod key;
+#[cfg(any(test, feature = "test-util"))]
+mod record_builder;
 
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
 use std::time::{Instant, SystemTime};
 
 use serde::{Deserialize, Serialize};
@@ -28,11 +50,212 @@ pub struct AuditEvent {
     pub record_count: u16,
     /// The correlated records that make up this event.
     pub records: Vec<ParsedAuditRecord>,
+    /// Whether this is a streaming amendment adding records to an event
+    /// emitted earlier with the same timestamp and serial (see
+    /// [`EventUpdate`]); `false` for complete events and initial updates.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub amendment: bool,
 }
 
 /// Buffer that groups incoming audit records by (timestamp, serial) and flushes
 /// them as `AuditEvent`s when an entry’s timeout elapses. Each time a record is
 /// added to an entry, that entry’s timeout is reset.
 pub struct Correlator {
-    pub(crate) event_buffer: HashMap<(SystemTime, u16), (Vec<ParsedAuditRecord>, Instant)>,
+    /// Buffered groups: records, last activity (drives the timeout), and when
+    /// the transport received the group's first record (drives latency
+    /// metrics).
+    pub(crate) event_buffer: HashMap<EventKey, (Vec<ParsedAuditRecord>, Instant, Instant)>,
+    /// The buffered groups ordered by when their first record was received,
+    /// so the oldest can be shed without scanning the buffer.
+    pub(crate) arrivals: BTreeSet<(Instant, EventKey)>,
+    /// Grouping decisions recorded for diagnostics; `None` when diagnostics
+    /// are disabled.
+    pub(crate) diagnostics: Option<Vec<String>>,
+    /// Whether events are emitted on completion or streamed.
+    pub(crate) mode: CorrelationMode,
+    /// In streaming mode, the number of records already emitted for each
+    /// buffered group that has been emitted.
+    pub(crate) streamed: HashMap<EventKey, usize>,
+    /// In streaming mode, updates waiting to be taken with
+    /// `Correlator::drain_updates`.
+    pub(crate) updates: Vec<EventUpdate>,
+    /// Computes the key each record is grouped by.
+    pub(crate) key_extractor: Box<dyn KeyExtractor>,
+    /// Estimated bytes the buffered records may use before the oldest groups
+    /// are shed; `0` for no limit.
+    pub(crate) memory_budget: usize,
+    /// Estimated bytes used by the buffered records (see
+    /// `ParsedAuditRecord::estimated_size`).
+    pub(crate) buffered_bytes: usize,
+    /// Records shed since the last `Correlator::take_shed_records`.
+    pub(crate) shed_records: u64,
+}
+
+/// The key records are grouped into events by. The emitted event carries the
+/// key's timestamp and serial.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EventKey {
+    /// The event timestamp.
+    pub timestamp: SystemTime,
+    /// The event serial.
+    pub serial: u16,
+    /// Further grouping beyond timestamp and serial set by a custom extractor
+    /// (e.g. a pid); `None` for the standard key.
+    pub discriminator: Option<String>,
+}
+
+/// Computes the [`EventKey`] a record is grouped by; records with equal keys
+/// form one event.
+pub trait KeyExtractor: Send {
+    /// Returns the key of `record`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record`: The record to key.
+    fn key(&self, record: &ParsedAuditRecord) -> EventKey;
+}
+
+/// The default [`KeyExtractor`]: a record's `(timestamp, serial)`, optionally
+/// with the timestamp truncated to whole milliseconds (configured as
+/// `correlation_quantize_timestamps`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StandardKeyExtractor {
+    /// Whether the timestamp is truncated to whole milliseconds (auditd's own
+    /// resolution), so records whose timestamps differ by sub-millisecond
+    /// jitter still group.
+    pub quantize_timestamps: bool,
+}
+
+/// A [`KeyExtractor`] that keys records like `inner` but with the record's
+/// `node` field as the discriminator, so events of different hosts sharing a
+/// `(timestamp, serial)` stay apart. Used when merging logs of several
+/// machines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeKeyExtractor {
+    /// Computes the timestamp and serial of the key.
+    pub inner: StandardKeyExtractor,
+}
+
+/// When the correlator emits events (configured as `correlation_mode`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CorrelationMode {
+    /// Emit each event once, after it has been idle for the correlation
+    /// timeout. The default.
+    #[default]
+    Batch,
+    /// Emit an event as soon as its `SYSCALL` record arrives and each later
+    /// record as an amendment. Events without a `SYSCALL` record are emitted
+    /// on completion as in batch mode.
+    Streaming,
+}
+
+/// An incremental event emitted in [`CorrelationMode::Streaming`].
+#[derive(Debug, Clone)]
+pub struct EventUpdate {
+    /// The event ID, `<timestamp>:<serial>` as in the legacy `msg=audit(...)`
+    /// header (see [`EventKey`]); identical for an event's initial update and
+    /// its amendments.
+    pub event_id: String,
+    /// `false` for the initial update, `true` for amendments.
+    pub amendment: bool,
+    /// The event's timestamp and serial with only the records this update
+    /// adds (`record_count` counts those records).
+    pub event: AuditEvent,
+    /// When the transport received the event's first record (see
+    /// [`Correlator::push_received`]), for the event latency metric.
+    pub received_at: Instant,
+}
+
+/// What completed an event, as reported by correlation diagnostics. An `EOE`
+/// record does not complete an event by itself; it is only noted in the
+/// flush diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorrelationTrigger {
+    /// The group was idle for the correlation timeout.
+    Timeout,
+    /// No more records will arrive, e.g. at the end of a replayed capture.
+    EndOfInput,
+}
+
+/// A two-step join across events (configured under `joins`): an event
+/// matching `first` followed, within `window` seconds, by an event matching
+/// `then` with the same `key` value.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JoinRule {
+    /// Name of the rule, written with each detection.
+    pub name: String,
+    /// The step that starts the join.
+    pub first: JoinStep,
+    /// The step that completes the join.
+    pub then: JoinStep,
+    /// Longest time in seconds between the two events.
+    pub window: u64,
+    /// The field both events must share.
+    #[serde(default)]
+    pub key: JoinKey,
+}
+
+/// One step of a [`JoinRule`]: an event containing a record of `record_type`
+/// whose records carry every field in `fields` with the given value.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JoinStep {
+    /// The auditd record type name, e.g. `EXECVE`.
+    pub record_type: String,
+    /// Field values the event must have, e.g. `{ exe = "/usr/bin/curl" }`.
+    #[serde(default)]
+    pub fields: HashMap<String, String>,
+}
+
+/// The field that ties the events of a join together.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinKey {
+    /// The process ID. The default.
+    #[default]
+    Pid,
+    /// The audit session ID.
+    Ses,
+}
+
+/// Matches events against [`JoinRule`]s, remembering events that matched a
+/// rule's first step until its window has passed.
+#[derive(Debug, Default)]
+pub struct EventJoiner {
+    /// The rules to match.
+    pub(crate) rules: Vec<JoinRule>,
+    /// Events that matched a first step: rule index, key value and the event.
+    /// Only the latest event per rule and key value is kept.
+    pub(crate) pending: Vec<(usize, String, AuditEvent)>,
+}
+
+/// A completed join: the two events that matched a [`JoinRule`].
+#[derive(Debug, Clone)]
+pub struct JoinDetection {
+    /// Name of the rule that matched.
+    pub rule: String,
+    /// The field the events share.
+    pub key: JoinKey,
+    /// The shared value of `key`.
+    pub key_value: String,
+    /// The event that matched the first step.
+    pub first: AuditEvent,
+    /// The event that matched the second step.
+    pub then: AuditEvent,
+}
+
+/// Fluent builder for [`ParsedAuditRecord`]s in tests, e.g.
+/// `RecordBuilder::syscall().serial(1).ts(t).field("uid", "1000").build()`.
+/// Records default to serial `1` at the Unix epoch without fields.
+#[cfg(any(test, feature = "test-util"))]
+#[derive(Debug, Clone)]
+pub struct RecordBuilder {
+    /// The type of the record.
+    record_type: crate::core::parser::RecordType,
+    /// The timestamp of the record.
+    timestamp: SystemTime,
+    /// The serial number of the record.
+    serial: u16,
+    /// The fields of the record.
+    fields: HashMap<String, String>,
 }
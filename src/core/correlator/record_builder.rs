@@ -0,0 +1,91 @@
+//! Implementation of `RecordBuilder`, the test helper for building records.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::core::correlator::RecordBuilder;
+use crate::core::parser::{ParsedAuditRecord, RecordType};
+
+impl RecordBuilder {
+    /// Starts a record of type `record_type`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record_type`: The type of the record.
+    pub fn new(record_type: RecordType) -> Self {
+        Self {
+            record_type,
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial: 1,
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Starts a `SYSCALL` record.
+    pub fn syscall() -> Self {
+        Self::new(RecordType::Syscall)
+    }
+
+    /// Starts a `PATH` record.
+    pub fn path() -> Self {
+        Self::new(RecordType::Path)
+    }
+
+    /// Starts an `EOE` (end of event) record.
+    pub fn eoe() -> Self {
+        Self::new(RecordType::Eoe)
+    }
+
+    /// Sets the serial number.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `serial`: The serial number of the record.
+    pub fn serial(mut self, serial: u16) -> Self {
+        self.serial = serial;
+        self
+    }
+
+    /// Sets the timestamp.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `timestamp`: The timestamp of the record.
+    pub fn ts(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the timestamp to `secs` seconds after the Unix epoch.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `secs`: Seconds since the Unix epoch.
+    pub fn ts_secs(self, secs: u64) -> Self {
+        self.ts(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Sets a field, replacing any earlier value.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `key`: The field name.
+    /// * `value`: The raw field value.
+    pub fn field(mut self, key: &str, value: &str) -> Self {
+        self.fields.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Builds the record.
+    pub fn build(self) -> ParsedAuditRecord {
+        ParsedAuditRecord {
+            record_type: self.record_type,
+            timestamp: self.timestamp,
+            serial: self.serial,
+            fields: self.fields,
+            quoted: Default::default(),
+            truncated: Default::default(),
+            nested: Default::default(),
+        }
+    }
+}
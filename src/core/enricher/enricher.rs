@@ -8,19 +8,11 @@
 //! - **Syscall**: map numeric `syscall` IDs to names for the **host**
 //!   architecture (the binary’s target), using the `syscalls` crate.
 //! - **Mode**: interpret octal `mode` into file type and permission strings.
-
-#[cfg(target_arch = "arm")]
-use syscalls::arm;
-#[cfg(target_arch = "riscv32")]
-use syscalls::riscv32;
-#[cfg(target_arch = "riscv64")]
-use syscalls::riscv64;
-#[cfg(target_arch = "x86")]
-use syscalls::x86;
-#[cfg(target_arch = "x86_64")]
-use syscalls::x86_64;
+//! - **Login user**: resolve the login uid `auid` into `login_user`, kept apart
+//!   from the (effective) `uid`, which changes under `su` and `sudo`.
 
 use crate::core::{correlator::AuditEvent, parser::ParsedAuditRecord};
+use crate::utils::{escape_bytes, syscall_name};
 
 /// Runs all registered record enrichers on each record in the event.
 ///
@@ -41,8 +33,12 @@ pub fn enrich_event(mut event: AuditEvent) -> AuditEvent {
 ///
 /// Add new enrichers here to participate in the pipeline; each function may
 /// insert keys into `record.fields` when its source fields are present.
-const RECORD_ENRICHERS: &[fn(&mut ParsedAuditRecord)] =
-    &[enrich_proctitle, enrich_syscall, enrich_mode];
+const RECORD_ENRICHERS: &[fn(&mut ParsedAuditRecord)] = &[
+    enrich_proctitle,
+    enrich_syscall,
+    enrich_mode,
+    enrich_login_user,
+];
 
 /// Applies every enricher in [`RECORD_ENRICHERS`] to a single record.
 ///
@@ -58,27 +54,28 @@ fn enrich_record(record: &mut ParsedAuditRecord) {
 /// Decodes the hex-encoded `proctitle` field into `proctitle_plaintext`.
 ///
 /// Null bytes in the decoded payload are replaced with spaces; trailing
-/// whitespace is trimmed.
+/// whitespace is trimmed. Other control characters and bytes that are not valid
+/// UTF-8 are escaped with [`escape_bytes`].
 ///
 /// **Parameters:**
 ///
 /// * `record`: The record that may contain a `proctitle` field.
 fn enrich_proctitle(record: &mut ParsedAuditRecord) {
     if let Some(value) = record.fields.get("proctitle") {
-        if let Ok(bytes) = hex::decode(&*value) {
+        if let Ok(mut bytes) = hex::decode(&*value) {
+            for byte in bytes.iter_mut().filter(|b| **b == 0) {
+                *byte = b' ';
+            }
             record.fields.insert(
                 "proctitle_plaintext".to_owned(),
-                String::from_utf8_lossy(&bytes)
-                    .replace('\u{0000}', " ")
-                    .trim_end()
-                    .to_owned(),
+                escape_bytes(bytes.trim_ascii_end()),
             );
         }
     }
 }
 
 /// Maps the numeric `syscall` field to `syscall_name` for the host
-/// architecture.
+/// architecture. Non-numeric values are left alone.
 ///
 /// The lookup uses the `syscalls` crate’s per-architecture syscall tables; the
 /// meaningful mapping is for the architecture this binary was built for (not
@@ -88,27 +85,33 @@ fn enrich_proctitle(record: &mut ParsedAuditRecord) {
 ///
 /// * `record`: The record that may contain a `syscall` field.
 fn enrich_syscall(record: &mut ParsedAuditRecord) {
-    if let Some(value) = record.fields.get("syscall") {
-        let syscall_id = value.parse::<u32>().unwrap();
-
-        #[cfg(target_arch = "x86_64")]
-        let syscall_name = x86_64::Sysno::from(syscall_id).name();
-        #[cfg(target_arch = "x86")]
-        let syscall_name = x86::Sysno::from(syscall_id).name();
-        #[cfg(target_arch = "riscv64")]
-        let syscall_name = riscv64::Sysno::from(syscall_id).name();
-        #[cfg(target_arch = "riscv32")]
-        let syscall_name = riscv32::Sysno::from(syscall_id).name();
-        #[cfg(target_arch = "arm")]
-        let syscall_name = arm::Sysno::from(syscall_id).name();
+    if let Some(syscall_id) = record
+        .fields
+        .get("syscall")
+        .and_then(|value| value.parse::<u32>().ok())
+    {
+        record.fields.insert(
+            "syscall_name".to_owned(),
+            syscall_name(syscall_id).to_owned(),
+        );
+    }
+}
 
-        record
-            .fields
-            .insert("syscall_name".to_owned(), syscall_name.to_owned());
+/// Adds `login_user`, the name of the login uid `auid` (see
+/// [`ParsedAuditRecord::login_user`]). Records without a numeric `auid` are
+/// left alone.
+///
+/// **Parameters:**
+///
+/// * `record`: The record that may contain an `auid` field.
+fn enrich_login_user(record: &mut ParsedAuditRecord) {
+    if let Some(user) = record.login_user() {
+        record.fields.insert("login_user".to_owned(), user);
     }
 }
 
-/// Parses octal `mode` and adds `file_type` and `file_permissions`.
+/// Parses octal `mode` and adds `file_type` and `file_permissions`. Values
+/// that are not valid octal are left alone.
 ///
 /// `file_permissions` uses the conventional nine-character `rwxrwxrwx` form,
 /// with setuid/setgid/sticky reflected as `s`/`S`/`t`/`T` on the execute slots.
@@ -118,8 +121,11 @@ fn enrich_syscall(record: &mut ParsedAuditRecord) {
 /// * `record`: The record that may contain a `mode` field (octal string, e.g.
 ///   `"040755"`).
 fn enrich_mode(record: &mut ParsedAuditRecord) {
-    if let Some(value) = record.fields.get("mode") {
-        let mode = parse_audit_mode_octal(value).unwrap();
+    if let Some(mode) = record
+        .fields
+        .get("mode")
+        .and_then(|value| parse_audit_mode_octal(value))
+    {
         let file_type = file_type_string(mode);
         let rwx = rwx_string(mode);
         record
@@ -136,7 +142,7 @@ fn enrich_mode(record: &mut ParsedAuditRecord) {
 /// **Parameters:**
 ///
 /// * `s`: Raw mode text from the audit record.
-fn parse_audit_mode_octal(s: &str) -> Option<u32> {
+pub(crate) fn parse_audit_mode_octal(s: &str) -> Option<u32> {
     let s = s.trim();
     let s = s.strip_prefix("0o").unwrap_or(s);
     u32::from_str_radix(s, 8).ok()
@@ -147,7 +153,7 @@ fn parse_audit_mode_octal(s: &str) -> Option<u32> {
 /// **Parameters:**
 ///
 /// * `mode`: Full st_mode value (including type and permission bits).
-fn file_type_string(mode: u32) -> &'static str {
+pub(crate) fn file_type_string(mode: u32) -> &'static str {
     match mode & 0o170000 {
         0o040000 => "dir",
         0o100000 => "file",
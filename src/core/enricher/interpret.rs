@@ -0,0 +1,377 @@
+//! Interpretation of raw audit values, matching `ausearch -i`.
+//!
+//! Where enrichment adds derived fields next to the raw ones, interpretation
+//! replaces the raw values themselves so output reads like `ausearch -i`:
+//!
+//! - **Ids**: `uid`, `auid`, `euid`, ... become user names and `gid`, `egid`,
+//!   ... group names; the unset id `4294967295` becomes `unset`.
+//! - **Syscall**: the numeric `syscall` becomes its name, when the record's
+//!   `arch` is the host architecture.
+//! - **Arch**: the audit arch constant (e.g. `c000003e`) becomes its name.
+//! - **Mode**: octal `mode` becomes `<type>,[suid,][sgid,][sticky,]<perms>`.
+//!
+//! Legacy logs must stay parseable as auditd logs, so there the raw values are
+//! kept and the interpreted ones are added as enriched companion fields
+//! (`UID=root` next to `uid=0`), like auditd's `ENRICHED` log format.
+
+use std::ffi::CStr;
+
+use crate::core::correlator::AuditEvent;
+use crate::core::enricher::enricher::{file_type_string, parse_audit_mode_octal};
+use crate::core::parser::ParsedAuditRecord;
+use crate::utils::{arch_name, is_host_arch, syscall_name};
+
+/// Fields holding a user id.
+const UID_FIELDS: &[&str] = &[
+    "uid", "auid", "euid", "suid", "fsuid", "ouid", "iuid", "loginuid",
+];
+
+/// Fields holding a group id.
+const GID_FIELDS: &[&str] = &["gid", "egid", "sgid", "fsgid", "ogid", "igid"];
+
+/// The id the kernel reports for an unset login uid / session.
+const UNSET_ID: u32 = u32::MAX;
+
+/// Largest scratch buffer a user or group lookup grows to.
+const MAX_LOOKUP_BUFFER: usize = 1024 * 1024;
+
+/// Replaces raw values in every record of the event with their interpreted
+/// form. Values that cannot be interpreted are left as they are.
+///
+/// **Parameters:**
+///
+/// * `event`: The event whose records are interpreted in place.
+/// * `keep_raw`: Whether to keep each replaced value under `<field>_raw`.
+pub fn interpret_event(mut event: AuditEvent, keep_raw: bool) -> AuditEvent {
+    for record in event.records.iter_mut() {
+        interpret_record(record, keep_raw);
+    }
+    event
+}
+
+/// Adds the interpreted form of every interpretable value in the event's
+/// records as an enriched companion field named after the field in
+/// uppercase (`UID=root` next to `uid=0`), keeping the raw values. Companion
+/// fields the records already carry (e.g. from auditd `ENRICHED` input) are
+/// left as they are.
+///
+/// **Parameters:**
+///
+/// * `event`: The event whose records gain companion fields.
+pub fn interpret_event_as_companions(mut event: AuditEvent) -> AuditEvent {
+    for record in event.records.iter_mut() {
+        for (field, interpreted) in interpreted_fields(record) {
+            record
+                .fields
+                .entry(field.to_ascii_uppercase())
+                .or_insert(interpreted);
+        }
+    }
+    event
+}
+
+/// Replaces the interpretable values of a single record.
+///
+/// **Parameters:**
+///
+/// * `record`: The record to interpret in place.
+/// * `keep_raw`: Whether to keep each replaced value under `<field>_raw`.
+fn interpret_record(record: &mut ParsedAuditRecord, keep_raw: bool) {
+    for (field, interpreted) in interpreted_fields(record) {
+        if let Some(raw) = record.fields.insert(field.clone(), interpreted)
+            && keep_raw
+        {
+            record.fields.insert(format!("{field}_raw"), raw);
+        }
+    }
+}
+
+/// Returns the interpretable fields of `record` with their interpreted values,
+/// leaving the record unchanged. Syscall numbers are only interpreted when the
+/// record's arch is the host architecture.
+///
+/// **Parameters:**
+///
+/// * `record`: The record to interpret.
+pub fn interpreted_fields(record: &ParsedAuditRecord) -> Vec<(String, String)> {
+    let host_arch = is_host_arch(record.fields.get("arch").map(String::as_str));
+    record
+        .fields
+        .iter()
+        .filter(|(field, _)| host_arch || field.as_str() != "syscall")
+        .filter_map(|(field, value)| Some((field.clone(), interpret_value(field, value)?)))
+        .collect()
+}
+
+/// Returns the interpreted form of `value`, or `None` if the field is not
+/// interpreted or the value cannot be.
+///
+/// **Parameters:**
+///
+/// * `field`: The field name, which selects the interpretation.
+/// * `value`: The raw value.
+pub fn interpret_value(field: &str, value: &str) -> Option<String> {
+    match field {
+        "syscall" => Some(syscall_name(value.parse().ok()?).to_owned()),
+        "arch" => arch_name(value).map(str::to_owned),
+        "mode" => parse_audit_mode_octal(value).map(mode_string),
+        _ if UID_FIELDS.contains(&field) => id_string(value, user_name),
+        _ if GID_FIELDS.contains(&field) => id_string(value, group_name),
+        _ => None,
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Returns the login uid (`auid`): the user who originally logged in,
+    /// which stays the same across `su` and `sudo` while `uid` changes.
+    /// `None` if the record has no numeric `auid` or it is unset
+    /// (`4294967295`, e.g. for daemons started at boot).
+    pub fn login_uid(&self) -> Option<u32> {
+        self.fields
+            .get("auid")?
+            .parse()
+            .ok()
+            .filter(|auid| *auid != UNSET_ID)
+    }
+
+    /// Returns the user name of the login uid: `unset` for the unset id and
+    /// `unknown(<id>)` for an id without a user, like `ausearch -i`. `None`
+    /// if the record has no numeric `auid`.
+    pub fn login_user(&self) -> Option<String> {
+        id_string(self.fields.get("auid")?, user_name)
+    }
+}
+
+/// Resolves a numeric id with `lookup`, falling back to `unknown(<id>)` like
+/// `ausearch -i` does.
+///
+/// **Parameters:**
+///
+/// * `value`: The raw id.
+/// * `lookup`: Resolves an id to a name.
+fn id_string(value: &str, lookup: fn(u32) -> Option<String>) -> Option<String> {
+    let id = value.parse::<u32>().ok()?;
+    if id == UNSET_ID {
+        return Some("unset".to_owned());
+    }
+    Some(lookup(id).unwrap_or_else(|| format!("unknown({id})")))
+}
+
+/// Formats a mode as `ausearch -i` does, e.g. `dir,sticky,777` or `file,644`.
+///
+/// **Parameters:**
+///
+/// * `mode`: Full st_mode value.
+fn mode_string(mode: u32) -> String {
+    let mut out = format!("{},", file_type_string(mode));
+    for (bit, name) in [(0o4000, "suid"), (0o2000, "sgid"), (0o1000, "sticky")] {
+        if mode & bit != 0 {
+            out.push_str(name);
+            out.push(',');
+        }
+    }
+    out.push_str(&format!("{:03o}", mode & 0o777));
+    out
+}
+
+/// Looks up a user name in the system user database.
+///
+/// **Parameters:**
+///
+/// * `uid`: The user id.
+fn user_name(uid: u32) -> Option<String> {
+    with_lookup_buffer(|buf| {
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        let rc =
+            unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc != 0 {
+            return Err(rc);
+        }
+        Ok((!result.is_null()).then(|| {
+            unsafe { CStr::from_ptr(pwd.pw_name) }
+                .to_string_lossy()
+                .into_owned()
+        }))
+    })
+}
+
+/// Looks up a group name in the system group database.
+///
+/// **Parameters:**
+///
+/// * `gid`: The group id.
+fn group_name(gid: u32) -> Option<String> {
+    with_lookup_buffer(|buf| {
+        let mut grp: libc::group = unsafe { std::mem::zeroed() };
+        let mut result = std::ptr::null_mut();
+        let rc =
+            unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc != 0 {
+            return Err(rc);
+        }
+        Ok((!result.is_null()).then(|| {
+            unsafe { CStr::from_ptr(grp.gr_name) }
+                .to_string_lossy()
+                .into_owned()
+        }))
+    })
+}
+
+/// Runs a reentrant user or group database lookup, doubling its scratch
+/// buffer while the lookup fails with `ERANGE` (an entry with many group
+/// members or long fields), up to [`MAX_LOOKUP_BUFFER`] bytes. Returns `None`
+/// when the entry does not exist or the lookup fails otherwise.
+///
+/// **Parameters:**
+///
+/// * `lookup`: Performs the lookup with the given buffer, returning the found
+///   name or the error code.
+fn with_lookup_buffer(
+    mut lookup: impl FnMut(&mut [libc::c_char]) -> Result<Option<String>, libc::c_int>,
+) -> Option<String> {
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        match lookup(&mut buf) {
+            Ok(name) => return name,
+            Err(libc::ERANGE) if buf.len() < MAX_LOOKUP_BUFFER => buf.resize(buf.len() * 2, 0),
+            Err(_) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::RecordType;
+    use crate::utils::HOST_AUDIT_ARCH;
+    use std::{collections::HashMap, time::SystemTime};
+
+    fn syscall_event() -> AuditEvent {
+        let fields = HashMap::from([
+            ("arch".to_owned(), HOST_AUDIT_ARCH.to_owned()),
+            ("syscall".to_owned(), "59".to_owned()),
+            ("uid".to_owned(), "0".to_owned()),
+            ("auid".to_owned(), "4294967295".to_owned()),
+            ("mode".to_owned(), "0100644".to_owned()),
+            ("pid".to_owned(), "1234".to_owned()),
+        ]);
+        AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial: 1,
+            record_count: 1,
+            records: vec![ParsedAuditRecord {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_type: RecordType::Syscall,
+                fields,
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
+            }],
+            amendment: false,
+        }
+    }
+
+    #[test]
+    fn interpret_replaces_numbers_with_names() {
+        let event = interpret_event(syscall_event(), true);
+        let fields = &event.records[0].fields;
+        assert_eq!(fields["uid"], "root");
+        assert_eq!(fields["uid_raw"], "0");
+        assert_eq!(fields["auid"], "unset");
+        assert_eq!(fields["syscall"], syscall_name(59));
+        assert_eq!(fields["syscall_raw"], "59");
+        assert_ne!(fields["arch"], HOST_AUDIT_ARCH);
+        assert_eq!(fields["arch_raw"], HOST_AUDIT_ARCH);
+        assert_eq!(fields["mode"], "file,644");
+        assert_eq!(fields["pid"], "1234");
+        assert!(!fields.contains_key("pid_raw"));
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            assert_eq!(fields["arch"], "x86_64");
+            assert_eq!(fields["syscall"], "execve");
+        }
+
+        let event = interpret_event(syscall_event(), false);
+        let fields = &event.records[0].fields;
+        assert_eq!(fields["uid"], "root");
+        assert!(!fields.keys().any(|field| field.ends_with("_raw")));
+    }
+
+    #[test]
+    fn companions_keep_raw_values() {
+        let event = interpret_event_as_companions(syscall_event());
+        let fields = &event.records[0].fields;
+        assert_eq!(fields["uid"], "0");
+        assert_eq!(fields["UID"], "root");
+        assert_eq!(fields["auid"], "4294967295");
+        assert_eq!(fields["AUID"], "unset");
+        assert_eq!(fields["MODE"], "file,644");
+        assert!(!fields.contains_key("PID"));
+        assert!(!fields.keys().any(|field| field.ends_with("_raw")));
+    }
+
+    #[test]
+    fn lookup_buffer_grows_on_erange() {
+        let mut sizes = Vec::new();
+        let name = with_lookup_buffer(|buf| {
+            sizes.push(buf.len());
+            if buf.len() < 4096 {
+                Err(libc::ERANGE)
+            } else {
+                Ok(Some("big".to_owned()))
+            }
+        });
+        assert_eq!(name.as_deref(), Some("big"));
+        assert_eq!(sizes, [1024, 2048, 4096]);
+
+        let mut calls = 0;
+        let name = with_lookup_buffer(|_| {
+            calls += 1;
+            Err(libc::ERANGE)
+        });
+        assert_eq!(name, None);
+        assert_eq!(calls, 11);
+        assert_eq!(with_lookup_buffer(|_| Err(libc::EIO)), None);
+    }
+
+    #[test]
+    fn foreign_arch_syscalls_are_left_raw() {
+        let mut event = syscall_event();
+        event.records[0]
+            .fields
+            .insert("arch".to_owned(), "80000016".to_owned());
+        let event = interpret_event(event, true);
+        let fields = &event.records[0].fields;
+        assert_eq!(fields["arch"], "s390x");
+        assert_eq!(fields["syscall"], "59");
+        assert_eq!(mode_string(0o041777), "dir,sticky,777");
+    }
+
+    #[test]
+    fn login_user_is_resolved_apart_from_uid() {
+        let mut record = syscall_event().records.remove(0);
+        assert_eq!(record.login_uid(), None);
+        assert_eq!(record.login_user().as_deref(), Some("unset"));
+
+        record.fields.insert("auid".to_owned(), "1000".to_owned());
+        assert_eq!(record.login_uid(), Some(1000));
+        let expected = user_name(1000).unwrap_or_else(|| "unknown(1000)".to_owned());
+        assert_eq!(record.login_user(), Some(expected.clone()));
+
+        let event = crate::core::enricher::enrich_event(AuditEvent {
+            records: vec![record],
+            ..syscall_event()
+        });
+        let fields = &event.records[0].fields;
+        assert_eq!(fields["login_user"], expected);
+        assert_eq!(fields["uid"], "0");
+        assert_eq!(fields["auid"], "1000");
+
+        record = event.records[0].clone();
+        record.fields.remove("auid");
+        assert_eq!(record.login_user(), None);
+    }
+}
@@ -1,7 +1,15 @@
 //! Enricher module for auditrs, responsible for augmenting parsed audit records
 //! with derived fields (decoded proctitle, syscall names, file type and
-//! permissions).
+//! permissions, login user) and, in interpret mode, replacing raw values with
+//! readable ones.
 
 mod enricher;
+mod interpret;
 
 pub use enricher::enrich_event;
+pub use interpret::{
+    interpret_event,
+    interpret_event_as_companions,
+    interpret_value,
+    interpreted_fields,
+};
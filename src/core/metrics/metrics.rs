@@ -0,0 +1,504 @@
+//! Implementation of `PipelineMetrics` counters, snapshots, the stats summary
+//! line printed by the periodic reporter, and Prometheus text rendering.
+
+use anyhow::{Context, Result};
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+use crate::core::metrics::{
+    ChannelFill,
+    ChannelProbe,
+    LATENCY_BUCKETS,
+    MetricsSnapshot,
+    PipelineMetrics,
+};
+
+impl PipelineMetrics {
+    /// Construct a zeroed set of counters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a raw record was received from the kernel.
+    pub fn record_received(&self) {
+        self.records_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that a raw record was parsed successfully.
+    pub fn record_parsed(&self) {
+        self.records_parsed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that `count` correlated events were emitted.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `count`: Number of events flushed from the correlator.
+    pub fn events_emitted(&self, count: u64) {
+        self.events_emitted.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Record that a record or event was dropped somewhere in the pipeline.
+    pub fn dropped(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that an event was written to the dead-letter file because it
+    /// could not be written to its sink.
+    pub fn dead_lettered(&self) {
+        self.dead_lettered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that the parser truncated `count` oversized field values.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `count`: Number of values truncated in one record.
+    pub fn fields_truncated(&self, count: u64) {
+        self.fields_truncated.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Record that the correlator shed `count` buffered records to stay within
+    /// the memory budget.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `count`: Number of records shed.
+    pub fn records_shed(&self, count: u64) {
+        self.records_shed.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Record that `count` event copies could not be written to a file
+    /// destination.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `count`: Number of failed destination writes.
+    pub fn destination_failures(&self, count: u64) {
+        self.destination_failures
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Record that a malformed netlink payload was skipped, returning the total
+    /// number skipped so far (including this one).
+    pub fn malformed_payload(&self) -> u64 {
+        self.malformed_payloads.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Publish the correlator's current number of buffered groups.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `pending`: Number of groups currently held by the correlator.
+    pub fn set_pending_groups(&self, pending: u64) {
+        self.pending_groups.store(pending, Ordering::Relaxed);
+    }
+
+    /// Publish the transport connection state.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `connected`: `true` once events are enabled on the audit socket or the
+    ///   tailed log has been read, `false` when the listener exits or reading
+    ///   the tailed log fails.
+    pub fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    /// Record the latency of one emitted event in the latency histogram.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `latency`: Time from the transport receiving the event's first record
+    ///   to the event being emitted.
+    pub fn event_latency(&self, latency: Duration) {
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        let micros = u64::try_from(latency.as_micros()).unwrap_or(u64::MAX);
+        self.latency_sum_micros.fetch_add(micros, Ordering::Relaxed);
+    }
+
+    /// Register a pipeline channel so that snapshots sample its fill level.
+    /// Only a weak handle is kept, so the channel still closes when its last
+    /// sender is dropped.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `name`: Name of the channel in summaries, e.g. `parsed`.
+    /// * `sender`: A sender of the channel.
+    pub fn watch_channel<T: Send + 'static>(&self, name: &'static str, sender: &mpsc::Sender<T>) {
+        let sender = sender.downgrade();
+        let probe = ChannelProbe {
+            name,
+            sample: Box::new(move || {
+                let sender = sender.upgrade()?;
+                Some((
+                    sender.max_capacity() - sender.capacity(),
+                    sender.max_capacity(),
+                ))
+            }),
+        };
+        self.channels
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(probe);
+    }
+
+    /// Sample the fill level of every registered channel that is still open.
+    fn channel_fills(&self) -> Vec<ChannelFill> {
+        let channels = self.channels.lock().unwrap_or_else(|e| e.into_inner());
+        channels
+            .iter()
+            .filter_map(|probe| {
+                let (len, capacity) = (probe.sample)()?;
+                Some(ChannelFill {
+                    name: probe.name,
+                    len,
+                    capacity,
+                })
+            })
+            .collect()
+    }
+
+    /// Take a point-in-time copy of every counter.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            taken_at: Instant::now(),
+            records_received: self.records_received.load(Ordering::Relaxed),
+            records_parsed: self.records_parsed.load(Ordering::Relaxed),
+            events_emitted: self.events_emitted.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+            dead_lettered: self.dead_lettered.load(Ordering::Relaxed),
+            fields_truncated: self.fields_truncated.load(Ordering::Relaxed),
+            records_shed: self.records_shed.load(Ordering::Relaxed),
+            pending_groups: self.pending_groups.load(Ordering::Relaxed),
+            malformed_payloads: self.malformed_payloads.load(Ordering::Relaxed),
+            destination_failures: self.destination_failures.load(Ordering::Relaxed),
+            connected: self.connected.load(Ordering::Relaxed),
+            latency_buckets: std::array::from_fn(|i| {
+                self.latency_buckets[i].load(Ordering::Relaxed)
+            }),
+            latency_sum_micros: self.latency_sum_micros.load(Ordering::Relaxed),
+            channels: self.channel_fills(),
+        }
+    }
+}
+
+impl fmt::Debug for ChannelProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelProbe")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MetricsSnapshot {
+    /// Render a one-line summary of this snapshot, with rates computed against
+    /// the previous snapshot.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `previous`: The snapshot taken at the end of the previous reporting
+    ///   interval.
+    pub fn summary_since(&self, previous: &MetricsSnapshot) -> String {
+        let elapsed = self
+            .taken_at
+            .saturating_duration_since(previous.taken_at)
+            .as_secs_f64();
+        let rate = |now: u64, then: u64| {
+            if elapsed > 0.0 {
+                now.saturating_sub(then) as f64 / elapsed
+            } else {
+                0.0
+            }
+        };
+        let mut line = format!(
+            "stats: records/sec={:.1} events/sec={:.1} drops={} dead_lettered={} truncated={} \
+             shed={} malformed={} destination_failures={} pending_groups={} connection={}",
+            rate(self.records_parsed, previous.records_parsed),
+            rate(self.events_emitted, previous.events_emitted),
+            self.dropped,
+            self.dead_lettered,
+            self.fields_truncated,
+            self.records_shed,
+            self.malformed_payloads,
+            self.destination_failures,
+            self.pending_groups,
+            if self.connected {
+                "connected"
+            } else {
+                "disconnected"
+            },
+        );
+        for channel in &self.channels {
+            let _ = write!(
+                line,
+                " {}_queue={}/{}",
+                channel.name, channel.len, channel.capacity
+            );
+        }
+        line
+    }
+
+    /// Render this snapshot in the Prometheus text exposition format. Counters
+    /// use the `auditrs_` prefix; the latency histogram is exported as
+    /// `auditrs_event_latency_seconds` with cumulative `le` buckets.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP auditrs_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE auditrs_{} {}", name, kind);
+            let _ = writeln!(out, "auditrs_{} {}", name, value);
+        };
+        metric(
+            "records_received_total",
+            "counter",
+            "Raw records received from the kernel.",
+            self.records_received,
+        );
+        metric(
+            "records_parsed_total",
+            "counter",
+            "Records parsed successfully.",
+            self.records_parsed,
+        );
+        metric(
+            "events_emitted_total",
+            "counter",
+            "Correlated events emitted.",
+            self.events_emitted,
+        );
+        metric(
+            "dropped_total",
+            "counter",
+            "Records or events dropped.",
+            self.dropped,
+        );
+        metric(
+            "dead_lettered_total",
+            "counter",
+            "Events written to the dead-letter file after failed writes.",
+            self.dead_lettered,
+        );
+        metric(
+            "fields_truncated_total",
+            "counter",
+            "Field values truncated at the configured maximum length.",
+            self.fields_truncated,
+        );
+        metric(
+            "records_shed_total",
+            "counter",
+            "Buffered records shed to stay within the memory budget.",
+            self.records_shed,
+        );
+        metric(
+            "malformed_payloads_total",
+            "counter",
+            "Netlink payloads skipped as malformed.",
+            self.malformed_payloads,
+        );
+        metric(
+            "destination_failures_total",
+            "counter",
+            "Event copies that could not be written to a file destination.",
+            self.destination_failures,
+        );
+        metric(
+            "pending_groups",
+            "gauge",
+            "Correlator groups currently pending.",
+            self.pending_groups,
+        );
+        metric(
+            "connected",
+            "gauge",
+            "Whether the netlink transport is connected.",
+            u64::from(self.connected),
+        );
+
+        let mut channel_gauge = |name: &str, help: &str, value: fn(&ChannelFill) -> usize| {
+            let _ = writeln!(out, "# HELP auditrs_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE auditrs_{} gauge", name);
+            for channel in &self.channels {
+                let _ = writeln!(
+                    out,
+                    "auditrs_{}{{channel=\"{}\"}} {}",
+                    name,
+                    channel.name,
+                    value(channel)
+                );
+            }
+        };
+        channel_gauge(
+            "channel_queued",
+            "Messages queued in a pipeline channel.",
+            |channel| channel.len,
+        );
+        channel_gauge(
+            "channel_capacity",
+            "Capacity of a pipeline channel.",
+            |channel| channel.capacity,
+        );
+
+        let name = "auditrs_event_latency_seconds";
+        let _ = writeln!(
+            out,
+            "# HELP {} Time from an event's first record to its emission.",
+            name
+        );
+        let _ = writeln!(out, "# TYPE {} histogram", name);
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{le=\"{}\"}} {}",
+                name,
+                bound.as_secs_f64(),
+                cumulative
+            );
+        }
+        let total: u64 = self.latency_buckets.iter().sum();
+        let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, total);
+        let _ = writeln!(
+            out,
+            "{}_sum {}",
+            name,
+            self.latency_sum_micros as f64 / 1_000_000.0
+        );
+        let _ = writeln!(out, "{}_count {}", name, total);
+        out
+    }
+
+    /// Writes [`MetricsSnapshot::to_prometheus`] to `path`, replacing the file
+    /// atomically so a scraper (e.g. the node_exporter textfile collector)
+    /// never reads a partial file.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The metrics file.
+    pub fn write_prometheus_file(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, self.to_prometheus())
+            .with_context(|| format!("failed to write metrics {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to replace metrics {}", path.display()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn snapshot_reflects_counters() {
+        let metrics = PipelineMetrics::new();
+        metrics.record_received();
+        metrics.record_parsed();
+        metrics.events_emitted(2);
+        metrics.dropped();
+        metrics.dead_lettered();
+        metrics.fields_truncated(3);
+        metrics.records_shed(4);
+        metrics.set_pending_groups(5);
+        metrics.set_connected(true);
+        metrics.destination_failures(6);
+        assert_eq!(metrics.malformed_payload(), 1);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.records_received, 1);
+        assert_eq!(snapshot.records_parsed, 1);
+        assert_eq!(snapshot.events_emitted, 2);
+        assert_eq!(snapshot.dropped, 1);
+        assert_eq!(snapshot.dead_lettered, 1);
+        assert_eq!(snapshot.fields_truncated, 3);
+        assert_eq!(snapshot.records_shed, 4);
+        assert_eq!(snapshot.pending_groups, 5);
+        assert_eq!(snapshot.malformed_payloads, 1);
+        assert_eq!(snapshot.destination_failures, 6);
+        assert!(snapshot.connected);
+    }
+
+    #[test]
+    fn summary_computes_rates_over_interval() {
+        let metrics = PipelineMetrics::new();
+        let mut previous = metrics.snapshot();
+        previous.taken_at -= Duration::from_secs(2);
+        for _ in 0..10 {
+            metrics.record_parsed();
+        }
+        metrics.events_emitted(4);
+
+        let line = metrics.snapshot().summary_since(&previous);
+        assert!(line.contains("records/sec=5.0"), "{line}");
+        assert!(line.contains("events/sec=2.0"), "{line}");
+        assert!(line.contains("connection=disconnected"), "{line}");
+    }
+
+    #[tokio::test]
+    async fn snapshot_samples_channel_fill_levels() {
+        let metrics = PipelineMetrics::new();
+        let (sender, mut receiver) = mpsc::channel(10);
+        metrics.watch_channel("parsed", &sender);
+        for i in 0..7 {
+            sender.send(i).await.unwrap();
+        }
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot.channels,
+            [ChannelFill {
+                name: "parsed",
+                len: 7,
+                capacity: 10,
+            }]
+        );
+        let line = snapshot.summary_since(&snapshot);
+        assert!(line.ends_with(" parsed_queue=7/10"), "{line}");
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("auditrs_channel_queued{channel=\"parsed\"} 7\n"));
+        assert!(text.contains("auditrs_channel_capacity{channel=\"parsed\"} 10\n"));
+
+        receiver.recv().await.unwrap();
+        assert_eq!(metrics.snapshot().channels[0].len, 6);
+        // The probe does not keep the channel open.
+        drop(sender);
+        assert!(metrics.snapshot().channels.is_empty());
+        assert_eq!(receiver.recv().await, Some(1));
+    }
+
+    #[test]
+    fn event_latency_populates_expected_buckets() {
+        let metrics = PipelineMetrics::new();
+        metrics.event_latency(Duration::from_micros(200));
+        metrics.event_latency(Duration::from_millis(1));
+        metrics.event_latency(Duration::from_millis(3200));
+        metrics.event_latency(Duration::from_millis(3400));
+        metrics.event_latency(Duration::from_secs(60));
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.latency_buckets, [1, 1, 0, 0, 0, 0, 0, 2, 0, 0, 1]);
+        assert_eq!(snapshot.latency_sum_micros, 66_601_200);
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("auditrs_event_latency_seconds_bucket{le=\"0.0005\"} 1\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_bucket{le=\"2.5\"} 2\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_bucket{le=\"5\"} 4\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_bucket{le=\"30\"} 4\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_bucket{le=\"+Inf\"} 5\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_count 5\n"));
+        assert!(text.contains("auditrs_event_latency_seconds_sum 66.6012\n"));
+        assert!(text.contains("auditrs_connected 0\n"));
+    }
+}
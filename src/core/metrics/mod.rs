@@ -0,0 +1,180 @@
+//! Runtime metrics for the daemon's event pipeline.
+//!
+//! `PipelineMetrics` is a set of lock-free counters shared (via `Arc`) between
+//! the netlink transport and the worker tasks. Each stage bumps the counters it
+//! owns; readers take a point-in-time [`MetricsSnapshot`] and never block the
+//! pipeline.
+//!
+//! Snapshots are consumed by the periodic stats reporter in the daemon worker
+//! (`auditrs start --stats <SECONDS>` or `stats_interval` in the config file)
+//! and can be rendered in the Prometheus text exposition format, which the
+//! daemon writes to `metrics_file` when it is configured.
+//!
+//! Besides counters, the metrics keep a fixed-bucket histogram of event
+//! latency: the time from the transport receiving the first record of an
+//! event to the event being emitted. Correlation timeouts show up here
+//! directly.
+//!
+//! The pipeline's channels are registered with
+//! [`PipelineMetrics::watch_channel`] so that each snapshot also samples how
+//! full every channel is. A channel that stays near capacity marks the stage
+//! after it as the bottleneck.
+//!
+//! Comparing two snapshots also yields [`PipelineWarning`]s (drops, parse
+//! failures, truncations, connection changes), which the writer can interleave
+//! with the events as synthetic `AUDITRS_WARNING` records (`warning_events` in
+//! the config file).
+
+mod metrics;
+mod warning;
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Upper bounds of the event latency histogram buckets, from sub-millisecond to
+/// tens of seconds. A final overflow bucket counts anything slower.
+pub const LATENCY_BUCKETS: [Duration; 10] = [
+    Duration::from_micros(500),
+    Duration::from_millis(1),
+    Duration::from_millis(10),
+    Duration::from_millis(100),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+    Duration::from_secs(30),
+];
+
+/// Number of latency histogram buckets, including the overflow bucket.
+pub const LATENCY_BUCKET_COUNT: usize = LATENCY_BUCKETS.len() + 1;
+
+/// Shared counters describing the health and throughput of the pipeline.
+#[derive(Debug, Default)]
+pub struct PipelineMetrics {
+    /// Raw records received from the kernel.
+    pub(crate) records_received: AtomicU64,
+    /// Records successfully parsed into `ParsedAuditRecord`s.
+    pub(crate) records_parsed: AtomicU64,
+    /// Correlated events emitted by the correlator.
+    pub(crate) events_emitted: AtomicU64,
+    /// Records or events that were dropped (parse failures, closed channels,
+    /// failed writes), plus one per netlink overrun, where the kernel dropped
+    /// an unknown number of records.
+    pub(crate) dropped: AtomicU64,
+    /// Events written to the dead-letter file after every write retry failed.
+    pub(crate) dead_lettered: AtomicU64,
+    /// Field values the parser cut at the configured `max_field_len`.
+    pub(crate) fields_truncated: AtomicU64,
+    /// Buffered records the correlator discarded to stay within the
+    /// `memory_budget`.
+    pub(crate) records_shed: AtomicU64,
+    /// Number of (timestamp, serial) groups currently buffered in the
+    /// correlator.
+    pub(crate) pending_groups: AtomicU64,
+    /// Netlink payloads skipped because they were empty or malformed.
+    pub(crate) malformed_payloads: AtomicU64,
+    /// Event copies that could not be written to a file destination.
+    pub(crate) destination_failures: AtomicU64,
+    /// Whether the netlink transport currently holds an audit connection.
+    pub(crate) connected: AtomicBool,
+    /// Emitted events per latency bucket (see [`LATENCY_BUCKETS`]).
+    pub(crate) latency_buckets: [AtomicU64; LATENCY_BUCKET_COUNT],
+    /// Sum of all recorded event latencies, in microseconds.
+    pub(crate) latency_sum_micros: AtomicU64,
+    /// Pipeline channels whose fill level is sampled in snapshots, in
+    /// pipeline order.
+    pub(crate) channels: Mutex<Vec<ChannelProbe>>,
+}
+
+/// Samples the fill level of a registered pipeline channel without keeping
+/// the channel open.
+pub(crate) struct ChannelProbe {
+    /// Name of the channel in summaries and Prometheus labels.
+    pub(crate) name: &'static str,
+    /// Returns `(queued, capacity)`, or `None` once the channel is closed.
+    pub(crate) sample: Box<dyn Fn() -> Option<(usize, usize)> + Send + Sync>,
+}
+
+/// Fill level of a pipeline channel when a snapshot was taken.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelFill {
+    /// Name of the channel, e.g. `parsed`.
+    pub name: &'static str,
+    /// Messages queued in the channel.
+    pub len: usize,
+    /// Maximum number of messages the channel holds.
+    pub capacity: usize,
+}
+
+/// A point-in-time copy of [`PipelineMetrics`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    /// Moment the snapshot was taken; used to derive rates between snapshots.
+    pub taken_at: Instant,
+    /// Raw records received from the kernel.
+    pub records_received: u64,
+    /// Records successfully parsed.
+    pub records_parsed: u64,
+    /// Correlated events emitted.
+    pub events_emitted: u64,
+    /// Records or events dropped.
+    pub dropped: u64,
+    /// Events written to the dead-letter file.
+    pub dead_lettered: u64,
+    /// Field values truncated by the parser.
+    pub fields_truncated: u64,
+    /// Buffered records shed to stay within the memory budget.
+    pub records_shed: u64,
+    /// Correlator groups currently pending.
+    pub pending_groups: u64,
+    /// Netlink payloads skipped as malformed.
+    pub malformed_payloads: u64,
+    /// Event copies that could not be written to a file destination.
+    pub destination_failures: u64,
+    /// Whether the transport (netlink or file tail) is connected.
+    pub connected: bool,
+    /// Emitted events per latency bucket (not cumulative); the last entry is
+    /// the overflow bucket.
+    pub latency_buckets: [u64; LATENCY_BUCKET_COUNT],
+    /// Sum of all recorded event latencies, in microseconds.
+    pub latency_sum_micros: u64,
+    /// Fill level of each open registered channel, in pipeline order.
+    pub channels: Vec<ChannelFill>,
+}
+
+/// The kind of problem a [`PipelineWarning`] reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Records or events were dropped (parse failures, closed channels, failed
+    /// writes, netlink overruns).
+    Dropped,
+    /// Events were written to the dead-letter file.
+    DeadLettered,
+    /// Field values were truncated by the parser.
+    Truncated,
+    /// Buffered records were shed to stay within the memory budget.
+    Shed,
+    /// Malformed netlink payloads were skipped.
+    Malformed,
+    /// Event copies could not be written to a file destination.
+    DestinationFailed,
+    /// The transport lost its audit connection or can no longer read its log.
+    Disconnected,
+    /// The transport is connected again after records had already
+    /// been received.
+    Reconnected,
+}
+
+/// An operational problem noticed between two metrics snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PipelineWarning {
+    /// When the problem was noticed (the later snapshot).
+    pub timestamp: SystemTime,
+    /// What went wrong.
+    pub kind: WarningKind,
+    /// Number of records, events or values affected since the earlier
+    /// snapshot; `0` for connection changes.
+    pub count: u64,
+}
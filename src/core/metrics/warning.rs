@@ -0,0 +1,166 @@
+//! Implementation of the pipeline warnings derived from metrics snapshots.
+//!
+//! A warning is written as a `type=AUDITRS_WARNING` line in legacy and simple
+//! logs, such as
+//!
+//! ```text
+//! type=AUDITRS_WARNING msg=audit(1700000001.000:0): kind=dropped count=3
+//! ```
+//!
+//! The serial is always `0`, so the line cannot be mistaken for a kernel
+//! record.
+
+use std::time::SystemTime;
+
+use anyhow::Result;
+
+use crate::core::metrics::{MetricsSnapshot, PipelineWarning, WarningKind};
+use crate::utils::{systemtime_to_timestamp_string, systemtime_to_utc_string};
+
+impl WarningKind {
+    /// Returns the kind's name as used in output, e.g. `dropped`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarningKind::Dropped => "dropped",
+            WarningKind::DeadLettered => "dead_lettered",
+            WarningKind::Truncated => "truncated",
+            WarningKind::Shed => "shed",
+            WarningKind::Malformed => "malformed",
+            WarningKind::DestinationFailed => "destination_failed",
+            WarningKind::Disconnected => "disconnected",
+            WarningKind::Reconnected => "reconnected",
+        }
+    }
+}
+
+impl MetricsSnapshot {
+    /// Returns one warning per problem counter that grew since `previous`, and
+    /// one for a lost or restored connection. A connection coming up before
+    /// any record was received is startup, not a reconnect, and is not
+    /// reported.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `previous`: The snapshot taken at the previous check.
+    /// * `now`: The time the warnings are stamped with.
+    pub fn warnings_since(
+        &self,
+        previous: &MetricsSnapshot,
+        now: SystemTime,
+    ) -> Vec<PipelineWarning> {
+        let counters = [
+            (WarningKind::Dropped, self.dropped, previous.dropped),
+            (
+                WarningKind::DeadLettered,
+                self.dead_lettered,
+                previous.dead_lettered,
+            ),
+            (
+                WarningKind::Truncated,
+                self.fields_truncated,
+                previous.fields_truncated,
+            ),
+            (WarningKind::Shed, self.records_shed, previous.records_shed),
+            (
+                WarningKind::Malformed,
+                self.malformed_payloads,
+                previous.malformed_payloads,
+            ),
+            (
+                WarningKind::DestinationFailed,
+                self.destination_failures,
+                previous.destination_failures,
+            ),
+        ];
+        let mut warnings: Vec<PipelineWarning> = counters
+            .into_iter()
+            .filter(|(_, current, then)| current > then)
+            .map(|(kind, current, then)| {
+                PipelineWarning {
+                    timestamp: now,
+                    kind,
+                    count: current - then,
+                }
+            })
+            .collect();
+        let connection = match (previous.connected, self.connected) {
+            (true, false) => Some(WarningKind::Disconnected),
+            (false, true) if previous.records_received > 0 => Some(WarningKind::Reconnected),
+            _ => None,
+        };
+        if let Some(kind) = connection {
+            warnings.push(PipelineWarning {
+                timestamp: now,
+                kind,
+                count: 0,
+            });
+        }
+        warnings
+    }
+}
+
+impl PipelineWarning {
+    /// Formats the warning as a legacy `type=AUDITRS_WARNING` line (with a
+    /// trailing newline).
+    pub fn to_legacy_line(&self) -> Result<String> {
+        Ok(format!(
+            "type=AUDITRS_WARNING msg=audit({}:0): kind={} count={}\n",
+            systemtime_to_timestamp_string(self.timestamp)?,
+            self.kind.as_str(),
+            self.count
+        ))
+    }
+
+    /// Returns the warning as a JSON object with `type: "AUDITRS_WARNING"`,
+    /// the timestamp, the kind and the count.
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "AUDITRS_WARNING",
+            "timestamp": systemtime_to_utc_string(self.timestamp),
+            "kind": self.kind.as_str(),
+            "count": self.count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::metrics::PipelineMetrics;
+    use std::time::Duration;
+
+    #[test]
+    fn counters_and_connection_changes_become_warnings() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_001);
+        let metrics = PipelineMetrics::new();
+        metrics.set_connected(true);
+        let previous = metrics.snapshot();
+        assert!(metrics.snapshot().warnings_since(&previous, now).is_empty());
+
+        metrics.record_received();
+        metrics.dropped();
+        metrics.dropped();
+        metrics.fields_truncated(2);
+        metrics.set_connected(false);
+        let current = metrics.snapshot();
+        let warnings = current.warnings_since(&previous, now);
+        let kinds: Vec<(WarningKind, u64)> = warnings.iter().map(|w| (w.kind, w.count)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (WarningKind::Dropped, 2),
+                (WarningKind::Truncated, 2),
+                (WarningKind::Disconnected, 0),
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_legacy_line().unwrap(),
+            "type=AUDITRS_WARNING msg=audit(1700000001.000:0): kind=dropped count=2\n"
+        );
+        assert_eq!(warnings[2].to_json()["kind"], "disconnected");
+
+        metrics.set_connected(true);
+        let warnings = metrics.snapshot().warnings_since(&current, now);
+        assert_eq!(warnings[0].kind, WarningKind::Reconnected);
+    }
+}
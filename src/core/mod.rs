@@ -10,9 +10,17 @@
 //! - `enricher`: optional enrichment stages that augment events with extra
 //!   context.
 //! - `writer`: generic writer interfaces used by the daemon to persist data.
+//! - `metrics`: shared pipeline counters and snapshots used for operational
+//!   visibility.
+//! - `severity`: per-record-type severity hints and the event severity scorer.
+//! - `process_tree`: parent-child process tree reconstructed from the
+//!   `pid`/`ppid` fields of correlated events.
 
 pub mod correlator;
 pub mod enricher;
+pub mod metrics;
 pub mod netlink;
 pub mod parser;
+pub mod process_tree;
+pub mod severity;
 pub mod writer;
@@ -0,0 +1,518 @@
+//! Implementation of the file-tail transport, which forwards lines appended to
+//! an auditd-format text log and resumes from a persisted offset on restart.
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+use std::os::unix::fs::{DirEntryExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::core::metrics::PipelineMetrics;
+use crate::core::netlink::{FileTailTransport, FileTailer, RawAuditRecord, TailState};
+use crate::core::parser::{ParseOptions, parser::parse_raw_line};
+use crate::utils::escape_invalid_utf8;
+
+/// How often the tailed file is checked for new lines.
+const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Most bytes read from the tailed file per poll, so a large backlog (e.g. on
+/// first start against a big log) is forwarded in bounded batches.
+const TAIL_READ_LIMIT: u64 = 1024 * 1024;
+
+impl TailState {
+    /// Loads a stored state, or `None` if the state file does not exist yet.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The state file.
+    pub fn load(path: &Path) -> Result<Option<TailState>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("failed to read tail state {}", path.display()));
+            }
+        };
+        let state = serde_json::from_str(&content)
+            .with_context(|| format!("corrupt tail state {}", path.display()))?;
+        Ok(Some(state))
+    }
+
+    /// Persists the state, replacing the state file atomically so a crash never
+    /// leaves a partially written offset behind.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The state file.
+    pub fn store(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string(self)?)
+            .with_context(|| format!("failed to write tail state {}", tmp.display()))?;
+        fs::rename(&tmp, path)
+            .with_context(|| format!("failed to replace tail state {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl FileTailer {
+    /// Creates a tailer for `log_path`, resuming from the state stored at
+    /// `state_path` (or from the start of the file when there is none).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `log_path`: The auditd-format log file to tail.
+    /// * `state_path`: Where the read position is persisted.
+    pub fn new(log_path: impl Into<PathBuf>, state_path: impl Into<PathBuf>) -> Result<Self> {
+        let state_path = state_path.into();
+        let state = TailState::load(&state_path)?.unwrap_or_default();
+        Ok(Self {
+            log_path: log_path.into(),
+            state_path,
+            state,
+            file: None,
+            pending: false,
+        })
+    }
+
+    /// Returns the current (possibly not yet committed) read position.
+    pub fn state(&self) -> TailState {
+        self.state
+    }
+
+    /// Whether the last [`FileTailer::poll`] stopped at the read limit and the
+    /// file has more lines to read right away.
+    pub fn has_pending(&self) -> bool {
+        self.pending
+    }
+
+    /// Reads the complete lines appended since the current position, at most
+    /// `TAIL_READ_LIMIT` bytes per call, and advances it in memory; call
+    /// [`FileTailer::commit`] once the records have been forwarded.
+    ///
+    /// If the log was rotated (the path has a new inode) the old file is read
+    /// to its end first, then reading restarts at offset `0` of the new file;
+    /// if the file shrank (truncation) it restarts at `0` of the same file. A
+    /// partially written trailing line is left for the next poll, and a line
+    /// longer than the read limit is skipped. Lines that are not audit records
+    /// are skipped; bytes that are not valid UTF-8 are escaped as `\xHH`. A
+    /// missing file yields no records.
+    pub fn poll(&mut self) -> Result<Vec<RawAuditRecord>> {
+        self.pending = false;
+        if self.file.is_none() {
+            self.file = self.open_resumed()?;
+        }
+        loop {
+            let Some(file) = self.file.as_mut() else {
+                return Ok(Vec::new());
+            };
+            if file.metadata()?.len() < self.state.offset {
+                self.state.offset = 0;
+            }
+            file.seek(SeekFrom::Start(self.state.offset))?;
+            let mut buf = Vec::new();
+            file.by_ref().take(TAIL_READ_LIMIT).read_to_end(&mut buf)?;
+            let full = buf.len() as u64 == TAIL_READ_LIMIT;
+
+            if let Some(end) = buf.iter().rposition(|b| *b == b'\n') {
+                let options = ParseOptions::lenient();
+                let records = escape_invalid_utf8(&buf[..end])
+                    .lines()
+                    .filter_map(|line| parse_raw_line(line, &options).ok())
+                    .collect();
+                self.state.offset += end as u64 + 1;
+                self.pending = full;
+                return Ok(records);
+            }
+            if full {
+                // The rest of the line starts mid-payload, which never parses
+                // as an audit record, so it is skipped on a later poll.
+                eprintln!(
+                    "Skipping a line of more than {} bytes in {}",
+                    TAIL_READ_LIMIT,
+                    self.log_path.display()
+                );
+                self.state.offset += buf.len() as u64;
+                self.pending = true;
+                return Ok(Vec::new());
+            }
+            // The open file is drained; switch to the log path if it was
+            // rotated in the meantime.
+            match fs::metadata(&self.log_path) {
+                Ok(metadata) if metadata.ino() != self.state.inode => {
+                    let Some(file) = self.open_log()? else {
+                        return Ok(Vec::new());
+                    };
+                    self.state = TailState {
+                        inode: file.metadata()?.ino(),
+                        offset: 0,
+                    };
+                    self.file = Some(file);
+                }
+                Ok(_) => return Ok(Vec::new()),
+                Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("failed to stat {}", self.log_path.display()));
+                }
+            }
+        }
+    }
+
+    /// Persists the current read position.
+    pub fn commit(&self) -> Result<()> {
+        self.state.store(&self.state_path)
+    }
+
+    /// Opens the file the stored state refers to: the log itself, or, if it
+    /// was rotated while the tailer was not running, the rotated file next to
+    /// it with the stored inode. Falls back to the start of the current log
+    /// when the old file is gone.
+    fn open_resumed(&mut self) -> Result<Option<File>> {
+        let Some(file) = self.open_log()? else {
+            return Ok(None);
+        };
+        let inode = file.metadata()?.ino();
+        if inode == self.state.inode {
+            return Ok(Some(file));
+        }
+        if self.state.inode != 0 {
+            let dir = match self.log_path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir,
+                _ => Path::new("."),
+            };
+            for entry in fs::read_dir(dir)? {
+                let entry = entry?;
+                if entry.ino() == self.state.inode {
+                    let rotated = File::open(entry.path())
+                        .with_context(|| format!("failed to open {}", entry.path().display()))?;
+                    return Ok(Some(rotated));
+                }
+            }
+        }
+        self.state = TailState { inode, offset: 0 };
+        Ok(Some(file))
+    }
+
+    /// Opens the log path, or returns `None` if it does not exist.
+    fn open_log(&self) -> Result<Option<File>> {
+        match File::open(&self.log_path) {
+            Ok(file) => Ok(Some(file)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("failed to open {}", self.log_path.display())),
+        }
+    }
+}
+
+impl FileTailTransport {
+    /// Creates a new `FileTailTransport` and spawns a task that polls the log
+    /// for new lines.
+    ///
+    /// The read position is committed only after a batch has been handed to
+    /// the channel, so lines read before a crash or shutdown are forwarded
+    /// again on restart (at-least-once delivery).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `log_path`: The auditd-format log file to tail.
+    /// * `state_path`: Where the read position is persisted.
+    /// * `metrics`: Shared pipeline counters; forwarded lines are counted as
+    ///   received records, and the transport counts as connected while the log
+    ///   can be read.
+    pub fn new(
+        log_path: impl Into<PathBuf>,
+        state_path: impl Into<PathBuf>,
+        metrics: Arc<PipelineMetrics>,
+    ) -> Result<Self> {
+        let mut tailer = FileTailer::new(log_path, state_path)?;
+        let (sender, receiver) = mpsc::channel(1000);
+        metrics.watch_channel("raw", &sender);
+        tokio::spawn(async move {
+            file_tail_task(&mut tailer, sender, &metrics).await;
+            metrics.set_connected(false);
+        });
+        Ok(Self { receiver })
+    }
+
+    /// Converts the `FileTailTransport` into a receiver for the raw audit
+    /// records.
+    pub fn into_receiver(self) -> mpsc::Receiver<RawAuditRecord> {
+        self.receiver
+    }
+}
+
+/// Polls `tailer` and forwards new records until the channel closes.
+///
+/// A failed read or commit (e.g. a permission or stat error while the log is
+/// rotated) does not stop the task: the transport is marked disconnected and
+/// the log is polled again on the next tick. Only the first error of a run of
+/// failures is logged, and so is the recovery.
+///
+/// **Parameters:**
+///
+/// * `tailer`: The tailer owning the read position.
+/// * `sender`: The MPSC channel to forward the raw audit records to.
+/// * `metrics`: Shared pipeline counters updated as records are forwarded and
+///   as reading the log fails or recovers.
+async fn file_tail_task(
+    tailer: &mut FileTailer,
+    sender: mpsc::Sender<RawAuditRecord>,
+    metrics: &PipelineMetrics,
+) {
+    let mut ticker = interval(TAIL_POLL_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut committed = tailer.state();
+    let mut failing = false;
+    loop {
+        if !tailer.has_pending() {
+            ticker.tick().await;
+        }
+        match forward_new_records(tailer, &sender, metrics, &mut committed).await {
+            Ok(true) => {
+                if failing {
+                    eprintln!("File tail transport recovered");
+                    failing = false;
+                }
+                metrics.set_connected(true);
+            }
+            Ok(false) => return,
+            Err(e) => {
+                if !failing {
+                    eprintln!("File tail transport error, retrying: {:#}", e);
+                    failing = true;
+                }
+                metrics.set_connected(false);
+            }
+        }
+    }
+}
+
+/// Polls `tailer` once, forwards the new records and commits the read
+/// position if it moved past `committed`. Returns `false` once the channel is
+/// closed.
+///
+/// **Parameters:**
+///
+/// * `tailer`: The tailer owning the read position.
+/// * `sender`: The MPSC channel to forward the raw audit records to.
+/// * `metrics`: Shared pipeline counters updated as records are forwarded.
+/// * `committed`: The last committed read position; updated on commit.
+async fn forward_new_records(
+    tailer: &mut FileTailer,
+    sender: &mpsc::Sender<RawAuditRecord>,
+    metrics: &PipelineMetrics,
+    committed: &mut TailState,
+) -> Result<bool> {
+    for record in tailer.poll()? {
+        metrics.record_received();
+        if sender.send(record).await.is_err() {
+            // Channel closed: leave the batch uncommitted so it is read again
+            // on restart.
+            return Ok(false);
+        }
+    }
+    if tailer.state() != *committed {
+        tailer.commit()?;
+        *committed = tailer.state();
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const LINE_1: &str = "type=SYSCALL msg=audit(1700000000.000:1): syscall=59 success=yes\n";
+    const LINE_2: &str = "type=PATH msg=audit(1700000000.000:1): item=0 name=\"/bin/ls\"\n";
+    const LINE_3: &str = "type=CWD msg=audit(1700000001.000:2): cwd=\"/root\"\n";
+
+    fn append(path: &Path, data: &str) {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        file.write_all(data.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn restart_resumes_from_stored_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        append(&log, LINE_1);
+        append(&log, LINE_2);
+
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        let records = tailer.poll().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].record_id, 1300);
+        assert_eq!(records[1].record_id, 1302);
+        tailer.commit().unwrap();
+        drop(tailer);
+
+        // A partial line is not consumed until it is complete.
+        append(&log, "type=CWD msg=audit(1700000001.000:2): ");
+        let mut restarted = FileTailer::new(&log, &state).unwrap();
+        assert_eq!(
+            restarted.state().offset,
+            (LINE_1.len() + LINE_2.len()) as u64
+        );
+        assert!(restarted.poll().unwrap().is_empty());
+
+        append(&log, "cwd=\"/root\"\n");
+        let records = restarted.poll().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].data, LINE_3["type=CWD msg=".len()..].trim_end());
+    }
+
+    #[test]
+    fn uncommitted_lines_are_read_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        append(&log, LINE_1);
+
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        assert_eq!(tailer.poll().unwrap().len(), 1);
+        drop(tailer);
+
+        let mut restarted = FileTailer::new(&log, &state).unwrap();
+        assert_eq!(restarted.poll().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn rotation_resets_offset_for_new_inode() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        append(&log, LINE_1);
+        append(&log, LINE_2);
+
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        assert_eq!(tailer.poll().unwrap().len(), 2);
+        tailer.commit().unwrap();
+
+        // Rotate: the old file moves away and a new file takes its name.
+        fs::rename(&log, dir.path().join("audit.log.1")).unwrap();
+        append(&log, LINE_3);
+
+        let mut restarted = FileTailer::new(&log, &state).unwrap();
+        let records = restarted.poll().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_id, 1307);
+        assert_eq!(restarted.state().offset, LINE_3.len() as u64);
+    }
+
+    #[test]
+    fn rotation_drains_the_old_file_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        append(&log, LINE_1);
+
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        assert_eq!(tailer.poll().unwrap().len(), 1);
+
+        // A line lands in the old file just before it is rotated away.
+        append(&log, LINE_2);
+        fs::rename(&log, dir.path().join("audit.log.1")).unwrap();
+        append(&log, LINE_3);
+
+        let records = tailer.poll().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_id, 1302);
+        tailer.commit().unwrap();
+        drop(tailer);
+
+        // A restart finds the rotated file by its inode and moves on to the
+        // new one once it is drained.
+        let mut restarted = FileTailer::new(&log, &state).unwrap();
+        let records = restarted.poll().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_id, 1307);
+    }
+
+    #[test]
+    fn reads_are_bounded_and_overlong_lines_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        let overlong = format!(
+            "type=SYSCALL msg=audit(1.000:1): a={}\n",
+            "x".repeat(2 * TAIL_READ_LIMIT as usize)
+        );
+        append(&log, &overlong);
+        append(&log, LINE_1);
+
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        let mut records = tailer.poll().unwrap();
+        let mut polls = 1;
+        while tailer.has_pending() {
+            records.extend(tailer.poll().unwrap());
+            polls += 1;
+        }
+        assert_eq!(polls, 3);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_id, 1300);
+        assert_eq!(
+            tailer.state().offset,
+            (overlong.len() + LINE_1.len()) as u64
+        );
+    }
+
+    #[test]
+    fn non_audit_lines_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        let state = dir.path().join("tail.state");
+        append(&log, "hello world\n");
+        append(&log, "node=host type=SYSCALL msg=audit(1.000:1): a=b\n");
+        append(&log, "type=SYSCALL msg=oops\n");
+
+        let records = FileTailer::new(&log, &state).unwrap().poll().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].record_id, 1300);
+        assert_eq!(records[0].data, "audit(1.000:1): a=b");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn task_keeps_polling_after_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("audit.log");
+        // The state directory is missing, so committing fails at first.
+        let state_dir = dir.path().join("state");
+        let state = state_dir.join("tail.state");
+        append(&log, LINE_1);
+        let mut tailer = FileTailer::new(&log, &state).unwrap();
+        let metrics = Arc::new(PipelineMetrics::new());
+        let (sender, mut receiver) = mpsc::channel(10);
+        let task_metrics = metrics.clone();
+        let task =
+            tokio::spawn(async move { file_tail_task(&mut tailer, sender, &task_metrics).await });
+
+        assert_eq!(receiver.recv().await.unwrap().record_id, 1300);
+        tokio::task::yield_now().await;
+        assert!(!metrics.snapshot().connected);
+        assert!(!task.is_finished());
+
+        fs::create_dir(&state_dir).unwrap();
+        append(&log, LINE_2);
+        tokio::time::advance(TAIL_POLL_INTERVAL).await;
+        assert_eq!(receiver.recv().await.unwrap().record_id, 1302);
+        tokio::task::yield_now().await;
+        assert!(metrics.snapshot().connected);
+        assert_eq!(
+            TailState::load(&state).unwrap().unwrap().offset,
+            (LINE_1.len() + LINE_2.len()) as u64
+        );
+
+        drop(receiver);
+        task.abort();
+    }
+}
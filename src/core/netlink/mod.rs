@@ -13,27 +13,80 @@
 //! The `NetlinkAuditTransport` struct is used to transport the raw audit
 //! records to the parser.
 //!
+//! `FileTailTransport` is the text-log counterpart: it tails an auditd-format
+//! log file and forwards each line as a `RawAuditRecord`, persisting its read
+//! position (`TailState`) so a restart resumes where it left off.
+//!
 //! [`apply_audit_rule_message`] uses separate short-lived netlink sessions to
 //! add or delete kernel rules (e.g. path watches), distinct from the event
 //! listener.
 
+mod file_tail;
 mod netlink;
 mod raw_record;
 mod rule_session;
 
-pub use rule_session::apply_audit_rule_message;
+pub use rule_session::{apply_audit_rule_message, delete_all_audit_rules};
 
 /// A raw audit record received from the kernel via netlink.
-#[derive(Debug, PartialEq)]
+#[derive(Debug)]
 pub struct RawAuditRecord {
     /// The record ID.
     pub record_id: u16,
     /// The data of the record.
     pub data: String,
+    /// When the transport received the record; event latency is measured
+    /// from here.
+    pub received_at: std::time::Instant,
 }
 
 /// A transport for receiving raw audit records from the kernel via netlink and
 /// forwarding them to an intermediary MPSC channel for parsing.
 pub struct NetlinkAuditTransport {
     receiver: tokio::sync::mpsc::Receiver<RawAuditRecord>,
+    /// Signals the listener task to stop and close the netlink socket.
+    shutdown: std::sync::Arc<tokio::sync::Notify>,
+    /// The listener task, awaited by `NetlinkAuditTransport::shutdown`.
+    listener: tokio::task::JoinHandle<()>,
+}
+
+/// A transport that tails a text audit log (auditd `type=... msg=audit(...)`
+/// lines) and forwards each complete line to an intermediary MPSC channel as a
+/// `RawAuditRecord`.
+///
+/// The read position is persisted to a state file after each forwarded batch,
+/// giving at-least-once delivery across restarts.
+pub struct FileTailTransport {
+    receiver: tokio::sync::mpsc::Receiver<RawAuditRecord>,
+}
+
+/// The persisted read position of a tailed log file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TailState {
+    /// Inode of the file the offset refers to. If the log path no longer has
+    /// this inode, the log was rotated: the rest of the old file is read
+    /// (when it can still be found next to the log) before reading restarts
+    /// at offset `0` of the new file.
+    pub inode: u64,
+    /// Byte offset just past the last forwarded line.
+    pub offset: u64,
+}
+
+/// Reads complete lines appended to a log file since the last stored
+/// `TailState`. Used by `FileTailTransport`; separated so resume and rotation
+/// can be tested without a running transport.
+#[derive(Debug)]
+pub struct FileTailer {
+    /// The log file being tailed.
+    log_path: std::path::PathBuf,
+    /// Where the `TailState` is persisted.
+    state_path: std::path::PathBuf,
+    /// The current read position.
+    state: TailState,
+    /// The open file `state` refers to. It is kept after the log is rotated
+    /// so the lines appended to the old file before the rotation are still
+    /// read.
+    file: Option<std::fs::File>,
+    /// Whether the last poll stopped at the read limit with more data left.
+    pending: bool,
 }
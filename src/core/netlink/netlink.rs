@@ -1,23 +1,59 @@
 //! Implementation of the netlink transport for receiving raw audit records from
 //! the kernel and passing them on through the daemon core.
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow, bail};
 use audit::packet::AuditMessage;
-use futures::stream::StreamExt;
+use futures::stream::{Stream, StreamExt};
 use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
-use tokio::sync::mpsc;
+use std::sync::Arc;
+use tokio::sync::{Notify, mpsc};
 
+use crate::core::metrics::PipelineMetrics;
 use crate::core::netlink::{NetlinkAuditTransport, RawAuditRecord};
 
-/// The following two functions are abstractions over the netlink listener task
+/// Number of malformed payloads that are logged individually before the
+/// listener falls back to only counting them.
+const MALFORMED_PAYLOAD_LOG_LIMIT: u64 = 5;
+
+/// The following functions are abstractions over the netlink listener task
 /// that are used for unit testing the inner logic of the listener task
 
+/// Returns why an audit event payload is unusable, or `None` if it is well
+/// formed (or not an event at all). Protocol edge cases such as an empty
+/// key/value string or a zero event id would otherwise produce degenerate
+/// records downstream.
+///
+/// **Parameters:**
+///
+/// * `msg`: The netlink message received from the audit socket.
+fn malformed_payload_reason(msg: &NetlinkMessage<AuditMessage>) -> Option<&'static str> {
+    let NetlinkPayload::InnerMessage(inner) = &msg.payload else {
+        return None;
+    };
+    let (eid, data) = match inner {
+        AuditMessage::Event((eid, kvs)) => (*eid, kvs.as_str()),
+        AuditMessage::Other((eid, data)) => (*eid, data.as_str()),
+        _ => return None,
+    };
+    if eid == 0 {
+        Some("missing event id")
+    } else if data.trim().is_empty() {
+        Some("empty payload")
+    } else {
+        None
+    }
+}
+
 /// Maps a netlink audit message to a [`RawAuditRecord`]. Used by
 /// [`netlink_listener_task`]; separated so the transformation can be
-/// unit-tested without a live audit session.
+/// unit-tested without a live audit session. Malformed payloads (see
+/// [`malformed_payload_reason`]) never produce a record.
 fn raw_record_from_netlink_message(
     msg: &NetlinkMessage<audit::packet::AuditMessage>,
 ) -> Option<RawAuditRecord> {
+    if malformed_payload_reason(msg).is_some() {
+        return None;
+    }
     if let NetlinkPayload::InnerMessage(inner) = &msg.payload {
         let data = match inner {
             AuditMessage::Event((_, kvs)) => kvs.to_string(),
@@ -32,6 +68,42 @@ fn raw_record_from_netlink_message(
     }
 }
 
+/// Handles a netlink message that carries no audit record and returns whether
+/// it was one. Acks, `NLMSG_DONE` and no-ops answer our own requests and are
+/// skipped. An overrun means the socket buffer filled up and the kernel
+/// dropped audit records; it is counted as a drop and logged. An error reply
+/// is logged.
+///
+/// The kernel does not send an overrun message itself: `recvmsg` fails with
+/// `ENOBUFS`, and netlink-proto turns that error into a `NLMSG_OVERRUN`
+/// message (sequence 0, port 0) on the unsolicited message stream instead of
+/// ending the stream. That message is the only way an audit overrun reaches
+/// the listener.
+///
+/// **Parameters:**
+///
+/// * `msg`: The netlink message received from the audit socket.
+/// * `metrics`: Shared pipeline counters; overruns count as drops.
+fn handle_control_message(msg: &NetlinkMessage<AuditMessage>, metrics: &PipelineMetrics) -> bool {
+    match &msg.payload {
+        NetlinkPayload::InnerMessage(_) => false,
+        NetlinkPayload::Overrun(_) => {
+            metrics.dropped();
+            eprintln!(
+                "Netlink socket overrun: the receive buffer was full and the kernel dropped \
+                 audit records"
+            );
+            true
+        }
+        NetlinkPayload::Error(error) if error.code.is_some() => {
+            eprintln!("Netlink error reply on the audit socket: {}", error);
+            true
+        }
+        // Acks (errors without a code), NLMSG_DONE and no-ops.
+        _ => true,
+    }
+}
+
 /// Sends a parsed record to the parser task. Returns `false` if the channel is
 /// closed (receiver dropped), which is the same condition that makes
 /// [`netlink_listener_task`] exit its receive loop.
@@ -42,24 +114,74 @@ async fn send_raw_record_to_channel(
     sender.send(record).await.is_ok()
 }
 
+impl RawAuditRecord {
+    /// Decodes a single raw netlink frame (header and payload, as read from
+    /// the audit socket) into a `RawAuditRecord`.
+    ///
+    /// This is the same decode path the listener uses, exposed for replaying
+    /// captured frames and for fuzzing. Truncated or otherwise malformed
+    /// frames, malformed payloads, and non-record control messages are
+    /// reported as errors; no input causes a panic.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `bytes`: One complete netlink frame.
+    pub fn from_netlink_bytes(bytes: &[u8]) -> Result<Self> {
+        let msg = NetlinkMessage::<AuditMessage>::deserialize(bytes)
+            .map_err(|e| anyhow!("Invalid netlink frame: {}", e))?;
+        if let Some(reason) = malformed_payload_reason(&msg) {
+            bail!("Malformed audit payload: {}", reason);
+        }
+        raw_record_from_netlink_message(&msg).ok_or_else(|| {
+            anyhow!(
+                "Netlink frame of type {} is not an audit record",
+                msg.header.message_type
+            )
+        })
+    }
+}
+
 impl NetlinkAuditTransport {
     /// Creates a new `NetlinkAuditTransport` and spawns a task to listen for
     /// audit events.
-    pub fn new() -> Self {
+    ///
+    /// **Parameters:**
+    ///
+    /// * `metrics`: Shared pipeline counters; the listener records received
+    ///   records and its connection state here.
+    pub fn new(metrics: Arc<PipelineMetrics>) -> Self {
         let (sender, receiver) = mpsc::channel(1000);
-        tokio::spawn(async move {
-            if let Err(e) = netlink_listener_task(sender).await {
+        metrics.watch_channel("raw", &sender);
+        let shutdown = Arc::new(Notify::new());
+        let listener_shutdown = shutdown.clone();
+        let listener = tokio::spawn(async move {
+            if let Err(e) = netlink_listener_task(sender, &listener_shutdown, &metrics).await {
                 eprintln!("Netlink listener error: {}", e);
             }
+            metrics.set_connected(false);
         });
-        Self { receiver }
+        Self {
+            receiver,
+            shutdown,
+            listener,
+        }
     }
+
     /// Converts the `NetlinkAuditTransport` into a receiver for the raw audit
-    /// records.
+    /// records. The listener task stops and closes the netlink socket once the
+    /// returned receiver is dropped.
     pub fn into_receiver(self) -> mpsc::Receiver<RawAuditRecord> {
         self.receiver
     }
 
+    /// Stops the listener task, closing the netlink socket, and waits for it
+    /// to exit. Records not yet received are discarded.
+    pub async fn shutdown(self) {
+        self.shutdown.notify_one();
+        drop(self.receiver);
+        let _ = self.listener.await;
+    }
+
     /// Receives a raw audit record from the kernel via netlink.
     async fn _recv(&mut self) -> Option<RawAuditRecord> {
         self.receiver.recv().await
@@ -70,40 +192,109 @@ impl NetlinkAuditTransport {
 /// netlink socket and forwards them into the a MPSC channel via the `sender`
 /// parameter. Used in the constructor of `NetlinkAuditTransport`.
 ///
+/// The netlink connection task is aborted when the listener returns, which
+/// closes the socket so the kernel stops delivering events to it.
+///
 /// **Parameters:**
 ///
 /// * `sender`: The MPSC channel to forward the raw audit records to.
-async fn netlink_listener_task(sender: mpsc::Sender<RawAuditRecord>) -> Result<()> {
+/// * `shutdown`: Notified by `NetlinkAuditTransport::shutdown` to stop
+///   listening.
+/// * `metrics`: Shared pipeline counters updated as records arrive.
+async fn netlink_listener_task(
+    sender: mpsc::Sender<RawAuditRecord>,
+    shutdown: &Notify,
+    metrics: &PipelineMetrics,
+) -> Result<()> {
     // Create netlink socket connection
-    let (connection, mut handle, mut messages) =
+    let (connection, mut handle, messages) =
         audit::new_connection().context("Netlink socket connection failed.")?;
 
     // Spawn connection task
-    tokio::spawn(connection);
+    let connection_task = tokio::spawn(connection);
 
     // Enable audit events
-    handle
-        .enable_events()
-        .await
-        .context("Failed to enable events.")?;
+    if let Err(e) = handle.enable_events().await {
+        connection_task.abort();
+        return Err(e).context("Failed to enable events.");
+    }
 
     println!("Netlink audit transport listening for kernel events");
+    metrics.set_connected(true);
 
+    forward_messages(messages, &sender, shutdown, metrics).await;
+    connection_task.abort();
+    Ok(())
+}
+
+/// Forwards audit messages from `messages` to `sender` until the stream ends,
+/// `shutdown` is notified, or the receiving side of `sender` is dropped.
+/// Control messages (acks, `NLMSG_DONE`, overruns) are handled by
+/// [`handle_control_message`] and never forwarded. Separated from
+/// [`netlink_listener_task`] so shutdown can be tested without a live audit
+/// session.
+///
+/// **Parameters:**
+///
+/// * `messages`: The stream of netlink messages from the audit socket.
+/// * `sender`: The MPSC channel to forward the raw audit records to.
+/// * `shutdown`: Notified to stop forwarding.
+/// * `metrics`: Shared pipeline counters updated as records arrive; overruns
+///   are counted as drops.
+async fn forward_messages<S, A>(
+    mut messages: S,
+    sender: &mpsc::Sender<RawAuditRecord>,
+    shutdown: &Notify,
+    metrics: &PipelineMetrics,
+) where
+    S: Stream<Item = (NetlinkMessage<AuditMessage>, A)> + Unpin,
+{
     // Process events from the Linux kernel audit subsystem
-    while let Some((msg, _addr)) = messages.next().await {
+    loop {
+        let msg = tokio::select! {
+            next = messages.next() => match next {
+                Some((msg, _addr)) => msg,
+                None => break,
+            },
+            _ = shutdown.notified() => break,
+            _ = sender.closed() => break,
+        };
+        if handle_control_message(&msg, metrics) {
+            continue;
+        }
+        if let Some(reason) = malformed_payload_reason(&msg) {
+            let seen = metrics.malformed_payload();
+            if seen <= MALFORMED_PAYLOAD_LOG_LIMIT {
+                eprintln!(
+                    "Skipping malformed netlink payload (type {}): {}",
+                    msg.header.message_type, reason
+                );
+                if seen == MALFORMED_PAYLOAD_LOG_LIMIT {
+                    eprintln!("Further malformed netlink payloads will be counted but not logged");
+                }
+            }
+            continue;
+        }
         if let Some(raw_record) = raw_record_from_netlink_message(&msg) {
-            if !send_raw_record_to_channel(&sender, raw_record).await {
+            metrics.record_received();
+            if !send_raw_record_to_channel(sender, raw_record).await {
                 break; // Channel closed
             }
         }
     }
-    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use netlink_packet_core::NetlinkHeader;
+    use bytes::BufMut;
+    use netlink_packet_core::{DoneMessage, ErrorMessage, NetlinkHeader};
+    use netlink_proto::{NetlinkCodec, NetlinkFramed};
+    use netlink_sys::{AsyncSocket, Socket, SocketAddr};
+    use std::collections::VecDeque;
+    use std::io;
+    use std::sync::Mutex;
+    use std::task::{Context, Poll};
     use std::time::Duration;
 
     #[test]
@@ -137,6 +328,32 @@ mod tests {
         assert!(raw_record_from_netlink_message(&msg).is_none());
     }
 
+    #[test]
+    fn raw_record_skips_empty_event_payload() {
+        let mut msg = NetlinkMessage::from(AuditMessage::Event((1300, String::new())));
+        msg.finalize();
+
+        assert_eq!(malformed_payload_reason(&msg), Some("empty payload"));
+        assert!(raw_record_from_netlink_message(&msg).is_none());
+    }
+
+    #[test]
+    fn raw_record_skips_event_without_id() {
+        let mut msg = NetlinkMessage::from(AuditMessage::Event((0, "key=value".to_string())));
+        msg.finalize();
+
+        assert_eq!(malformed_payload_reason(&msg), Some("missing event id"));
+        assert!(raw_record_from_netlink_message(&msg).is_none());
+    }
+
+    #[test]
+    fn well_formed_event_is_not_malformed() {
+        let mut msg = NetlinkMessage::from(AuditMessage::Event((1300, "a=b".to_string())));
+        msg.finalize();
+
+        assert_eq!(malformed_payload_reason(&msg), None);
+    }
+
     #[test]
     fn raw_record_returns_none_for_non_inner_payload() {
         let msg =
@@ -144,6 +361,26 @@ mod tests {
         assert!(raw_record_from_netlink_message(&msg).is_none());
     }
 
+    fn first_source_log_frame() -> Vec<u8> {
+        let log = std::fs::read_to_string("tests/test-source.log").unwrap();
+        hex::decode(log.lines().next().unwrap().trim()).unwrap()
+    }
+
+    #[test]
+    fn from_netlink_bytes_decodes_captured_frame() {
+        let record = RawAuditRecord::from_netlink_bytes(&first_source_log_frame()).unwrap();
+        assert_eq!(record.record_id, 1105);
+        assert!(record.data.starts_with("audit("));
+    }
+
+    #[test]
+    fn from_netlink_bytes_rejects_malformed_frames() {
+        let frame = first_source_log_frame();
+        assert!(RawAuditRecord::from_netlink_bytes(&[]).is_err());
+        assert!(RawAuditRecord::from_netlink_bytes(&frame[..10]).is_err());
+        assert!(RawAuditRecord::from_netlink_bytes(&[0xff; 64]).is_err());
+    }
+
     #[tokio::test]
     async fn send_raw_record_to_channel_false_when_receiver_dropped() {
         let (sender, receiver) = mpsc::channel::<RawAuditRecord>(1);
@@ -160,9 +397,162 @@ mod tests {
         assert_eq!(got.data, "y");
     }
 
+    #[tokio::test]
+    async fn overrun_counts_as_drop_and_control_messages_are_skipped() {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let metrics = PipelineMetrics::new();
+        let control = |payload: NetlinkPayload<AuditMessage>| {
+            NetlinkMessage::new(NetlinkHeader::default(), payload)
+        };
+        let mut event = NetlinkMessage::from(AuditMessage::Event((1300, "a=b".to_string())));
+        event.finalize();
+        let messages = futures::stream::iter([
+            (control(NetlinkPayload::Overrun(Vec::new())), ()),
+            (control(NetlinkPayload::Error(ErrorMessage::default())), ()),
+            (control(NetlinkPayload::Done(DoneMessage::default())), ()),
+            (event, ()),
+        ]);
+
+        forward_messages(messages, &sender, &Notify::new(), &metrics).await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.dropped, 1);
+        assert_eq!(snapshot.records_received, 1);
+        assert_eq!(snapshot.malformed_payloads, 0);
+        assert_eq!(receiver.recv().await.unwrap().record_id, 1300);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    /// Socket that hands each scripted `recvmsg` result to netlink-proto in
+    /// turn and fails once the script runs out, which ends the stream.
+    struct ScriptedSocket(Mutex<VecDeque<io::Result<Vec<u8>>>>);
+
+    impl AsyncSocket for ScriptedSocket {
+        fn socket_ref(&self) -> &Socket {
+            unreachable!("the framed stream only receives")
+        }
+
+        fn socket_mut(&mut self) -> &mut Socket {
+            unreachable!("the framed stream only receives")
+        }
+
+        fn new(_protocol: isize) -> io::Result<Self> {
+            unreachable!("built directly by the test")
+        }
+
+        fn poll_send(&self, _cx: &mut Context<'_>, _buf: &[u8]) -> Poll<io::Result<usize>> {
+            unreachable!("the framed stream only receives")
+        }
+
+        fn poll_send_to(
+            &self,
+            _cx: &mut Context<'_>,
+            _buf: &[u8],
+            _addr: &SocketAddr,
+        ) -> Poll<io::Result<usize>> {
+            unreachable!("the framed stream only receives")
+        }
+
+        fn poll_recv<B: BufMut>(
+            &self,
+            _cx: &mut Context<'_>,
+            _buf: &mut B,
+        ) -> Poll<io::Result<()>> {
+            unreachable!("the framed stream receives with poll_recv_from")
+        }
+
+        fn poll_recv_from<B: BufMut>(
+            &self,
+            _cx: &mut Context<'_>,
+            buf: &mut B,
+        ) -> Poll<io::Result<SocketAddr>> {
+            let next = self
+                .0
+                .lock()
+                .unwrap()
+                .pop_front()
+                .unwrap_or_else(|| Err(io::ErrorKind::ConnectionAborted.into()));
+            Poll::Ready(next.map(|frame| {
+                buf.put_slice(&frame);
+                SocketAddr::new(0, 0)
+            }))
+        }
+
+        fn poll_recv_from_full(
+            &self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<io::Result<(Vec<u8>, SocketAddr)>> {
+            unreachable!("the framed stream receives with poll_recv_from")
+        }
+    }
+
+    #[tokio::test]
+    async fn enobufs_from_recvmsg_counts_as_drop() {
+        let (sender, mut receiver) = mpsc::channel(4);
+        let metrics = PipelineMetrics::new();
+        let mut event = NetlinkMessage::from(AuditMessage::Event((1300, "a=b".to_string())));
+        event.finalize();
+        let mut frame = vec![0; event.buffer_len()];
+        event.serialize(&mut frame);
+        let socket = ScriptedSocket(Mutex::new(VecDeque::from([
+            Err(io::Error::from_raw_os_error(libc::ENOBUFS)),
+            Ok(frame),
+        ])));
+        let messages = NetlinkFramed::<AuditMessage, _, NetlinkCodec>::new(socket);
+
+        forward_messages(messages, &sender, &Notify::new(), &metrics).await;
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.dropped, 1);
+        assert_eq!(snapshot.records_received, 1);
+        assert_eq!(receiver.recv().await.unwrap().record_id, 1300);
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn forward_messages_stops_on_shutdown() {
+        let (sender, _receiver) = mpsc::channel(1);
+        let shutdown = Arc::new(Notify::new());
+        let metrics = Arc::new(PipelineMetrics::new());
+        let task = {
+            let (shutdown, metrics) = (shutdown.clone(), metrics.clone());
+            tokio::spawn(async move {
+                let messages = futures::stream::pending::<(NetlinkMessage<AuditMessage>, ())>();
+                forward_messages(messages, &sender, &shutdown, &metrics).await;
+            })
+        };
+        shutdown.notify_one();
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("listener exits after shutdown")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn forward_messages_stops_when_receiver_dropped() {
+        let (sender, receiver) = mpsc::channel(1);
+        let task = tokio::spawn(async move {
+            let messages = futures::stream::pending::<(NetlinkMessage<AuditMessage>, ())>();
+            forward_messages(messages, &sender, &Notify::new(), &PipelineMetrics::new()).await;
+        });
+        drop(receiver);
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("listener exits once the receiver is gone")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn netlink_audit_transport_shutdown_terminates_listener() {
+        let transport = NetlinkAuditTransport::new(Arc::new(PipelineMetrics::new()));
+        tokio::time::timeout(Duration::from_secs(1), transport.shutdown())
+            .await
+            .expect("shutdown waits for the listener to exit");
+    }
+
     #[tokio::test]
     async fn netlink_audit_transport_new_and_into_receiver() {
-        let transport = NetlinkAuditTransport::new();
+        let transport = NetlinkAuditTransport::new(Arc::new(PipelineMetrics::new()));
         let mut receiver = transport.into_receiver();
         // Background task may fail immediately without audit privileges - we only check
         // if the receiver is open
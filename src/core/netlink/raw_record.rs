@@ -1,9 +1,12 @@
 //! Raw audit record constructor implementation.
 
+use std::time::Instant;
+
 use crate::core::netlink::RawAuditRecord;
 
 impl RawAuditRecord {
-    /// Creates a new `RawAuditRecord` with the given record ID and data.
+    /// Creates a new `RawAuditRecord` with the given record ID and data,
+    /// received now.
     ///
     /// **Parameters:**
     ///
@@ -13,6 +16,15 @@ impl RawAuditRecord {
         RawAuditRecord {
             record_id: id,
             data,
+            received_at: Instant::now(),
         }
     }
 }
+
+/// Records are equal when their type and data are; when they were received
+/// does not matter.
+impl PartialEq for RawAuditRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.record_id == other.record_id && self.data == other.data
+    }
+}
@@ -1,13 +1,15 @@
 //! Short-lived netlink sessions to add or delete kernel audit rules.
 //!
 //! Each call opens a new [`audit::new_connection`], spawns the connection
-//! driver, and runs [`audit::Handle::add_rule`] or [`audit::Handle::del_rule`].
+//! driver, and runs [`audit::Handle::add_rule`] or [`audit::Handle::del_rule`]
+//! (or lists and deletes every rule, for auditctl's `-D`).
 //! This is intentionally separate from
 //! [`super::netlink::netlink_listener_task`], which owns the long-lived
 //! connection for receiving audit events.
 
 use anyhow::{Context, Result};
 use audit::packet::RuleMessage;
+use futures::stream::TryStreamExt;
 
 /// Apply a single [`RuleMessage`] to the kernel (`add_rule` or `del_rule`)
 /// using a one-shot netlink session.
@@ -30,3 +32,27 @@ pub fn apply_audit_rule_message(rule: RuleMessage, delete: bool) -> Result<()> {
         }
     })
 }
+
+/// Delete every audit rule currently loaded in the kernel (auditctl `-D`)
+/// using a one-shot netlink session. Returns the number of rules deleted.
+pub fn delete_all_audit_rules() -> Result<usize> {
+    let rt = tokio::runtime::Runtime::new().context("failed to create Tokio runtime")?;
+    rt.block_on(async move {
+        let (connection, mut handle, _messages) =
+            audit::new_connection().context("audit netlink new_connection")?;
+        tokio::spawn(connection);
+        let rules: Vec<RuleMessage> = handle
+            .list_rules()
+            .try_collect()
+            .await
+            .map_err(|e| anyhow::anyhow!("audit list_rules: {}", e))?;
+        let count = rules.len();
+        for rule in rules {
+            handle
+                .del_rule(rule)
+                .await
+                .map_err(|e| anyhow::anyhow!("audit del_rule: {}", e))?;
+        }
+        Ok(count)
+    })
+}
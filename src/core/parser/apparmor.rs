@@ -0,0 +1,174 @@
+//! Decoding of AppArmor audit records: `AVC` and `USER_AVC` records carrying
+//! an `apparmor=` field and the kernel's own `APPARMOR_*` records.
+
+use crate::core::parser::{AppArmorDecision, AppArmorRecord, ParsedAuditRecord, RecordType};
+
+impl AppArmorDecision {
+    /// Decodes the value of an `apparmor=` field, e.g. `DENIED`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `value`: The field value, without quotes.
+    pub fn from_field(value: &str) -> Self {
+        match value {
+            "AUDIT" => AppArmorDecision::Audit,
+            "ALLOWED" => AppArmorDecision::Allowed,
+            "DENIED" => AppArmorDecision::Denied,
+            "HINT" => AppArmorDecision::Hint,
+            "STATUS" => AppArmorDecision::Status,
+            "ERROR" => AppArmorDecision::Error,
+            "KILLED" => AppArmorDecision::Kill,
+            other => AppArmorDecision::Unknown(other.to_string()),
+        }
+    }
+
+    /// Returns the decision implied by a kernel `APPARMOR_*` record type, or
+    /// `None` for other record types.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record_type`: The type of the record.
+    fn from_record_type(record_type: RecordType) -> Option<Self> {
+        match record_type {
+            RecordType::ApparmorAudit => Some(AppArmorDecision::Audit),
+            RecordType::ApparmorAllowed => Some(AppArmorDecision::Allowed),
+            RecordType::ApparmorDenied => Some(AppArmorDecision::Denied),
+            RecordType::ApparmorHint => Some(AppArmorDecision::Hint),
+            RecordType::ApparmorStatus => Some(AppArmorDecision::Status),
+            RecordType::ApparmorError => Some(AppArmorDecision::Error),
+            RecordType::ApparmorKill => Some(AppArmorDecision::Kill),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the access was refused: denied, or the task killed.
+    pub fn is_denial(&self) -> bool {
+        matches!(self, AppArmorDecision::Denied | AppArmorDecision::Kill)
+    }
+}
+
+impl AppArmorRecord {
+    /// The decision AppArmor took.
+    pub fn decision(&self) -> &AppArmorDecision {
+        &self.decision
+    }
+
+    /// The operation that was checked, e.g. `open`.
+    pub fn operation(&self) -> Option<&str> {
+        self.operation.as_deref()
+    }
+
+    /// The profile that made the decision.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The object of the operation, such as a file path.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The permissions requested, e.g. `r`.
+    pub fn requested_mask(&self) -> Option<&str> {
+        self.requested_mask.as_deref()
+    }
+
+    /// The permissions denied, e.g. `r`.
+    pub fn denied_mask(&self) -> Option<&str> {
+        self.denied_mask.as_deref()
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Decodes the AppArmor fields of this record. Returns `None` for records
+    /// that carry no `apparmor=` field and are not kernel `APPARMOR_*`
+    /// records (e.g. SELinux `AVC` records).
+    ///
+    /// In `USER_AVC` records from user-space enforcers such as D-Bus, the
+    /// fields follow `msg='`, so the first one reaches the parser as
+    /// `msg='apparmor=...`; that form is recognised too.
+    pub fn apparmor(&self) -> Option<AppArmorRecord> {
+        let decision = self
+            .fields
+            .get("apparmor")
+            .map(String::as_str)
+            .or_else(|| {
+                self.fields
+                    .get("msg")?
+                    .strip_prefix("'apparmor=")
+                    .map(|value| value.trim_matches('"'))
+            })
+            .map(AppArmorDecision::from_field)
+            .or_else(|| AppArmorDecision::from_record_type(self.record_type))?;
+        let field = |name: &str| self.fields.get(name).cloned();
+        Some(AppArmorRecord {
+            decision,
+            operation: field("operation"),
+            profile: field("profile"),
+            name: field("name"),
+            requested_mask: field("requested_mask"),
+            denied_mask: field("denied_mask"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::ParseOptions;
+    use crate::core::parser::parser::parse_line;
+
+    const DENIED: &str = "type=AVC msg=audit(1700000000.000:200): apparmor=\"DENIED\" \
+                          operation=\"open\" profile=\"/usr/sbin/cupsd\" name=\"/etc/shadow\" \
+                          pid=1234 comm=\"cupsd\" requested_mask=\"r\" denied_mask=\"r\" \
+                          fsuid=0 ouid=0";
+
+    #[test]
+    fn decodes_apparmor_denial() {
+        let record = parse_line(DENIED, &ParseOptions::default()).unwrap();
+        let apparmor = record.apparmor().unwrap();
+
+        assert_eq!(apparmor.decision(), &AppArmorDecision::Denied);
+        assert!(apparmor.decision().is_denial());
+        assert_eq!(apparmor.operation(), Some("open"));
+        assert_eq!(apparmor.profile(), Some("/usr/sbin/cupsd"));
+        assert_eq!(apparmor.name(), Some("/etc/shadow"));
+        assert_eq!(apparmor.requested_mask(), Some("r"));
+        assert_eq!(apparmor.denied_mask(), Some("r"));
+
+        // SELinux AVC records are not AppArmor records.
+        let selinux = parse_line(
+            "type=AVC msg=audit(1700000000.000:201): avc:  denied  { read } for pid=1 \
+             scontext=system_u:system_r:init_t:s0 tcontext=system_u:object_r:etc_t:s0",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(selinux.apparmor(), None);
+    }
+
+    #[test]
+    fn decodes_kernel_and_user_space_records() {
+        let kernel = parse_line(
+            "type=APPARMOR_STATUS msg=audit(1700000000.000:202): operation=\"profile_load\" \
+             profile=\"unconfined\" name=\"/usr/bin/man\" pid=900 comm=\"apparmor_parser\"",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        let apparmor = kernel.apparmor().unwrap();
+        assert_eq!(apparmor.decision(), &AppArmorDecision::Status);
+        assert_eq!(apparmor.operation(), Some("profile_load"));
+        assert!(!apparmor.decision().is_denial());
+
+        let user = parse_line(
+            "type=USER_AVC msg=audit(1700000000.000:203): pid=800 uid=103 auid=4294967295 \
+             ses=4294967295 msg='apparmor=\"DENIED\" operation=\"dbus_method_call\" \
+             bus=\"system\" profile=\"/usr/bin/evince\" exe=\"/usr/bin/dbus-daemon\"'",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        let apparmor = user.apparmor().unwrap();
+        assert_eq!(apparmor.decision(), &AppArmorDecision::Denied);
+        assert_eq!(apparmor.operation(), Some("dbus_method_call"));
+        assert_eq!(apparmor.profile(), Some("/usr/bin/evince"));
+    }
+}
@@ -14,11 +14,25 @@
 //!
 //! <https://github.com/Rowdy-Rustiles/docs/blob/main/Reference/Record%20Types.md>
 
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use crate::core::severity::Severity;
 
 #[allow(missing_docs)]
 #[derive(
-    Debug, Clone, Copy, PartialEq, Eq, strum::EnumIter, strum::EnumString, Deserialize, Serialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    strum::EnumIter,
+    strum::EnumString,
+    Deserialize,
+    Serialize,
 )]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -50,6 +64,9 @@ pub enum RecordType {
     /* =========================
      * User (1100–1199)
      * ========================= */
+    /// Type 1100, written as `USER_FIRST_MSG`. auditd logs name it
+    /// `USER_AUTH` (PAM authentication), which is read as well.
+    #[strum(serialize = "FIRST_USER_MSG", serialize = "USER_AUTH")]
     FirstUserMsg,
     UserAcct,
     UserMgmt,
@@ -332,6 +349,33 @@ pub enum RecordType {
 }
 
 impl RecordType {
+    /// Leniently maps a record type name from real-world log input to a
+    /// `RecordType`.
+    ///
+    /// Surrounding whitespace is trimmed and the name is matched
+    /// case-insensitively against the auditd names (see
+    /// [`RecordType::as_audit_str`]). auditd's `UNKNOWN[<id>]` form maps to
+    /// the type with that id. Anything else falls back to `Unknown(0)`; use the
+    /// strict `FromStr` implementation where invalid names must be rejected.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `s`: The record type name, e.g. `"SYSCALL"` or `" syscall "`.
+    pub fn from_audit_str(s: &str) -> RecordType {
+        let name = s.trim().to_ascii_uppercase();
+        if let Some(id) = name
+            .strip_prefix("UNKNOWN[")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|id| id.parse::<u16>().ok())
+        {
+            return RecordType::from(id);
+        }
+        RecordType::iter()
+            .find(|rt| !matches!(rt, RecordType::Unknown(_)) && rt.as_audit_str() == name)
+            .or_else(|| RecordType::from_str(&name).ok())
+            .unwrap_or(RecordType::Unknown(0))
+    }
+
     /// Returns the string representation of the record type as defined in the
     /// auditd documentation.
     pub fn as_audit_str(&self) -> &'static str {
@@ -611,6 +655,34 @@ impl RecordType {
             Self::Unknown(_) => "UNKNOWN",
         }
     }
+
+    /// Returns the base severity of this record type, used as the starting
+    /// point by the [`SeverityScorer`](crate::core::severity::SeverityScorer).
+    ///
+    /// AVC/SELinux denials and all `ANOM_*`/`RESP_*` records are `High`;
+    /// logins, authentication and account or policy changes are `Medium`;
+    /// session and credential bookkeeping is `Low`. `SYSCALL` returns `None`
+    /// because its severity depends on its fields; other types are `Info`.
+    pub fn severity_hint(&self) -> Option<Severity> {
+        use RecordType::*;
+        let name = self.as_audit_str();
+        if name.starts_with("ANOM_") || name.starts_with("RESP_") {
+            return Some(Severity::High);
+        }
+        let severity = match self {
+            Syscall => return None,
+            Avc | UserAvc | SelinuxErr | UserSelinuxErr => Severity::High,
+            Login | UserLogin | FirstUserMsg | UserAcct | UserErr | UserChauthtok | UserMgmt
+            | AddUser | DelUser | AddGroup | DelGroup | GrpMgmt | GrpChauthtok | UserRoleChange
+            | RoleAssign | RoleRemove | ConfigChange | MacPolicyLoad | MacStatus
+            | MacConfigChange | UserMacPolicyLoad | UserMacConfigChange | UserMacStatus
+            | IntegrityRule | IntegrityPolicyRule => Severity::Medium,
+            UserStart | UserEnd | CredAcq | CredDisp | CredRefr | UserCmd | UserTty | Tty
+            | Execve => Severity::Low,
+            _ => Severity::Info,
+        };
+        Some(severity)
+    }
 }
 
 impl From<u16> for RecordType {
@@ -1163,6 +1235,22 @@ mod tests {
         assert_eq!(u16::from(RecordType::GetStatus), 1000);
     }
 
+    #[test]
+    fn record_type_from_audit_str_is_lenient() {
+        assert_eq!(RecordType::from_audit_str(" syscall "), RecordType::Syscall);
+        assert_eq!(RecordType::from_audit_str("Avc\t"), RecordType::Avc);
+        assert_eq!(
+            RecordType::from_audit_str("UNKNOWN[1300]"),
+            RecordType::Syscall
+        );
+        assert_eq!(
+            RecordType::from_audit_str("not a type"),
+            RecordType::Unknown(0)
+        );
+        // The strict parser still rejects non-canonical input.
+        assert!(RecordType::from_str(" syscall ").is_err());
+    }
+
     #[test]
     fn record_type_as_audit_str() {
         assert_eq!(RecordType::GetStatus.as_audit_str(), "GET_STATUS");
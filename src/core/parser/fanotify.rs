@@ -0,0 +1,141 @@
+//! Decoding of `FANOTIFY` records, which the kernel emits when a fanotify
+//! permission listener answers an access with `FAN_AUDIT`:
+//!
+//! ```text
+//! type=FANOTIFY msg=audit(...): resp=2 fan_type=1 fan_info=11 subj_trust=0 obj_trust=2
+//! ```
+//!
+//! Kernels before 6.3 only write `resp`. A listener response without extra
+//! information is written as `fan_type=0 fan_info=3F` (a hex-encoded `?`).
+
+use crate::core::parser::{FanotifyRecord, FanotifyResponse, ParsedAuditRecord, RecordType};
+
+impl FanotifyResponse {
+    /// Decodes the `resp=` value of a `FANOTIFY` record.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `resp`: The response value.
+    pub fn from_resp(resp: u32) -> Self {
+        match resp {
+            1 => FanotifyResponse::Allow,
+            2 => FanotifyResponse::Deny,
+            resp => FanotifyResponse::Unknown(resp),
+        }
+    }
+}
+
+impl FanotifyRecord {
+    /// The listener's decision.
+    pub fn response(&self) -> FanotifyResponse {
+        self.response
+    }
+
+    /// The type of the extra response information, `0` for none.
+    pub fn fan_type(&self) -> u32 {
+        self.fan_type
+    }
+
+    /// The extra response information, e.g. the listener's rule number.
+    pub fn fan_info(&self) -> Option<u32> {
+        self.fan_info
+    }
+
+    /// Whether the listener trusts the subject, if known.
+    pub fn subj_trust(&self) -> Option<bool> {
+        self.subj_trust
+    }
+
+    /// Whether the listener trusts the object, if known.
+    pub fn obj_trust(&self) -> Option<bool> {
+        self.obj_trust
+    }
+
+    /// The process that made the access, if the record carries its pid.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Decodes this record as a `FANOTIFY` record. Returns `None` for other
+    /// record types and for records without a numeric `resp`.
+    pub fn fanotify(&self) -> Option<FanotifyRecord> {
+        if self.record_type != RecordType::Fanotify {
+            return None;
+        }
+        let fan_type = self
+            .get_u64("fan_type")
+            .and_then(|fan_type| u32::try_from(fan_type).ok())
+            .unwrap_or(0);
+        let trust = |key: &str| {
+            match self.get(key)? {
+                "0" => Some(false),
+                "1" => Some(true),
+                _ => None,
+            }
+        };
+        Some(FanotifyRecord {
+            response: FanotifyResponse::from_resp(self.get("resp")?.parse().ok()?),
+            fan_type,
+            fan_info: self
+                .get("fan_info")
+                .filter(|_| fan_type != 0)
+                .and_then(|info| u32::from_str_radix(info, 16).ok()),
+            subj_trust: trust("subj_trust"),
+            obj_trust: trust("obj_trust"),
+            pid: self.get("pid").and_then(|pid| pid.parse().ok()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::ParseOptions;
+    use crate::core::parser::parser::parse_line;
+
+    #[test]
+    fn decodes_fanotify_response_and_pid() {
+        let record = parse_line(
+            "type=FANOTIFY msg=audit(1700000000.000:200): pid=4242 resp=2 fan_type=1 \
+             fan_info=11 subj_trust=0 obj_trust=2",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        let fanotify = record.fanotify().unwrap();
+
+        assert_eq!(fanotify.response(), FanotifyResponse::Deny);
+        assert_eq!(fanotify.fan_type(), 1);
+        assert_eq!(fanotify.fan_info(), Some(0x11));
+        assert_eq!(fanotify.subj_trust(), Some(false));
+        assert_eq!(fanotify.obj_trust(), None);
+        assert_eq!(fanotify.pid(), Some(4242));
+    }
+
+    #[test]
+    fn decodes_responses_without_extra_information() {
+        let decode = |fields: &str| {
+            let line = format!("type=FANOTIFY msg=audit(1700000000.000:201): {fields}");
+            parse_line(&line, &ParseOptions::default())
+                .unwrap()
+                .fanotify()
+        };
+
+        let fanotify = decode("resp=1 fan_type=0 fan_info=3F subj_trust=2 obj_trust=2").unwrap();
+        assert_eq!(fanotify.response(), FanotifyResponse::Allow);
+        assert_eq!(fanotify.fan_info(), None);
+        assert_eq!(fanotify.pid(), None);
+
+        // Kernels before 6.3 only write the response.
+        assert_eq!(decode("resp=1").unwrap().fan_type(), 0);
+        assert_eq!(decode("fan_type=0"), None);
+
+        let syscall = parse_line(
+            "type=SYSCALL msg=audit(1700000000.000:201): syscall=257 resp=1",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(syscall.fanotify(), None);
+    }
+}
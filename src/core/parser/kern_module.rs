@@ -0,0 +1,53 @@
+//! Decoding of `KERN_MODULE` records, which name the kernel module loaded or
+//! unloaded by the event's `init_module`, `finit_module` or `delete_module`
+//! syscall:
+//!
+//! ```text
+//! type=KERN_MODULE msg=audit(...): name="nf_tables"
+//! ```
+
+use crate::core::parser::{KernModuleRecord, ParsedAuditRecord, RecordType};
+
+impl KernModuleRecord {
+    /// The module name, e.g. `nf_tables`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Decodes this record as a `KERN_MODULE` record. Returns `None` for other
+    /// record types and for records without a `name`. A hex-encoded name is
+    /// decoded.
+    pub fn kern_module(&self) -> Option<KernModuleRecord> {
+        if self.record_type != RecordType::KernModule {
+            return None;
+        }
+        Some(KernModuleRecord {
+            name: self.get_quoted("name")?.into_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::parser::ParseOptions;
+    use crate::core::parser::parser::parse_line;
+
+    #[test]
+    fn decodes_module_name() {
+        let record = parse_line(
+            "type=KERN_MODULE msg=audit(1700000000.000:300): name=\"nf_tables\"",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record.kern_module().unwrap().name(), "nf_tables");
+
+        let path = parse_line(
+            "type=PATH msg=audit(1700000000.000:300): name=\"/lib/modules\"",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(path.kern_module(), None);
+    }
+}
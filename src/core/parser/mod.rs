@@ -8,13 +8,26 @@
 //!
 //! Note that the parser does not perform any type enrichment; this is handled
 //! by the `enricher` module.
+//!
+//! [`RecordReader`] streams records from auditd text logs line by line and, on
+//! request, reports the byte offset each record's line starts at. Other
+//! line-based inputs (e.g. JSONL) are read through the same reader with a
+//! [`LineParser`].
 
+pub mod apparmor;
 pub mod audit_types;
+pub mod fanotify;
+pub mod kern_module;
+pub mod pam;
 pub mod parser;
+pub mod reader;
+pub mod seccomp;
+pub mod selinux;
 
 use serde::{Deserialize, Serialize};
 
 pub use audit_types::RecordType;
+pub use selinux::parse_selinux_context;
 
 /// Intermediate result of parsing an audit message; used by parser and
 /// parsed_record. This should be phased out
@@ -26,10 +39,242 @@ pub struct RecordData {
     pub serial: String,
     /// The key-value pairs of the record (stored as strings).
     pub fields: std::collections::HashMap<String, String>,
+    /// Names of the fields whose value was double-quoted.
+    pub quoted: std::collections::HashSet<String>,
+    /// Names of the fields whose value was truncated.
+    pub truncated: std::collections::HashSet<String>,
+    /// Names of the fields read from inside a `msg='...'` blob.
+    pub nested: std::collections::HashSet<String>,
+}
+
+/// The fields of a key–value payload, as read by `parser::read_payload`.
+#[derive(Debug, Default)]
+pub(crate) struct PayloadFields {
+    /// The key-value pairs, with values capped at the maximum field length.
+    pub fields: std::collections::HashMap<String, String>,
+    /// Names of the fields whose value was double-quoted.
+    pub quoted: std::collections::HashSet<String>,
+    /// Names of the fields whose value was truncated.
+    pub truncated: std::collections::HashSet<String>,
+    /// Names of the fields read from inside a `msg='...'` blob.
+    pub nested: std::collections::HashSet<String>,
+}
+
+/// An audit log line parsed by `parser::parse_line_borrowed`, whose fields
+/// are slices of the line rather than owned strings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowedRecord<'a> {
+    /// The type of the record.
+    pub record_type: RecordType,
+    /// The timestamp of the record.
+    pub timestamp: std::time::SystemTime,
+    /// The serial number of the record.
+    pub serial: u16,
+    /// The `key=value ...` payload after the header, split on demand.
+    payload: &'a str,
+}
+
+/// Errors produced when parsing a full audit log line
+/// (`type=<TYPE> msg=audit(<timestamp>:<serial>): ...`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The line does not start with a `type=<RECORD_TYPE>` field.
+    MissingType,
+    /// The `type=` field is not followed by a `msg=audit(...)` header.
+    MissingMsg,
+    /// The `type=` value is not a known record type (strict mode only).
+    UnknownType(String),
+    /// The `msg=audit(...)` header or payload could not be parsed.
+    InvalidMessage(String),
+}
+
+/// Controls how strictly full audit log lines are validated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// When `true` (the default), a line must begin with a known `type=`
+    /// followed by `msg=audit(...)`. When `false`, `type=` names are matched
+    /// with [`RecordType::from_audit_str`], and a missing or unrecognized
+    /// `type=` yields an `Unknown` record type instead of an error. Either way,
+    /// leading `node=` fields are skipped and the `msg=audit(...)` header is
+    /// required.
+    pub strict: bool,
+    /// Longest field value kept, in bytes (default
+    /// `parser::DEFAULT_MAX_FIELD_LEN`). A longer value is cut at a character
+    /// boundary and ends with `parser::TRUNCATED_MARKER`, so hostile or
+    /// corrupt input cannot make a single field arbitrarily large.
+    pub max_field_len: usize,
+}
+
+/// A SELinux security context such as
+/// `unconfined_u:unconfined_r:unconfined_t:s0-s0:c0.c1023`, as found in the
+/// `subj=`, `obj=`, `scontext=` and `tcontext=` fields.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelinuxContext {
+    /// SELinux user (e.g. `system_u`).
+    user: String,
+    /// SELinux role (e.g. `object_r`).
+    role: String,
+    /// SELinux type or domain (e.g. `etc_t`).
+    type_: String,
+    /// MLS/MCS range (e.g. `s0-s0:c0.c1023`), absent on non-MLS policies.
+    range: Option<String>,
+}
+
+/// The action a seccomp filter returned for a syscall, decoded from the
+/// `code=` field of a `SECCOMP` record (`SECCOMP_RET_*`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeccompAction {
+    /// The whole process was killed (`SECCOMP_RET_KILL_PROCESS`).
+    KillProcess,
+    /// The calling thread was killed (`SECCOMP_RET_KILL_THREAD`).
+    KillThread,
+    /// A `SIGSYS` was delivered to the thread (`SECCOMP_RET_TRAP`).
+    Trap,
+    /// The syscall failed with the given errno (`SECCOMP_RET_ERRNO`).
+    Errno(u16),
+    /// The decision was deferred to a user-space supervisor
+    /// (`SECCOMP_RET_USER_NOTIF`).
+    UserNotif,
+    /// A ptrace tracer was notified (`SECCOMP_RET_TRACE`).
+    Trace,
+    /// The syscall was allowed and logged (`SECCOMP_RET_LOG`).
+    Log,
+    /// The syscall was allowed (`SECCOMP_RET_ALLOW`).
+    Allow,
+    /// An action this version does not know, with the raw action bits.
+    Unknown(u32),
+}
+
+/// The decoded contents of a `SECCOMP` record: which syscall a seccomp filter
+/// acted on and what it did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeccompRecord {
+    /// The action taken.
+    action: SeccompAction,
+    /// The syscall number.
+    syscall: u32,
+    /// The syscall name, when the record's arch is the host architecture.
+    syscall_name: Option<&'static str>,
+    /// The audit arch constant (e.g. `c000003e`).
+    arch: Option<String>,
+    /// Whether the syscall was made through a compat (32-bit) entry point.
+    compat: bool,
+    /// The instruction pointer of the syscall (e.g. `0x7f3a1b2c3d4e`).
+    ip: Option<String>,
+    /// The signal the process received, `0` for none.
+    signal: u32,
+}
+
+/// The decision AppArmor reported for an access, from the `apparmor=` field
+/// or, for kernel `APPARMOR_*` records without it, the record type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppArmorDecision {
+    /// The access was allowed and audited by an `audit` rule (`AUDIT`).
+    Audit,
+    /// The access was allowed by a profile in complain mode (`ALLOWED`).
+    Allowed,
+    /// The access was denied by a profile in enforce mode (`DENIED`).
+    Denied,
+    /// A hint, e.g. about a profile being attached (`HINT`).
+    Hint,
+    /// A status change such as a profile load or replace (`STATUS`).
+    Status,
+    /// An internal AppArmor error (`ERROR`).
+    Error,
+    /// The task was killed by a profile in kill mode (`KILLED`).
+    Kill,
+    /// A decision this version does not know, as written in the record.
+    Unknown(String),
+}
+
+/// The decoded AppArmor fields of an `AVC`, `USER_AVC` or kernel
+/// `APPARMOR_*` record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppArmorRecord {
+    /// The decision taken.
+    decision: AppArmorDecision,
+    /// The operation that was checked (e.g. `open`, `exec`,
+    /// `dbus_method_call`).
+    operation: Option<String>,
+    /// The profile that made the decision (e.g. `/usr/sbin/cupsd`).
+    profile: Option<String>,
+    /// The object of the operation, such as a file path.
+    name: Option<String>,
+    /// The permissions requested (e.g. `r`).
+    requested_mask: Option<String>,
+    /// The permissions denied (e.g. `r`).
+    denied_mask: Option<String>,
+}
+
+/// The fields of the nested `msg='...'` blob that PAM (and tools using
+/// libaudit's user message API) write in user-space records such as
+/// `USER_AUTH`, `USER_ACCT` and `USER_LOGIN`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PamRecord {
+    /// The operation, e.g. `PAM:authentication` or `login`.
+    op: String,
+    /// The PAM modules that granted the operation (`grantors`).
+    grantors: Option<String>,
+    /// The account the operation was for (`acct`).
+    acct: Option<String>,
+    /// The program that performed the operation (`exe`).
+    exe: Option<String>,
+    /// The remote host name (`hostname`).
+    hostname: Option<String>,
+    /// The remote address (`addr`).
+    addr: Option<String>,
+    /// The terminal or service, e.g. `ssh` or `/dev/pts/0` (`terminal`).
+    terminal: Option<String>,
+    /// Whether the operation succeeded (`res=success`) or failed
+    /// (`res=failed`); `None` if `res` is missing or unknown.
+    succeeded: Option<bool>,
+}
+
+/// The decision a fanotify permission listener returned, from the `resp=`
+/// field of a `FANOTIFY` record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FanotifyResponse {
+    /// The access was allowed (`FAN_ALLOW`).
+    Allow,
+    /// The access was denied (`FAN_DENY`).
+    Deny,
+    /// A response this version does not know, with the raw value.
+    Unknown(u32),
+}
+
+/// The decoded contents of a `FANOTIFY` record, which the kernel emits when a
+/// fanotify permission listener (such as an EDR agent) asks for its decision
+/// to be audited. The file is named by the event's `PATH` records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FanotifyRecord {
+    /// The listener's decision (`resp`).
+    response: FanotifyResponse,
+    /// The type of the extra information in `fan_info` (`fan_type`): `0` for
+    /// none, `1` for the number of the listener rule that decided.
+    fan_type: u32,
+    /// The extra information (`fan_info`, hex), absent when `fan_type` is
+    /// `0`.
+    fan_info: Option<u32>,
+    /// Whether the listener trusts the subject (`subj_trust`); `None` when
+    /// unknown.
+    subj_trust: Option<bool>,
+    /// Whether the listener trusts the object (`obj_trust`); `None` when
+    /// unknown.
+    obj_trust: Option<bool>,
+    /// The process that made the access (`pid`), if the record carries it.
+    pid: Option<u32>,
+}
+
+/// The decoded contents of a `KERN_MODULE` record, emitted with the syscall
+/// that loads or unloads a kernel module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernModuleRecord {
+    /// The module name (`name`), e.g. `nf_tables`.
+    name: String,
 }
 
 /// A parsed audit record.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Eq, Serialize, Deserialize)]
 pub struct ParsedAuditRecord {
     /// The type of the record.
     pub(crate) record_type: RecordType,
@@ -40,4 +285,51 @@ pub struct ParsedAuditRecord {
     pub(crate) serial: u16,
     /// The key-value pairs of the record (stored as strings).
     pub(crate) fields: std::collections::HashMap<String, String>,
+    /// Names of the fields whose value was double-quoted in the parsed line;
+    /// [`ParsedAuditRecord::get_quoted`] never hex-decodes these. Not
+    /// serialized, and empty for records built in code.
+    #[serde(skip)]
+    pub(crate) quoted: std::collections::HashSet<String>,
+    /// Names of the fields whose value was cut at
+    /// [`ParseOptions::max_field_len`] while parsing. Not serialized, and
+    /// empty for records built in code.
+    #[serde(skip)]
+    pub(crate) truncated: std::collections::HashSet<String>,
+    /// Names of the fields that came from inside the nested `msg='...'` blob
+    /// of a user-space record (see [`ParsedAuditRecord::pam`]). Not
+    /// serialized, and empty for records built in code.
+    #[serde(skip)]
+    pub(crate) nested: std::collections::HashSet<String>,
+}
+
+/// Turns one trimmed, non-empty input line into the records it holds; lets a
+/// [`RecordReader`] read line-based inputs other than auditd text.
+pub type LineParser = Box<dyn FnMut(&str) -> anyhow::Result<Vec<ParsedAuditRecord>>>;
+
+/// Streaming parser for auditd text logs: reads one line at a time from a
+/// `BufRead` and yields a `ParsedAuditRecord` per non-empty line, or the
+/// records a [`LineParser`] finds in it.
+pub struct RecordReader<R> {
+    /// The input.
+    reader: R,
+    /// Options each line is parsed with.
+    options: ParseOptions,
+    /// Parser used instead of `parse_line`, set with
+    /// `RecordReader::with_line_parser`.
+    line_parser: Option<LineParser>,
+    /// Byte offset of the next line in the input.
+    offset: u64,
+    /// Buffer the current line is read into.
+    line: Vec<u8>,
+    /// Records of the last line that were not yielded yet.
+    pending: std::vec::IntoIter<ParsedAuditRecord>,
+    /// Byte offset of the line the pending records come from.
+    pending_offset: u64,
+}
+
+/// A [`RecordReader`] that yields each record together with the byte offset
+/// its line starts at, created with `RecordReader::with_offsets`.
+pub struct OffsetRecordReader<R> {
+    /// The underlying reader.
+    inner: RecordReader<R>,
 }
@@ -0,0 +1,210 @@
+//! Decoding of the nested `msg='...'` blob in user-space audit records, as
+//! written by PAM and other tools that use libaudit's user message API:
+//!
+//! ```text
+//! type=USER_AUTH msg=audit(...): pid=2101 uid=0 auid=4294967295 ses=4294967295
+//!   msg='op=PAM:authentication grantors=? acct="root" exe="/usr/sbin/sshd"
+//!   hostname=1.2.3.4 addr=1.2.3.4 terminal=ssh res=failed'
+//! ```
+//!
+//! The parser splits the blob like the rest of the payload, so its first pair
+//! ends up in the `msg` field with the opening quote (`'op=PAM:...`) and its
+//! last value keeps the closing quote (`failed'`). Both are undone here. Only
+//! the fields the parser found inside the blob are read, never the outer
+//! fields of the record.
+
+use crate::core::parser::parser::{decode_string_field, parse_success};
+use crate::core::parser::{PamRecord, ParsedAuditRecord};
+
+/// Record type numbers reserved for user-space messages.
+const USER_MSG_TYPES: std::ops::RangeInclusive<u16> = 1100..=1199;
+
+impl PamRecord {
+    /// The operation, e.g. `PAM:authentication`.
+    pub fn op(&self) -> &str {
+        &self.op
+    }
+
+    /// The PAM modules that granted the operation, e.g. `pam_unix`.
+    pub fn grantors(&self) -> Option<&str> {
+        self.grantors.as_deref()
+    }
+
+    /// The account the operation was for, e.g. `root`.
+    pub fn acct(&self) -> Option<&str> {
+        self.acct.as_deref()
+    }
+
+    /// The program that performed the operation, e.g. `/usr/sbin/sshd`.
+    pub fn exe(&self) -> Option<&str> {
+        self.exe.as_deref()
+    }
+
+    /// The remote host name.
+    pub fn hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    /// The remote address.
+    pub fn addr(&self) -> Option<&str> {
+        self.addr.as_deref()
+    }
+
+    /// The terminal or service, e.g. `ssh`.
+    pub fn terminal(&self) -> Option<&str> {
+        self.terminal.as_deref()
+    }
+
+    /// Whether the operation succeeded; `None` if the record has no known
+    /// `res` value.
+    pub fn succeeded(&self) -> Option<bool> {
+        self.succeeded
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Decodes the nested `msg='...'` fields of a user-space record. Returns
+    /// `None` for kernel records and for user-space records without an `op`
+    /// (e.g. systemd's `msg='unit=...'`).
+    ///
+    /// Unknown values (`?`) are `None`; hex-encoded `acct` and `exe` values
+    /// are decoded.
+    pub fn pam(&self) -> Option<PamRecord> {
+        if !USER_MSG_TYPES.contains(&u16::from(self.record_type)) {
+            return None;
+        }
+        let field = |name: &str| self.nested_field(name);
+        let decoded = |name: &str| {
+            self.nested_value(name).map(|(value, quoted)| {
+                if quoted {
+                    value
+                } else {
+                    decode_string_field(&value).into_owned()
+                }
+            })
+        };
+        Some(PamRecord {
+            op: field("op")?,
+            grantors: field("grantors"),
+            acct: decoded("acct"),
+            exe: decoded("exe"),
+            hostname: field("hostname"),
+            addr: field("addr"),
+            terminal: field("terminal"),
+            succeeded: field("res").and_then(|res| parse_success(&res)),
+        })
+    }
+
+    /// Returns a field of the nested `msg='...'` blob without the blob's
+    /// quotes or its own double quotes, or `None` if it is missing or `?`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `name`: The nested field name, e.g. `acct`.
+    fn nested_field(&self, name: &str) -> Option<String> {
+        self.nested_value(name).map(|(value, _)| value)
+    }
+
+    /// Like [`Self::nested_field`], but also returns whether the value was
+    /// double-quoted (and so is never hex-encoded).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `name`: The nested field name, e.g. `acct`.
+    fn nested_value(&self, name: &str) -> Option<(String, bool)> {
+        let first = self
+            .fields
+            .get("msg")
+            .and_then(|msg| msg.strip_prefix('\''))
+            .and_then(|pair| pair.split_once('='));
+        let (value, quoted) = match first {
+            Some((key, value)) if key == name => (value, false),
+            _ if self.nested.contains(name) => {
+                (self.fields.get(name)?.as_str(), self.quoted.contains(name))
+            }
+            _ => return None,
+        };
+        let value = value.strip_suffix('\'').unwrap_or(value);
+        let quoted = quoted || value.starts_with('"');
+        let value = value.trim_matches('"');
+        (value != "?").then(|| (value.to_string(), quoted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::parser::ParseOptions;
+    use crate::core::parser::parser::parse_line;
+
+    const PAM_FAILURE: &str = "type=USER_AUTH msg=audit(1700000000.000:300): pid=2101 uid=0 \
+                               auid=4294967295 ses=4294967295 \
+                               subj=system_u:system_r:sshd_t:s0-s0:c0.c1023 \
+                               msg='op=PAM:authentication grantors=? acct=\"root\" \
+                               exe=\"/usr/sbin/sshd\" hostname=1.2.3.4 addr=1.2.3.4 \
+                               terminal=ssh res=failed'";
+
+    #[test]
+    fn decodes_pam_failure() {
+        let record = parse_line(PAM_FAILURE, &ParseOptions::default()).unwrap();
+        let pam = record.pam().unwrap();
+
+        assert_eq!(pam.op(), "PAM:authentication");
+        assert_eq!(pam.grantors(), None);
+        assert_eq!(pam.acct(), Some("root"));
+        assert_eq!(pam.exe(), Some("/usr/sbin/sshd"));
+        assert_eq!(pam.hostname(), Some("1.2.3.4"));
+        assert_eq!(pam.addr(), Some("1.2.3.4"));
+        assert_eq!(pam.terminal(), Some("ssh"));
+        assert_eq!(pam.succeeded(), Some(false));
+    }
+
+    #[test]
+    fn decodes_hex_account_and_quoted_last_field() {
+        let line = "type=USER_LOGIN msg=audit(1700000001.000:301): pid=2102 uid=0 \
+                    auid=1000 ses=3 msg='op=login id=1000 acct=6A6F686E20646F65 \
+                    exe=\"/usr/sbin/sshd\" terminal=\"/dev/pts/0\" res=\"success\"'";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+        let pam = record.pam().unwrap();
+
+        assert_eq!(pam.op(), "login");
+        assert_eq!(pam.acct(), Some("john doe"));
+        assert_eq!(pam.terminal(), Some("/dev/pts/0"));
+        assert_eq!(pam.succeeded(), Some(true));
+
+        let syscall = parse_line(
+            "type=SYSCALL msg=audit(1700000001.000:302): syscall=59 op=x",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(syscall.pam(), None);
+    }
+
+    #[test]
+    fn quoted_account_is_not_hex_decoded() {
+        let line = "type=USER_AUTH msg=audit(1700000002.000:303): pid=2103 uid=0 \
+                    msg='op=PAM:authentication acct=\"CAFE\" exe=\"/usr/sbin/sshd\" res=success'";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+
+        assert_eq!(record.pam().unwrap().acct(), Some("CAFE"));
+    }
+
+    #[test]
+    fn outer_fields_are_not_read_as_nested_fields() {
+        let line = "type=USER_CMD msg=audit(1700000003.000:304): pid=2104 uid=0 auid=1000 \
+                    terminal=pts/0 msg='op=sudo res=success' exe=\"/usr/bin/sudo\"";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+        let pam = record.pam().unwrap();
+
+        assert_eq!(pam.op(), "sudo");
+        assert_eq!(pam.succeeded(), Some(true));
+        assert_eq!(pam.terminal(), None);
+        assert_eq!(pam.exe(), None);
+
+        let single = parse_line(
+            "type=USER_CMD msg=audit(1700000003.000:305): pid=2105 msg='op=sudo' res=success",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(single.pam().unwrap().succeeded(), None);
+    }
+}
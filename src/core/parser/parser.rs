@@ -12,13 +12,32 @@ use nom::{
     Parser,
     bytes::complete::{tag, take_while1},
     character::complete::{char, space1},
+    combinator::recognize,
 };
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 use std::time::SystemTime;
 
 use crate::core::netlink::RawAuditRecord;
-use crate::core::parser::{ParsedAuditRecord, RecordData};
-use crate::utils::timestamp_string_to_systemtime;
+use crate::core::parser::{
+    BorrowedRecord,
+    ParseError,
+    ParseOptions,
+    ParsedAuditRecord,
+    PayloadFields,
+    RecordData,
+    RecordType,
+};
+use crate::utils::{escape_bytes, timestamp_string_to_systemtime};
+
+/// Default for [`ParseOptions::max_field_len`]: 64 KiB, far above any value
+/// the kernel emits (a whole audit message is under 9 KiB).
+pub const DEFAULT_MAX_FIELD_LEN: usize = 64 * 1024;
+
+/// Appended to a field value that was cut at [`ParseOptions::max_field_len`].
+pub const TRUNCATED_MARKER: &str = "...[truncated]";
 
 impl ParsedAuditRecord {
     /// Returns the `(timestamp, serial)` pair that uniquely identifies the
@@ -31,6 +50,122 @@ impl ParsedAuditRecord {
     pub fn identifier(&self) -> (SystemTime, u16) {
         (self.timestamp, self.serial)
     }
+
+    /// Returns the type of the record.
+    pub fn record_type(&self) -> RecordType {
+        self.record_type
+    }
+
+    /// Returns the timestamp of the record.
+    pub fn timestamp(&self) -> SystemTime {
+        self.timestamp
+    }
+
+    /// Returns the serial number of the record.
+    pub fn serial(&self) -> u16 {
+        self.serial
+    }
+
+    /// Returns the value of a field as stored (quotes already removed).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `key`: The field name, e.g. `"syscall"`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields.get(key).map(String::as_str)
+    }
+
+    /// Returns the value of a field parsed as a decimal `u64`, or `None` if
+    /// the field is missing or not a decimal number. Hex fields such as
+    /// `a0`-`a3` or `arch` must be read with [`ParsedAuditRecord::get`].
+    ///
+    /// **Parameters:**
+    ///
+    /// * `key`: The field name, e.g. `"pid"`.
+    pub fn get_u64(&self, key: &str) -> Option<u64> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// Returns the text of an auditd string field (`comm`, `exe`, `name`,
+    /// `proctitle`, ...). The kernel writes such values either quoted or, when
+    /// they contain spaces or special characters, hex-encoded; hex-encoded
+    /// values are decoded, with control characters and bytes that are not
+    /// valid UTF-8 escaped (see [`escape_bytes`]).
+    ///
+    /// A value that was quoted in the parsed line is returned as it is, so a
+    /// quoted `comm="1234"` is not mistaken for hex. An unquoted value (or one
+    /// of a record that was not parsed from text, e.g. read from JSON) is
+    /// treated as hex-encoded when it is a non-empty, even-length string of
+    /// uppercase hex digits.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `key`: The field name, e.g. `"comm"`.
+    pub fn get_quoted(&self, key: &str) -> Option<Cow<'_, str>> {
+        let value = self.get(key)?;
+        Some(if self.quoted.contains(key) {
+            Cow::Borrowed(value)
+        } else {
+            decode_string_field(value)
+        })
+    }
+
+    /// Returns the field names of the record, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields.keys().map(String::as_str)
+    }
+
+    /// Returns the number of fields in the record.
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    /// Returns whether the record has no fields.
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Returns whether the operation the record describes succeeded, from
+    /// `success=yes|no` (syscalls) or `res=success|failed` (user-space
+    /// records), where `true`, `false`, `1` and `0` are accepted too and case
+    /// is ignored. `None` if the record has neither field or only unknown
+    /// values.
+    pub fn succeeded(&self) -> Option<bool> {
+        ["success", "res"]
+            .iter()
+            .find_map(|key| self.get(key).and_then(parse_success))
+    }
+
+    /// Returns an estimate of the heap and inline memory the record uses, in
+    /// bytes: the struct itself plus, per field, its key, its value and the
+    /// map entry. Used to keep buffered records within a memory budget.
+    pub fn estimated_size(&self) -> usize {
+        let entry = 2 * std::mem::size_of::<String>();
+        std::mem::size_of::<Self>()
+            + self
+                .fields
+                .iter()
+                .map(|(key, value)| entry + key.len() + value.len())
+                .sum::<usize>()
+    }
+
+    /// Returns the underlying field map, for access the typed getters do not
+    /// cover.
+    pub fn fields(&self) -> &HashMap<String, String> {
+        &self.fields
+    }
+
+    /// Returns the underlying field map mutably, e.g. to add derived fields.
+    pub fn fields_mut(&mut self) -> &mut HashMap<String, String> {
+        &mut self.fields
+    }
+
+    /// Returns how many field values were cut at
+    /// [`ParseOptions::max_field_len`] while parsing (those values end with
+    /// [`TRUNCATED_MARKER`]).
+    pub fn truncated_fields(&self) -> usize {
+        self.truncated.len()
+    }
 }
 
 impl TryFrom<RawAuditRecord> for ParsedAuditRecord {
@@ -41,12 +176,62 @@ impl TryFrom<RawAuditRecord> for ParsedAuditRecord {
     /// The conversion understands the standard Linux audit header of
     /// the form:
     ///
-    /// `audit(<seconds>.<millis>:<serial>): key1=val1 key2="val 2" ...`
+    /// `audit(<seconds>.<fraction>:<serial>): key1=val1 key2="val 2" ...`
     ///
     /// The header is parsed with `nom` and the remaining key–value
     /// payload is stored in the `fields` map.
     fn try_from(raw_record: RawAuditRecord) -> Result<Self, Self::Error> {
-        parse_audit_message(&raw_record.data)
+        ParsedAuditRecord::from_raw(raw_record, &ParseOptions::default())
+    }
+}
+
+/// Records are equal when their type, timestamp, serial and fields are;
+/// which values were quoted in the source line does not matter.
+impl PartialEq for ParsedAuditRecord {
+    fn eq(&self, other: &Self) -> bool {
+        self.record_type == other.record_type
+            && self.timestamp == other.timestamp
+            && self.serial == other.serial
+            && self.fields == other.fields
+    }
+}
+
+/// Leaves out the parse bookkeeping (`quoted`, `truncated` and `nested`), like
+/// [`ParsedAuditRecord::simple_string`].
+impl fmt::Debug for ParsedAuditRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParsedAuditRecord")
+            .field("record_type", &self.record_type)
+            .field("timestamp", &self.timestamp)
+            .field("serial", &self.serial)
+            .field("fields", &self.fields)
+            .finish()
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Formats the record as written in the simple log format:
+    /// `ParsedAuditRecord { record_type: .., timestamp: .., serial: ..,
+    /// fields: {..} }`. `parse_simple_events` reads this back, so the layout
+    /// must not change; the parse bookkeeping (`quoted`, `truncated` and
+    /// `nested`) is not written.
+    pub(crate) fn simple_string(&self) -> String {
+        format!(
+            "ParsedAuditRecord {{ record_type: {:?}, timestamp: {:?}, serial: {}, fields: {:?} }}",
+            self.record_type, self.timestamp, self.serial, self.fields
+        )
+    }
+
+    /// Parses a `RawAuditRecord` like `TryFrom`, capping field values at
+    /// `options.max_field_len`. The other options only apply to full log
+    /// lines (see [`parse_line`]).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `raw_record`: The record to parse.
+    /// * `options`: The field value cap to apply.
+    pub fn from_raw(raw_record: RawAuditRecord, options: &ParseOptions) -> anyhow::Result<Self> {
+        parse_audit_message(&raw_record.data, options.max_field_len)
             .finish()
             .map(|(_, record_data)| {
                 ParsedAuditRecord {
@@ -54,111 +239,501 @@ impl TryFrom<RawAuditRecord> for ParsedAuditRecord {
                     timestamp: record_data.timestamp,
                     serial: record_data.serial.parse::<u16>().unwrap_or(0),
                     fields: record_data.fields,
+                    quoted: record_data.quoted,
+                    truncated: record_data.truncated,
+                    nested: record_data.nested,
                 }
             })
             .map_err(|e| anyhow::anyhow!("Failed to parse audit message: {:?}", e))
     }
 }
 
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            max_field_len: DEFAULT_MAX_FIELD_LEN,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Options that tolerate missing or unrecognized `type=` fields.
+    pub fn lenient() -> Self {
+        Self {
+            strict: false,
+            ..Self::default()
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingType => write!(f, "line does not start with type=<RECORD_TYPE>"),
+            ParseError::MissingMsg => {
+                write!(f, "missing msg=audit(<timestamp>:<serial>) after type")
+            }
+            ParseError::UnknownType(name) => write!(f, "unknown record type {:?}", name),
+            ParseError::InvalidMessage(reason) => write!(f, "invalid audit message: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parses a full audit log line as written by auditd or the auditrs legacy
+/// writer: `type=<RECORD_TYPE> msg=audit(<timestamp>:<serial>): key=value ...`.
+///
+/// The two leading fields are validated before the payload is parsed so that
+/// non-audit input produces a specific [`ParseError`] rather than a generic
+/// failure.
+///
+/// **Parameters:**
+///
+/// * `line`: The log line to parse.
+/// * `options`: How strictly the leading `type=`/`msg=` fields are validated,
+///   and the field value cap.
+pub fn parse_line(line: &str, options: &ParseOptions) -> Result<ParsedAuditRecord, ParseError> {
+    let (record_type, data) = split_line(line, options)?;
+    ParsedAuditRecord::from_raw(
+        RawAuditRecord::new(u16::from(record_type), data.to_string()),
+        options,
+    )
+    .map(|mut record| {
+        // Keep the parsed type rather than round-tripping through the u16
+        // id, which cannot represent every `Unknown` value.
+        record.record_type = record_type;
+        record
+    })
+    .map_err(|e| ParseError::InvalidMessage(e.to_string()))
+}
+
+/// Splits a full audit log line into the [`RawAuditRecord`] the kernel would
+/// have delivered for it, validating the leading fields like [`parse_line`]
+/// but leaving the payload unparsed. Used by transports that read text logs
+/// and hand records to the parser task.
+///
+/// **Parameters:**
+///
+/// * `line`: The log line to split.
+/// * `options`: How strictly the leading `type=`/`msg=` fields are validated.
+pub fn parse_raw_line(line: &str, options: &ParseOptions) -> Result<RawAuditRecord, ParseError> {
+    let (record_type, data) = split_line(line, options)?;
+    Ok(RawAuditRecord::new(
+        u16::from(record_type),
+        data.to_string(),
+    ))
+}
+
+/// Parses a full audit log line like [`parse_line`] with strict
+/// [`ParseOptions`], but without allocating: the fields of the returned
+/// [`BorrowedRecord`] are slices of `line`. Use it for read-only analysis
+/// (counting, filtering); pipeline code that keeps records uses
+/// [`parse_line`].
+///
+/// **Parameters:**
+///
+/// * `line`: The log line to parse.
+pub fn parse_line_borrowed(line: &str) -> Result<BorrowedRecord<'_>, ParseError> {
+    let (record_type, data) = split_line(line, &ParseOptions::default())?;
+    let (payload, (timestamp, serial)) = parse_audit_header(data)
+        .finish()
+        .map_err(|e| ParseError::InvalidMessage(format!("{:?}", e)))?;
+    let timestamp = timestamp_string_to_systemtime(timestamp)
+        .map_err(|e| ParseError::InvalidMessage(e.to_string()))?;
+    Ok(BorrowedRecord {
+        record_type,
+        timestamp,
+        serial: serial.parse::<u16>().unwrap_or(0),
+        payload,
+    })
+}
+
+impl<'a> BorrowedRecord<'a> {
+    /// Returns the `key=value` pairs of the record in line order, with
+    /// double quotes around values removed. Skips the same malformed tokens
+    /// as [`read_to_fields`].
+    pub fn fields(&self) -> impl Iterator<Item = (&'a str, &'a str)> + use<'a> {
+        payload_fields(self.payload)
+    }
+
+    /// Returns the value of a field (the last one, if the key repeats, as in
+    /// [`ParsedAuditRecord::get`]).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `key`: The field name, e.g. `"syscall"`.
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.fields()
+            .filter(|(k, _)| *k == key)
+            .last()
+            .map(|(_, v)| v)
+    }
+
+    /// Returns the unparsed `key=value ...` payload after the header.
+    pub fn payload(&self) -> &'a str {
+        self.payload
+    }
+}
+
+/// Splits the leading `node=<name>` fields that auditd writes when
+/// `name_format` is set off a log line, returning the last node name, if any,
+/// and the rest of the line.
+///
+/// **Parameters:**
+///
+/// * `line`: The log line to split.
+pub fn split_node(line: &str) -> (Option<&str>, &str) {
+    let mut line = line.trim();
+    let mut node = None;
+    while let Some(rest) = line.strip_prefix("node=") {
+        let (name, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        node = Some(name);
+        line = rest.trim_start();
+    }
+    (node, line)
+}
+
+/// Splits a full audit log line into its record type and the
+/// `audit(<timestamp>:<serial>): ...` message, validating the leading
+/// `type=`/`msg=` fields according to `options`.
+///
+/// **Parameters:**
+///
+/// * `line`: The log line to split.
+/// * `options`: How strictly the leading `type=`/`msg=` fields are validated.
+fn split_line<'a>(
+    line: &'a str,
+    options: &ParseOptions,
+) -> Result<(RecordType, &'a str), ParseError> {
+    let line = split_node(line).1;
+
+    let (record_type, msg) = match line.strip_prefix("type=") {
+        Some(rest) => {
+            let (type_str, msg) = rest.split_once(' ').ok_or(ParseError::MissingMsg)?;
+            let record_type = if options.strict {
+                RecordType::from_str(type_str)
+                    .map_err(|_| ParseError::UnknownType(type_str.to_string()))?
+            } else {
+                RecordType::from_audit_str(type_str)
+            };
+            (record_type, msg.trim_start())
+        }
+        None if options.strict => return Err(ParseError::MissingType),
+        None => (RecordType::Unknown(0), line),
+    };
+
+    let data = msg
+        .strip_prefix("msg=")
+        .filter(|data| data.starts_with("audit("))
+        .ok_or(ParseError::MissingMsg)?;
+    Ok((record_type, data))
+}
+
 /// Parses a single audit message line into `RecordData`.
 ///
 /// The expected format is the canonical Linux audit prefix followed by
 /// a space and an opaque key–value payload:
 ///
-/// `audit(<seconds>.<millis>:<serial>): key1=val1 key2="val 2" ...`
+/// `audit(<seconds>.<fraction>:<serial>): key1=val1 key2="val 2" ...`
 ///
 /// The timestamp is converted into a `SystemTime`, the serial is stored
 /// as a string, and the remaining payload is parsed into key–value
-/// pairs stored directly in the `fields` map.
-fn parse_audit_message(input: &str) -> IResult<&str, RecordData> {
+/// pairs stored directly in the `fields` map, with values capped at
+/// `max_field_len` bytes.
+fn parse_audit_message(input: &str, max_field_len: usize) -> IResult<&str, RecordData> {
+    let (input, (timestamp_str, serial)) = parse_audit_header(input)?;
+
+    // Now parse the rest of the line as key-value pairs
+    // Brute implementation: put everything into a single "kv" field.
+    // There will only be one line in the payload, so we can just take until the end
+    // of the line
+    let (input, kvs) = nom::combinator::rest(input)?;
+    let PayloadFields {
+        fields,
+        quoted,
+        truncated,
+        nested,
+    } = read_payload(kvs, max_field_len);
+
+    // Out-of-range timestamps (e.g. more digits than fit in a u64) are a parse
+    // failure rather than a panic.
+    let timestamp = timestamp_string_to_systemtime(timestamp_str).map_err(|_| {
+        nom::Err::Error(nom::error::Error::new(
+            timestamp_str,
+            nom::error::ErrorKind::Verify,
+        ))
+    })?;
+    let serial = serial.to_string();
+
+    let parsed_record = RecordData {
+        timestamp,
+        serial,
+        fields,
+        quoted,
+        truncated,
+        nested,
+    };
+    Ok((input, parsed_record))
+}
+
+/// Parses the `audit(<seconds>.<fraction>:<serial>): ` header (including the
+/// space after it), returning the timestamp and serial text.
+///
+/// **Parameters:**
+///
+/// * `input`: The audit message, starting at `audit(`.
+fn parse_audit_header(input: &str) -> IResult<&str, (&str, &str)> {
     // Basic parsers
     let audit_tag = tag("audit(");
     let timestamp_digits = take_while1(|c: char| c.is_ascii_digit());
     let timestamp_milis = take_while1(|c: char| c.is_ascii_digit());
-    let timestamp = (timestamp_digits, char('.'), timestamp_milis);
+    let timestamp = recognize((timestamp_digits, char('.'), timestamp_milis));
     let serial_digits = take_while1(|c: char| c.is_ascii_digit());
 
-    // Parse the header: 'audit(1234567890.123:456):'
-    let (input, (_, timestamp_tuple, _, serial, _, _)) = (
+    // Parse the header: 'audit(1234567890.123:456): '
+    let (input, (_, timestamp, _, serial, _, _, _)) = (
         audit_tag,
         timestamp,
         char(':'),
         serial_digits,
         char(')'),
         char(':'),
+        space1,
     )
-        .parse(input)?; // does not parse the trailing ' '.
+        .parse(input)?;
+    Ok((input, (timestamp, serial)))
+}
 
-    // Now parse the rest of the line as key-value pairs
-    // Brute implementation: put everything into a single "kv" field.
-    // There will only be one line in the payload, so we can just take until the end
-    // of the line
+/// Separators auditd writes between a record's raw fields and the enriched
+/// companion fields it appends in `log_format = ENRICHED` (`\x1d`, and `\x1c`
+/// written by some tools).
+pub const ENRICHED_SEPARATORS: [char; 2] = ['\x1d', '\x1c'];
 
-    let (input, _) = space1(input)?; // consume the space after the header
+/// Returns whether `key` names an enriched companion field (`AUID`, `ARCH`,
+/// `SYSCALL`, ...). auditd writes these in uppercase while kernel field names
+/// are lowercase.
+///
+/// **Parameters:**
+///
+/// * `key`: The field name.
+pub fn is_enriched_field(key: &str) -> bool {
+    key.starts_with(|c: char| c.is_ascii_uppercase())
+        && key
+            .bytes()
+            .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit() || b == b'_')
+}
 
-    let (input, kvs) = nom::combinator::rest(input)?;
-    let mut fields = HashMap::new();
-    // Parse key–value pairs of the form:
-    // key=value key2="val 2 with spaces"
-    let mut chars = kvs.chars().peekable();
-    while chars.peek().is_some() {
-        let mut key = String::new();
-        while let Some(&c) = chars.peek() {
-            if c == '=' {
-                chars.next();
-                break;
-            }
-            key.push(c);
-            chars.next();
-        }
+/// Maps a `success` or `res` value to whether the operation succeeded, as
+/// described for [`ParsedAuditRecord::succeeded`].
+///
+/// **Parameters:**
+///
+/// * `value`: The field value, without quotes.
+pub(crate) fn parse_success(value: &str) -> Option<bool> {
+    let is = |names: &[&str]| names.iter().any(|name| value.eq_ignore_ascii_case(name));
+    if is(&["yes", "success", "true", "1"]) {
+        Some(true)
+    } else if is(&["no", "failed", "false", "0"]) {
+        Some(false)
+    } else {
+        None
+    }
+}
 
-        let mut value = String::new();
-        if let Some(&'"') = chars.peek() {
-            chars.next();
-            while let Some(c) = chars.next() {
-                if c == '"' {
-                    break;
-                }
-                value.push(c);
-            }
+/// Decodes the value of an auditd string field as described for
+/// [`ParsedAuditRecord::get_quoted`].
+///
+/// **Parameters:**
+///
+/// * `value`: The field value, without quotes.
+pub(crate) fn decode_string_field(value: &str) -> Cow<'_, str> {
+    let is_hex = value.len().is_multiple_of(2) && is_hex_encoded(value);
+    match is_hex.then(|| hex::decode(value)) {
+        Some(Ok(bytes)) => Cow::Owned(escape_bytes(&bytes)),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+/// Returns whether `value` is non-empty and made of the uppercase hex digits
+/// the kernel encodes untrusted strings with.
+///
+/// **Parameters:**
+///
+/// * `value`: The raw field value.
+fn is_hex_encoded(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'A'..=b'F'))
+}
+
+/// Parses the key–value payload that follows an audit header into a field
+/// map.
+///
+/// Pairs have the form `key=value` or `key="value with spaces"`; double quotes
+/// around a value are removed. Enriched companion fields after an
+/// [`ENRICHED_SEPARATORS`] character are stored like the others (see
+/// [`is_enriched_field`]). Pairs with an empty key and tokens without an
+/// `=` are skipped. Each token is split with `split_once('=')` rather than by
+/// byte index, so multibyte text on either side of the separator is safe.
+///
+/// Values longer than `max_field_len` bytes keep their first `max_field_len`
+/// bytes (cut back to a character boundary) followed by [`TRUNCATED_MARKER`].
+/// Hex-encoded values are cut to an even length, so the kept part still
+/// decodes.
+///
+/// **Parameters:**
+///
+/// * `kvs`: The payload after `audit(<timestamp>:<serial>): `.
+/// * `max_field_len`: The longest value kept, in bytes.
+pub fn read_to_fields(kvs: &str, max_field_len: usize) -> HashMap<String, String> {
+    read_payload(kvs, max_field_len).fields
+}
+
+/// Parses a key–value payload like [`read_to_fields`], also recording which
+/// values were double-quoted, which were truncated and which came from inside
+/// a `msg='...'` blob.
+///
+/// A user-space record's blob is split like the rest of the payload: its
+/// first pair stays in the `msg` value after the opening quote, and the
+/// others become fields of their own up to the value ending with the closing
+/// quote.
+///
+/// **Parameters:**
+///
+/// * `kvs`: The payload after `audit(<timestamp>:<serial>): `.
+/// * `max_field_len`: The longest value kept, in bytes.
+pub(crate) fn read_payload(kvs: &str, max_field_len: usize) -> PayloadFields {
+    let mut payload = PayloadFields::default();
+    let mut in_blob = false;
+    for (key, value, quoted) in payload_pairs(kvs) {
+        if quoted {
+            payload.quoted.insert(key.to_string());
         } else {
-            while let Some(&c) = chars.peek() {
-                if c == ' ' {
-                    break;
-                }
-                value.push(c);
-                chars.next();
-            }
+            payload.quoted.remove(key);
         }
-
-        if !key.is_empty() {
-            fields.insert(key.trim().to_string(), value);
+        if in_blob {
+            payload.nested.insert(key.to_string());
+            in_blob = !value.ends_with('\'');
+        } else {
+            payload.nested.remove(key);
+            in_blob = key == "msg"
+                && !quoted
+                && value
+                    .strip_prefix('\'')
+                    .is_some_and(|rest| !rest.ends_with('\''));
         }
-
-        while let Some(&c) = chars.peek() {
-            if !c.is_whitespace() {
-                break;
+        match capped_value(value, max_field_len, quoted) {
+            Cow::Borrowed(value) => {
+                payload.truncated.remove(key);
+                payload.fields.insert(key.to_string(), value.to_string());
+            }
+            Cow::Owned(capped) => {
+                payload.truncated.insert(key.to_string());
+                payload.fields.insert(key.to_string(), capped);
             }
-            chars.next();
         }
     }
+    payload
+}
 
-    let timestamp =
-        timestamp_string_to_systemtime(&format!("{}.{}", timestamp_tuple.0, timestamp_tuple.2))
-            .unwrap();
-    let serial = serial.to_string();
+/// Returns `value`, or an owned copy truncated with [`TRUNCATED_MARKER`] if
+/// it is longer than `max_len` bytes. The cut is moved back to a character
+/// boundary, and to an even length for unquoted hex-encoded values.
+///
+/// **Parameters:**
+///
+/// * `value`: The field value.
+/// * `max_len`: The longest value kept, in bytes.
+/// * `quoted`: Whether the value was double-quoted, and so is not hex.
+fn capped_value(value: &str, max_len: usize, quoted: bool) -> Cow<'_, str> {
+    if value.len() <= max_len {
+        return Cow::Borrowed(value);
+    }
+    let mut end = max_len;
+    if !quoted && is_hex_encoded(value) {
+        end -= end % 2;
+    }
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    let mut capped = String::with_capacity(end + TRUNCATED_MARKER.len());
+    capped.push_str(&value[..end]);
+    capped.push_str(TRUNCATED_MARKER);
+    Cow::Owned(capped)
+}
 
-    let parsed_record = RecordData {
-        timestamp,
-        serial,
-        fields,
-    };
-    Ok((input, parsed_record))
+/// Returns the `(key, value)` pairs of a key–value payload as slices of it,
+/// in order, with the rules described in [`read_to_fields`].
+///
+/// **Parameters:**
+///
+/// * `kvs`: The payload after `audit(<timestamp>:<serial>): `.
+fn payload_fields(kvs: &str) -> impl Iterator<Item = (&str, &str)> {
+    payload_pairs(kvs).map(|(key, value, _)| (key, value))
+}
+
+/// Returns the `(key, value, quoted)` triples of a key–value payload, like
+/// [`payload_fields`], where `quoted` tells whether the value was enclosed in
+/// double quotes.
+///
+/// **Parameters:**
+///
+/// * `kvs`: The payload after `audit(<timestamp>:<serial>): `.
+fn payload_pairs(kvs: &str) -> impl Iterator<Item = (&str, &str, bool)> {
+    PayloadTokens { rest: kvs }.filter_map(|token| {
+        let (key, value) = token.split_once('=')?;
+        let key = key.trim();
+        if key.is_empty() {
+            return None;
+        }
+        Some(match value.strip_prefix('"') {
+            Some(quoted) => (key, quoted.strip_suffix('"').unwrap_or(quoted), true),
+            None => (key, value, false),
+        })
+    })
+}
+
+/// Splits a key–value payload on whitespace and enriched separators that are
+/// not inside double quotes.
+struct PayloadTokens<'a> {
+    /// The part of the payload not yet split.
+    rest: &'a str,
+}
+
+impl<'a> Iterator for PayloadTokens<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let start = self.rest.find(|c| !is_token_separator(c))?;
+        let rest = &self.rest[start..];
+        let mut in_quotes = false;
+        let end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                !in_quotes && is_token_separator(c)
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        self.rest = &rest[end..];
+        Some(&rest[..end])
+    }
+}
+
+/// Returns whether `c` separates payload tokens.
+fn is_token_separator(c: char) -> bool {
+    c.is_whitespace() || ENRICHED_SEPARATORS.contains(&c)
 }
 
 // tests
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
     use crate::core::parser::RecordType;
 
     use super::*;
@@ -176,9 +751,12 @@ mod tests {
                 map.insert("key1".to_string(), "value".to_string());
                 map
             },
+            quoted: HashSet::new(),
+            truncated: HashSet::new(),
+            nested: HashSet::new(),
         };
 
-        let result = parse_audit_message(input);
+        let result = parse_audit_message(input, DEFAULT_MAX_FIELD_LEN);
         assert!(result.is_ok(), "Parsing failed: {:?}", result);
         let (remaining, parsed) = result.unwrap();
         assert_eq!(remaining, "");
@@ -211,6 +789,9 @@ mod tests {
             timestamp: timestamp_string_to_systemtime("1234567890.123").unwrap(),
             serial: 456,
             fields: HashMap::from([("key1".to_string(), "value".to_string())]),
+            quoted: Default::default(),
+            truncated: Default::default(),
+            nested: Default::default(),
         };
         assert_eq!(
             parsed_record.identifier(),
@@ -221,7 +802,7 @@ mod tests {
     #[test]
     fn parse_audit_message_quoted_value_with_spaces() {
         let input = r#"audit(1234567890.123:1): msg="hello world""#;
-        let (_, parsed) = parse_audit_message(input).unwrap();
+        let (_, parsed) = parse_audit_message(input, DEFAULT_MAX_FIELD_LEN).unwrap();
         assert_eq!(
             parsed.fields.get("msg").map(String::as_str),
             Some("hello world")
@@ -231,7 +812,7 @@ mod tests {
     #[test]
     fn parse_audit_message_multiple_key_value_pairs() {
         let input = "audit(1234567890.123:2): a=1 b=two c=three";
-        let (_, parsed) = parse_audit_message(input).unwrap();
+        let (_, parsed) = parse_audit_message(input, DEFAULT_MAX_FIELD_LEN).unwrap();
         assert_eq!(
             parsed.fields,
             HashMap::from([
@@ -246,7 +827,7 @@ mod tests {
     fn parse_audit_message_skips_empty_key_before_equals() {
         // Leading `=foo` yields an empty key for the first pair and is skipped.
         let input = "audit(1234567890.123:3): =skipped key1=kept";
-        let (_, parsed) = parse_audit_message(input).unwrap();
+        let (_, parsed) = parse_audit_message(input, DEFAULT_MAX_FIELD_LEN).unwrap();
         assert_eq!(
             parsed.fields,
             HashMap::from([("key1".to_string(), "kept".to_string())])
@@ -255,12 +836,12 @@ mod tests {
 
     #[test]
     fn parse_audit_message_rejects_invalid_prefix() {
-        assert!(parse_audit_message("not_audit(1.2:3): k=v").is_err());
+        assert!(parse_audit_message("not_audit(1.2:3): k=v", DEFAULT_MAX_FIELD_LEN).is_err());
     }
 
     #[test]
     fn parse_audit_message_requires_space_after_header() {
-        assert!(parse_audit_message("audit(1234567890.123:4):k=v").is_err());
+        assert!(parse_audit_message("audit(1234567890.123:4):k=v", DEFAULT_MAX_FIELD_LEN).is_err());
     }
 
     #[test]
@@ -269,6 +850,303 @@ mod tests {
         assert!(ParsedAuditRecord::try_from(raw).is_err());
     }
 
+    #[test]
+    fn parse_audit_message_rejects_out_of_range_timestamp() {
+        // Seconds overflow a u64; this used to panic on unwrap.
+        assert!(
+            parse_audit_message(
+                "audit(99999999999999999999999.123:1): k=v",
+                DEFAULT_MAX_FIELD_LEN
+            )
+            .is_err()
+        );
+        // Fits in a u64 but overflows SystemTime.
+        assert!(
+            parse_audit_message(
+                "audit(18446744073709551615.999:1): k=v",
+                DEFAULT_MAX_FIELD_LEN
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn parse_audit_message_fraction_by_digit_count() {
+        let since_epoch = |input| {
+            let (_, parsed) = parse_audit_message(input, DEFAULT_MAX_FIELD_LEN).unwrap();
+            parsed
+                .timestamp
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap()
+        };
+        let millis = since_epoch("audit(1700000000.243:1): k=v");
+        assert_eq!(millis.as_secs(), 1700000000);
+        assert_eq!(millis.subsec_nanos(), 243_000_000);
+        let micros = since_epoch("audit(1700000000.243517:1): k=v");
+        assert_eq!(micros.as_secs(), 1700000000);
+        assert_eq!(micros.subsec_nanos(), 243_517_000);
+        let nanos = since_epoch("audit(1700000000.000000007:1): k=v");
+        assert_eq!(nanos.subsec_nanos(), 7);
+    }
+
+    #[test]
+    fn read_to_fields_handles_malformed_payloads() {
+        assert!(read_to_fields("", DEFAULT_MAX_FIELD_LEN).is_empty());
+        assert!(read_to_fields("=", DEFAULT_MAX_FIELD_LEN).is_empty());
+        assert_eq!(
+            read_to_fields("k=\"unterminated", DEFAULT_MAX_FIELD_LEN),
+            HashMap::from([("k".to_string(), "unterminated".to_string())])
+        );
+        assert_eq!(
+            read_to_fields("ключ=значение", DEFAULT_MAX_FIELD_LEN),
+            HashMap::from([("ключ".to_string(), "значение".to_string())])
+        );
+    }
+
+    #[test]
+    fn read_to_fields_multibyte_around_separator() {
+        let fields = read_to_fields(
+            "café=naïve über=\"größe ß\" 日=本=語 é=",
+            DEFAULT_MAX_FIELD_LEN,
+        );
+        assert_eq!(fields.get("café").map(String::as_str), Some("naïve"));
+        assert_eq!(fields.get("über").map(String::as_str), Some("größe ß"));
+        // Only the first `=` separates key from value.
+        assert_eq!(fields.get("日").map(String::as_str), Some("本=語"));
+        assert_eq!(fields.get("é").map(String::as_str), Some(""));
+    }
+
+    #[test]
+    fn oversized_field_values_are_truncated_and_flagged() {
+        let proctitle = "41".repeat(100);
+        let line = format!(
+            "type=PROCTITLE msg=audit(1700000000.123:42): proctitle={proctitle} comm=\"bash\""
+        );
+        let options = ParseOptions {
+            max_field_len: 64,
+            ..ParseOptions::default()
+        };
+
+        let record = parse_line(&line, &options).unwrap();
+        let value = record.get("proctitle").unwrap();
+        assert_eq!(value, format!("{}{TRUNCATED_MARKER}", &proctitle[..64]));
+        assert_eq!(record.get("comm"), Some("bash"));
+        assert_eq!(record.truncated_fields(), 1);
+
+        // Values are cut on a character boundary.
+        let fields = read_to_fields("k=ééé", 3);
+        assert_eq!(fields["k"], format!("é{TRUNCATED_MARKER}"));
+        let record = parse_line(&line, &ParseOptions::default()).unwrap();
+        assert_eq!(record.truncated_fields(), 0);
+    }
+
+    #[test]
+    fn truncation_is_recorded_and_keeps_hex_decodable() {
+        let options = ParseOptions {
+            max_field_len: 5,
+            ..ParseOptions::default()
+        };
+        let record = parse_line(
+            "type=PROCTITLE msg=audit(1700000000.123:42): proctitle=414243444546 \
+             comm=\"ABCDEFGH\" pid=42",
+            &options,
+        )
+        .unwrap();
+        // An odd cut would leave half a hex byte; quoted values are not hex.
+        assert_eq!(
+            record.get("proctitle"),
+            Some(format!("4142{TRUNCATED_MARKER}").as_str())
+        );
+        assert_eq!(
+            record.get("comm"),
+            Some(format!("ABCDE{TRUNCATED_MARKER}").as_str())
+        );
+        assert_eq!(record.truncated_fields(), 2);
+
+        // A value that merely ends like a truncated one was not truncated.
+        let note = format!("x{TRUNCATED_MARKER}");
+        let line = format!("type=USER msg=audit(1700000000.123:43): note={note}");
+        let record = parse_line(&line, &ParseOptions::default()).unwrap();
+        assert_eq!(record.get("note"), Some(note.as_str()));
+        assert_eq!(record.truncated_fields(), 0);
+    }
+
+    #[test]
+    fn typed_getters_read_parsed_record() {
+        let record = parse_line(
+            "type=SYSCALL msg=audit(1700000000.123:42): syscall=59 pid=1234 a0=7ffd \
+             comm=\"bash\" exe=2F746D702F6D7920736372697074 key=(null)",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record.record_type(), RecordType::Syscall);
+        assert_eq!(record.serial(), 42);
+        assert_eq!(record.len(), 6);
+        assert!(!record.is_empty());
+        assert_eq!(record.get("comm"), Some("bash"));
+        assert_eq!(record.get("missing"), None);
+        assert_eq!(record.get_u64("pid"), Some(1234));
+        assert_eq!(record.get_u64("a0"), None);
+        assert_eq!(record.get_quoted("comm").as_deref(), Some("bash"));
+        assert_eq!(record.get_quoted("exe").as_deref(), Some("/tmp/my script"));
+        assert_eq!(record.get_quoted("key").as_deref(), Some("(null)"));
+        let mut keys: Vec<_> = record.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["a0", "comm", "exe", "key", "pid", "syscall"]);
+        assert_eq!(record.fields()["syscall"], "59");
+    }
+
+    #[test]
+    fn get_quoted_keeps_quoted_hex_looking_values() {
+        let record = parse_line(
+            "type=SYSCALL msg=audit(1700000000.123:42): comm=\"1234\" exe=31323334",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record.get_quoted("comm").as_deref(), Some("1234"));
+        assert_eq!(record.get_quoted("exe").as_deref(), Some("1234"));
+    }
+
+    #[test]
+    fn succeeded_understands_success_and_res() {
+        let succeeded = |fields: &str| {
+            let line = format!("type=SYSCALL msg=audit(1234567890.123:7): {fields}");
+            parse_line(&line, &ParseOptions::default())
+                .unwrap()
+                .succeeded()
+        };
+        assert_eq!(succeeded("success=yes exit=0"), Some(true));
+        assert_eq!(succeeded("success=no exit=-13"), Some(false));
+        assert_eq!(succeeded("res=success"), Some(true));
+        assert_eq!(succeeded("res=failed"), Some(false));
+        assert_eq!(succeeded("res=1"), Some(true));
+        assert_eq!(succeeded("res=0"), Some(false));
+        assert_eq!(succeeded("res=\"failed\""), Some(false));
+        assert_eq!(succeeded("success=YES"), Some(true));
+        assert_eq!(succeeded("success=true"), Some(true));
+        assert_eq!(succeeded("success=False"), Some(false));
+        assert_eq!(succeeded("syscall=59"), None);
+        assert_eq!(succeeded("res=?"), None);
+    }
+
+    #[test]
+    fn parse_line_ok() {
+        let record = parse_line(
+            "type=SYSCALL msg=audit(1234567890.123:7): syscall=59",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record.record_type, RecordType::Syscall);
+        assert_eq!(record.serial, 7);
+        assert_eq!(record.fields.get("syscall").map(String::as_str), Some("59"));
+    }
+
+    #[test]
+    fn parse_line_missing_type_is_specific_error() {
+        let line = "msg=audit(1234567890.123:7): syscall=59";
+        assert_eq!(
+            parse_line(line, &ParseOptions::default()),
+            Err(ParseError::MissingType)
+        );
+        // Lenient parsing accepts the line with an unknown record type.
+        let record = parse_line(line, &ParseOptions::lenient()).unwrap();
+        assert_eq!(record.record_type, RecordType::Unknown(0));
+    }
+
+    #[test]
+    fn parse_line_missing_msg_is_specific_error() {
+        let strict = ParseOptions::default();
+        assert_eq!(
+            parse_line("type=SYSCALL", &strict),
+            Err(ParseError::MissingMsg)
+        );
+        assert_eq!(
+            parse_line("type=SYSCALL syscall=59", &strict),
+            Err(ParseError::MissingMsg)
+        );
+        assert_eq!(
+            parse_line("type=SYSCALL syscall=59", &ParseOptions::lenient()),
+            Err(ParseError::MissingMsg)
+        );
+    }
+
+    #[test]
+    fn parse_line_strictness_controls_unknown_types() {
+        let line = "node=host1 type=NOT_A_TYPE msg=audit(1234567890.123:7): a=b";
+        assert_eq!(
+            parse_line(line, &ParseOptions::default()),
+            Err(ParseError::UnknownType("NOT_A_TYPE".to_string()))
+        );
+        let record = parse_line(line, &ParseOptions::lenient()).unwrap();
+        assert_eq!(record.record_type, RecordType::Unknown(0));
+        assert_eq!(record.fields.get("a").map(String::as_str), Some("b"));
+
+        let line = "type=syscall msg=audit(1234567890.123:7): a=b";
+        assert_eq!(
+            parse_line(line, &ParseOptions::default()),
+            Err(ParseError::UnknownType("syscall".to_string()))
+        );
+        let record = parse_line(line, &ParseOptions::lenient()).unwrap();
+        assert_eq!(record.record_type, RecordType::Syscall);
+    }
+
+    #[test]
+    fn strict_parsing_skips_node_prefixes() {
+        let line = "node=host1 node=host2 type=SYSCALL msg=audit(1234567890.123:7): a=b";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+        assert_eq!(record.record_type, RecordType::Syscall);
+        assert_eq!(record.fields.get("a").map(String::as_str), Some("b"));
+        assert_eq!(
+            parse_line(
+                "node=host1 msg=audit(1.000:7): a=b",
+                &ParseOptions::default()
+            ),
+            Err(ParseError::MissingType)
+        );
+    }
+
+    #[test]
+    fn simple_string_keeps_the_simple_log_layout() {
+        let line = "type=SYSCALL msg=audit(1.500:7): exe=\"/bin/ls\"";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+        assert_eq!(
+            record.simple_string(),
+            "ParsedAuditRecord { record_type: Syscall, timestamp: SystemTime { tv_sec: 1, \
+             tv_nsec: 500000000 }, serial: 7, fields: {\"exe\": \"/bin/ls\"} }"
+        );
+    }
+
+    #[test]
+    fn split_node_returns_the_last_node() {
+        assert_eq!(
+            split_node("node=a node=b type=SYSCALL msg=audit(1.000:1): a=b"),
+            (Some("b"), "type=SYSCALL msg=audit(1.000:1): a=b")
+        );
+        assert_eq!(split_node("type=SYSCALL"), (None, "type=SYSCALL"));
+        assert_eq!(split_node("node=a"), (Some("a"), ""));
+    }
+
+    #[test]
+    fn parse_raw_line_keeps_payload_unparsed() {
+        let raw = parse_raw_line(
+            "node=host type=SYSCALL msg=audit(1.000:1): a=b",
+            &ParseOptions::lenient(),
+        )
+        .unwrap();
+        assert_eq!(
+            raw,
+            RawAuditRecord::new(1300, "audit(1.000:1): a=b".to_string())
+        );
+        assert_eq!(
+            parse_raw_line("hello world", &ParseOptions::lenient()),
+            Err(ParseError::MissingMsg)
+        );
+        assert_eq!(
+            parse_raw_line("type=SYSCALL msg=oops", &ParseOptions::lenient()),
+            Err(ParseError::MissingMsg)
+        );
+    }
+
     #[test]
     fn try_from_serial_overflow_defaults_to_zero() {
         // Header serial is > u16::MAX - parse::<u16>() fails and unwrap_or(0) applies.
@@ -276,4 +1154,27 @@ mod tests {
         let parsed = ParsedAuditRecord::try_from(raw).unwrap();
         assert_eq!(parsed.serial, 0);
     }
+
+    #[test]
+    fn borrowed_fields_reference_the_line() {
+        let line = "type=PATH msg=audit(1700000000.123456:42): item=0 name=\"/etc/my file\" \
+                    inode=42 item=1";
+        let record = parse_line_borrowed(line).unwrap();
+        let owned = parse_line(line, &ParseOptions::default()).unwrap();
+        assert_eq!(record.record_type, RecordType::Path);
+        assert_eq!(record.timestamp, owned.timestamp);
+        assert_eq!(record.serial, 42);
+        assert_eq!(record.get("name"), Some("/etc/my file"));
+        assert_eq!(record.get("item"), owned.get("item"));
+        assert_eq!(record.get("missing"), None);
+
+        let range = line.as_bytes().as_ptr_range();
+        for (key, value) in record.fields() {
+            assert!(range.contains(&key.as_ptr()) && range.contains(&value.as_ptr()));
+            assert!(owned.fields.contains_key(key));
+        }
+        assert_eq!(record.fields().count(), 4);
+        assert!(parse_line_borrowed("type=PATH").is_err());
+        assert!(parse_line_borrowed("type=PATH msg=audit(x): a=b").is_err());
+    }
 }
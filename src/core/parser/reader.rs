@@ -0,0 +1,207 @@
+//! Implementation of the streaming record readers.
+//!
+//! Lines are read as bytes, so offsets count bytes of the input even when it
+//! holds invalid UTF-8 (which is escaped as `\xHH` before parsing). Blank lines
+//! are skipped; a line that fails to parse yields an error naming its offset,
+//! and reading continues with the next line. Errors reading the input are
+//! returned as the underlying `io::Error`, so callers can tell them apart from
+//! unparseable lines.
+
+use std::io::BufRead;
+
+use anyhow::{Context, Result};
+
+use crate::core::parser::parser::parse_line;
+use crate::core::parser::{
+    LineParser,
+    OffsetRecordReader,
+    ParseOptions,
+    ParsedAuditRecord,
+    RecordReader,
+};
+use crate::utils::escape_invalid_utf8;
+
+impl<R: BufRead> RecordReader<R> {
+    /// Creates a reader parsing the lines of `reader` with `options`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `reader`: The input, positioned at the start of a line.
+    /// * `options`: Options each line is parsed with.
+    pub fn new(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            options,
+            line_parser: None,
+            offset: 0,
+            line: Vec::new(),
+            pending: Vec::new().into_iter(),
+            pending_offset: 0,
+        }
+    }
+
+    /// Parses lines with `parser` instead of `parse_line`, for inputs that are
+    /// not auditd text. Records found on the same line are yielded in order,
+    /// all with that line's offset; a line without records is skipped.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `parser`: Turns a trimmed, non-empty line into its records.
+    pub fn with_line_parser(mut self, parser: LineParser) -> Self {
+        self.line_parser = Some(parser);
+        self
+    }
+
+    /// Makes offsets relative to a different start, for input that was
+    /// positioned (e.g. with `seek`) past the start of the file.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `offset`: The byte offset the input is positioned at.
+    pub fn starting_at(mut self, offset: u64) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Returns a reader that also yields the byte offset each record's line
+    /// starts at, for seeking back to it later.
+    pub fn with_offsets(self) -> OffsetRecordReader<R> {
+        OffsetRecordReader { inner: self }
+    }
+
+    /// Reads up to the next non-empty line and parses it, returning the line's
+    /// offset with the result. Returns `None` at the end of the input.
+    fn next_record(&mut self) -> Option<(u64, Result<ParsedAuditRecord>)> {
+        if let Some(record) = self.pending.next() {
+            return Some((self.pending_offset, Ok(record)));
+        }
+        loop {
+            self.line.clear();
+            let offset = self.offset;
+            let read = match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(0) => return None,
+                Ok(read) => read,
+                Err(e) => return Some((offset, Err(e.into()))),
+            };
+            self.offset += read as u64;
+
+            let text = escape_invalid_utf8(&self.line);
+            let text = text.trim();
+            if text.is_empty() {
+                continue;
+            }
+            let context = || format!("Could not parse line at byte offset {}", offset);
+            let Some(parser) = &mut self.line_parser else {
+                return Some((
+                    offset,
+                    parse_line(text, &self.options).with_context(context),
+                ));
+            };
+            match parser(text).with_context(context) {
+                Ok(records) => {
+                    self.pending = records.into_iter();
+                    self.pending_offset = offset;
+                    if let Some(record) = self.pending.next() {
+                        return Some((offset, Ok(record)));
+                    }
+                }
+                Err(e) => return Some((offset, Err(e))),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordReader<R> {
+    type Item = Result<ParsedAuditRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().map(|(_, record)| record)
+    }
+}
+
+impl<R: BufRead> Iterator for OffsetRecordReader<R> {
+    type Item = Result<(u64, ParsedAuditRecord)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next_record()
+            .map(|(offset, record)| record.map(|record| (offset, record)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::RecordType;
+    use std::io::Cursor;
+
+    const LOG: &str = "type=SYSCALL msg=audit(1700000000.123:42): syscall=59 comm=\"läuft\"\n\
+                       \n\
+                       type=CWD msg=audit(1700000000.123:42): cwd=\"/root\"\n\
+                       not an audit line\n\
+                       type=PATH msg=audit(1700000000.123:42): item=0 name=\"/bin/ls\"";
+
+    #[test]
+    fn offsets_point_at_line_starts() {
+        let reader = RecordReader::new(Cursor::new(LOG), ParseOptions::default());
+        let results: Vec<_> = reader.with_offsets().collect();
+        assert_eq!(results.len(), 4);
+        assert!(results[2].is_err());
+
+        let records: Vec<(u64, ParsedAuditRecord)> =
+            results.into_iter().filter_map(Result::ok).collect();
+        let types: Vec<RecordType> = records.iter().map(|(_, r)| r.record_type).collect();
+        assert_eq!(
+            types,
+            [RecordType::Syscall, RecordType::Cwd, RecordType::Path]
+        );
+        for (offset, record) in &records {
+            let line = &LOG[*offset as usize..];
+            let header = format!("type={} ", record.record_type.as_audit_str());
+            assert!(line.starts_with(&header), "{line:?} at {offset}");
+        }
+        // The multi-byte `ä` counts as two bytes.
+        assert_eq!(records[1].0, LOG.find("type=CWD").unwrap() as u64);
+    }
+
+    #[test]
+    fn offsets_continue_from_the_start_position() {
+        let start = LOG.find("type=CWD").unwrap();
+        let reader = RecordReader::new(Cursor::new(&LOG[start..]), ParseOptions::default())
+            .starting_at(start as u64);
+        let offsets: Vec<u64> = reader
+            .with_offsets()
+            .filter_map(|r| r.ok().map(|(offset, _)| offset))
+            .collect();
+        assert_eq!(
+            offsets,
+            [start as u64, LOG.find("type=PATH").unwrap() as u64]
+        );
+    }
+
+    #[test]
+    fn line_parser_yields_every_record_of_a_line() {
+        let input = "type=SYSCALL msg=audit(1700000000.123:42): syscall=59\n\
+                     skip\n\
+                     type=CWD msg=audit(1700000000.123:42): cwd=\"/root\"\n";
+        // Reads each line twice, and nothing from lines not starting with `type=`.
+        let parser: LineParser = Box::new(|line| {
+            if !line.starts_with("type=") {
+                return Ok(Vec::new());
+            }
+            let record = parse_line(line, &ParseOptions::default())?;
+            Ok(vec![record.clone(), record])
+        });
+        let records: Vec<(u64, ParsedAuditRecord)> =
+            RecordReader::new(Cursor::new(input), ParseOptions::default())
+                .with_line_parser(parser)
+                .with_offsets()
+                .collect::<Result<_>>()
+                .unwrap();
+        let offsets: Vec<u64> = records.iter().map(|(offset, _)| *offset).collect();
+        let cwd = input.find("type=CWD").unwrap() as u64;
+        assert_eq!(offsets, [0, 0, cwd, cwd]);
+        assert_eq!(records[0].1, records[1].1);
+        assert_eq!(records[2].1.record_type, RecordType::Cwd);
+    }
+}
@@ -0,0 +1,164 @@
+//! Decoding of `SECCOMP` records, which the kernel emits when a seccomp filter
+//! kills, traps, fails or logs a syscall.
+
+use crate::core::parser::{ParsedAuditRecord, RecordType, SeccompAction, SeccompRecord};
+use crate::utils::syscall_name_for_arch;
+
+/// Mask of the action bits of a seccomp return value
+/// (`SECCOMP_RET_ACTION_FULL`).
+const SECCOMP_RET_ACTION_FULL: u32 = 0xffff_0000;
+/// Mask of the data bits of a seccomp return value (`SECCOMP_RET_DATA`).
+const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+impl SeccompAction {
+    /// Decodes a seccomp filter return value, as found in the `code=` field.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `code`: The return value (action and data bits).
+    pub fn from_code(code: u32) -> Self {
+        let data = (code & SECCOMP_RET_DATA) as u16;
+        match code & SECCOMP_RET_ACTION_FULL {
+            0x8000_0000 => SeccompAction::KillProcess,
+            0x0000_0000 => SeccompAction::KillThread,
+            0x0003_0000 => SeccompAction::Trap,
+            0x0005_0000 => SeccompAction::Errno(data),
+            0x7fc0_0000 => SeccompAction::UserNotif,
+            0x7ff0_0000 => SeccompAction::Trace,
+            0x7ffc_0000 => SeccompAction::Log,
+            0x7fff_0000 => SeccompAction::Allow,
+            action => SeccompAction::Unknown(action),
+        }
+    }
+
+    /// Returns whether the syscall was denied: the process or thread was
+    /// killed, trapped, or the syscall failed with an errno.
+    pub fn is_denial(&self) -> bool {
+        matches!(
+            self,
+            SeccompAction::KillProcess
+                | SeccompAction::KillThread
+                | SeccompAction::Trap
+                | SeccompAction::Errno(_)
+        )
+    }
+}
+
+impl SeccompRecord {
+    /// The action the seccomp filter took.
+    pub fn action(&self) -> SeccompAction {
+        self.action
+    }
+
+    /// The syscall number the filter acted on.
+    pub fn syscall(&self) -> u32 {
+        self.syscall
+    }
+
+    /// The syscall name, if the record's arch is the host architecture.
+    pub fn syscall_name(&self) -> Option<&'static str> {
+        self.syscall_name
+    }
+
+    /// The audit arch constant of the syscall (e.g. `c000003e`), if present.
+    pub fn arch(&self) -> Option<&str> {
+        self.arch.as_deref()
+    }
+
+    /// Whether the syscall was made through a compat (32-bit) entry point.
+    pub fn compat(&self) -> bool {
+        self.compat
+    }
+
+    /// The instruction pointer of the syscall, if present.
+    pub fn ip(&self) -> Option<&str> {
+        self.ip.as_deref()
+    }
+
+    /// The signal delivered to the process, `0` for none.
+    pub fn signal(&self) -> u32 {
+        self.signal
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Decodes this record as a `SECCOMP` record. Returns `None` for other
+    /// record types and for records missing a numeric `syscall` or a hex
+    /// `code`.
+    pub fn seccomp(&self) -> Option<SeccompRecord> {
+        if self.record_type != RecordType::Seccomp {
+            return None;
+        }
+        let code = self.fields.get("code")?;
+        let code = u32::from_str_radix(code.trim_start_matches("0x"), 16).ok()?;
+        let syscall = self.fields.get("syscall")?.parse().ok()?;
+        let arch = self.fields.get("arch").cloned();
+        Some(SeccompRecord {
+            action: SeccompAction::from_code(code),
+            syscall,
+            syscall_name: syscall_name_for_arch(arch.as_deref(), syscall),
+            arch,
+            compat: self.fields.get("compat").is_some_and(|c| c == "1"),
+            ip: self.fields.get("ip").cloned(),
+            signal: self
+                .fields
+                .get("sig")
+                .and_then(|sig| sig.parse().ok())
+                .unwrap_or(0),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::parser::ParseOptions;
+    use crate::core::parser::parser::parse_line;
+
+    const DENIAL: &str = "type=SECCOMP msg=audit(1700000000.000:100): auid=1000 uid=1000 \
+                          gid=1000 ses=2 subj=unconfined pid=4242 comm=\"curl\" \
+                          exe=\"/usr/bin/curl\" sig=0 arch=c000003e syscall=41 compat=0 \
+                          ip=0x7f1c2b3a4d5e code=0x50001";
+
+    #[test]
+    fn decodes_seccomp_action_and_syscall() {
+        let record = parse_line(DENIAL, &ParseOptions::default()).unwrap();
+        let seccomp = record.seccomp().unwrap();
+
+        assert_eq!(seccomp.action(), SeccompAction::Errno(1));
+        assert!(seccomp.action().is_denial());
+        assert_eq!(seccomp.syscall(), 41);
+        assert_eq!(seccomp.arch(), Some("c000003e"));
+        assert!(!seccomp.compat());
+        assert_eq!(seccomp.ip(), Some("0x7f1c2b3a4d5e"));
+        assert_eq!(seccomp.signal(), 0);
+        #[cfg(target_arch = "x86_64")]
+        assert_eq!(seccomp.syscall_name(), Some("socket"));
+
+        // Another arch's syscall numbers are not resolved.
+        let foreign = DENIAL.replace("arch=c000003e", "arch=80000016");
+        let record = parse_line(&foreign, &ParseOptions::default()).unwrap();
+        assert_eq!(record.seccomp().unwrap().syscall_name(), None);
+    }
+
+    #[test]
+    fn decodes_each_action() {
+        assert_eq!(
+            SeccompAction::from_code(0x8000_0000),
+            SeccompAction::KillProcess
+        );
+        assert_eq!(SeccompAction::from_code(0), SeccompAction::KillThread);
+        assert_eq!(SeccompAction::from_code(0x0003_0000), SeccompAction::Trap);
+        assert_eq!(SeccompAction::from_code(0x7ff0_0000), SeccompAction::Trace);
+        assert_eq!(SeccompAction::from_code(0x7ffc_0000), SeccompAction::Log);
+        assert_eq!(SeccompAction::from_code(0x7fff_0000), SeccompAction::Allow);
+        assert!(!SeccompAction::from_code(0x7fff_0000).is_denial());
+
+        let record = parse_line(
+            "type=SYSCALL msg=audit(1700000000.000:100): syscall=41 code=0x50001",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(record.seccomp(), None);
+    }
+}
@@ -0,0 +1,96 @@
+//! Parsing of SELinux security contexts carried in audit record fields.
+
+use crate::core::parser::{ParsedAuditRecord, SelinuxContext};
+
+/// Splits a SELinux context into user, role, type and optional MLS range.
+///
+/// Returns `None` when the value has fewer than three components or any of
+/// user/role/type is empty (e.g. AppArmor labels such as `unconfined`). The
+/// range keeps its own `:` separators (`s0-s0:c0.c1023`).
+///
+/// **Parameters:**
+///
+/// * `context`: The context text, e.g. `system_u:object_r:etc_t:s0`.
+pub fn parse_selinux_context(context: &str) -> Option<SelinuxContext> {
+    let mut parts = context.trim().splitn(4, ':');
+    let user = parts.next().filter(|s| !s.is_empty())?;
+    let role = parts.next().filter(|s| !s.is_empty())?;
+    let type_ = parts.next().filter(|s| !s.is_empty())?;
+    let range = parts.next().filter(|s| !s.is_empty());
+    Some(SelinuxContext {
+        user: user.to_string(),
+        role: role.to_string(),
+        type_: type_.to_string(),
+        range: range.map(str::to_string),
+    })
+}
+
+impl SelinuxContext {
+    /// The SELinux user (e.g. `unconfined_u`).
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// The SELinux role (e.g. `unconfined_r`).
+    pub fn role(&self) -> &str {
+        &self.role
+    }
+
+    /// The SELinux type or domain (e.g. `etc_t`).
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    /// The MLS/MCS range (e.g. `s0-s0:c0.c1023`), if present.
+    pub fn range(&self) -> Option<&str> {
+        self.range.as_deref()
+    }
+}
+
+impl ParsedAuditRecord {
+    /// Parses the named field of this record as a SELinux context.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `field`: The field holding the context, e.g. `subj` or `obj`.
+    pub fn selinux_context(&self, field: &str) -> Option<SelinuxContext> {
+        self.fields
+            .get(field)
+            .and_then(|value| parse_selinux_context(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_subj_with_mls_range() {
+        let ctx =
+            parse_selinux_context("unconfined_u:unconfined_r:unconfined_t:s0-s0:c0.c1023").unwrap();
+        assert_eq!(ctx.user(), "unconfined_u");
+        assert_eq!(ctx.role(), "unconfined_r");
+        assert_eq!(ctx.type_(), "unconfined_t");
+        assert_eq!(ctx.range(), Some("s0-s0:c0.c1023"));
+    }
+
+    #[test]
+    fn parses_obj_and_context_without_range() {
+        let ctx = parse_selinux_context("system_u:object_r:etc_t:s0").unwrap();
+        assert_eq!(ctx.user(), "system_u");
+        assert_eq!(ctx.role(), "object_r");
+        assert_eq!(ctx.type_(), "etc_t");
+        assert_eq!(ctx.range(), Some("s0"));
+
+        let ctx = parse_selinux_context("system_u:object_r:etc_t").unwrap();
+        assert_eq!(ctx.type_(), "etc_t");
+        assert_eq!(ctx.range(), None);
+    }
+
+    #[test]
+    fn rejects_non_selinux_labels() {
+        assert_eq!(parse_selinux_context("unconfined"), None);
+        assert_eq!(parse_selinux_context("system_u::etc_t:s0"), None);
+        assert_eq!(parse_selinux_context(""), None);
+    }
+}
@@ -0,0 +1,49 @@
+//! Reconstruction of the process tree from correlated events.
+//!
+//! [`ProcessTree`] collects the `pid`/`ppid` pairs of `SYSCALL` (including
+//! `execve`) events into parent-child links, so a forensic timeline can show
+//! which process spawned which. [`ProcessTree::ancestry`] walks from a process
+//! up through its parent, grandparent and so on.
+//!
+//! Pids are reused by the kernel, so a pid alone does not name a process. An
+//! event starts a new process for its pid when its `ppid` differs from the
+//! last process seen with that pid, or when the pid was idle for longer than
+//! the reuse window. Lookups then pick the process whose time window contains
+//! the queried time.
+
+mod process_tree;
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// Default idle time after which a pid is assumed to have been reused.
+pub const DEFAULT_REUSE_WINDOW: Duration = Duration::from_secs(300);
+
+/// One process observed in the audit stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcessNode {
+    /// The process id.
+    pub pid: u32,
+    /// The parent process id.
+    pub ppid: u32,
+    /// The executable of the latest event (changes on `execve`).
+    pub exe: Option<String>,
+    /// The command name of the latest event.
+    pub comm: Option<String>,
+    /// The audit session id, if set.
+    pub ses: Option<u32>,
+    /// Timestamp of the first event of this process.
+    pub first_seen: SystemTime,
+    /// Timestamp of the latest event of this process.
+    pub last_seen: SystemTime,
+}
+
+/// Parent-child process tree built from the `pid`/`ppid` fields of events.
+#[derive(Debug, Clone)]
+pub struct ProcessTree {
+    /// The processes seen for each pid, oldest first; more than one when the
+    /// pid was reused.
+    processes: HashMap<u32, Vec<ProcessNode>>,
+    /// Idle time after which an event for a known pid starts a new process.
+    reuse_window: Duration,
+}
@@ -0,0 +1,230 @@
+//! Implementation of `ProcessTree`: recording processes from events and
+//! walking their ancestry.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+use crate::core::correlator::AuditEvent;
+use crate::core::parser::RecordType;
+use crate::core::process_tree::{DEFAULT_REUSE_WINDOW, ProcessNode, ProcessTree};
+
+/// The `ses` value of processes outside an audit session.
+const UNSET_SESSION: u32 = u32::MAX;
+
+impl ProcessTree {
+    /// Creates an empty tree.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `reuse_window`: Idle time after which an event for a known pid is
+    ///   taken to be a new process reusing the pid.
+    pub fn new(reuse_window: Duration) -> Self {
+        Self {
+            processes: HashMap::new(),
+            reuse_window,
+        }
+    }
+
+    /// Records the process of `event`, taken from its `SYSCALL` record. Events
+    /// without one, or without numeric `pid` and `ppid` fields, are ignored.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The correlated event to record.
+    pub fn add_event(&mut self, event: &AuditEvent) {
+        let Some(record) = event.primary_record() else {
+            return;
+        };
+        if record.record_type != RecordType::Syscall {
+            return;
+        }
+        let field = |name: &str| record.fields.get(name);
+        let (Some(pid), Some(ppid)) = (
+            field("pid").and_then(|pid| pid.parse().ok()),
+            field("ppid").and_then(|ppid| ppid.parse().ok()),
+        ) else {
+            return;
+        };
+        let node = ProcessNode {
+            pid,
+            ppid,
+            exe: field("exe").map(|exe| exe.trim_matches('"').to_string()),
+            comm: field("comm").map(|comm| comm.trim_matches('"').to_string()),
+            ses: field("ses")
+                .and_then(|ses| ses.parse().ok())
+                .filter(|ses| *ses != UNSET_SESSION),
+            first_seen: event.timestamp,
+            last_seen: event.timestamp,
+        };
+        self.add_process(node);
+    }
+
+    /// Merges `node` into the latest process with its pid, or starts a new
+    /// process if the pid was reused.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `node`: The process as seen in a single event.
+    fn add_process(&mut self, node: ProcessNode) {
+        let reuse_window = self.reuse_window;
+        let instances = self.processes.entry(node.pid).or_default();
+        if let Some(latest) = instances.last_mut()
+            && latest.ppid == node.ppid
+            && !is_idle_past(latest.last_seen, node.first_seen, reuse_window)
+        {
+            latest.first_seen = latest.first_seen.min(node.first_seen);
+            if node.last_seen >= latest.last_seen {
+                latest.last_seen = node.last_seen;
+                latest.exe = node.exe.or(latest.exe.take());
+                latest.comm = node.comm.or(latest.comm.take());
+                latest.ses = node.ses.or(latest.ses);
+            }
+            return;
+        }
+        instances.push(node);
+    }
+
+    /// Returns the process with `pid` at time `at`: the latest one whose time
+    /// window (widened by the reuse window, since a process runs before and
+    /// after its events) contains `at`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `pid`: The process id.
+    /// * `at`: The time of interest.
+    pub fn process_at(&self, pid: u32, at: SystemTime) -> Option<&ProcessNode> {
+        self.processes.get(&pid)?.iter().rev().find(|process| {
+            !is_idle_past(at, process.first_seen, self.reuse_window)
+                && !is_idle_past(process.last_seen, at, self.reuse_window)
+        })
+    }
+
+    /// Returns the ancestry of the process with `pid` at time `at`: the
+    /// process itself, then its parent, grandparent and so on up to the
+    /// oldest ancestor seen. Empty if the process is unknown.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `pid`: The process id.
+    /// * `at`: The time of interest, which selects among processes that reused
+    ///   the pid.
+    pub fn ancestry(&self, pid: u32, at: SystemTime) -> Vec<&ProcessNode> {
+        let mut ancestry = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = self.process_at(pid, at);
+        while let Some(process) = next
+            && visited.insert((process.pid, process.first_seen))
+        {
+            ancestry.push(process);
+            // The parent was running when the child was first seen.
+            next = self.process_at(process.ppid, process.first_seen);
+        }
+        ancestry
+    }
+
+    /// Returns the number of processes recorded, counting reused pids once
+    /// per process.
+    pub fn len(&self) -> usize {
+        self.processes.values().map(Vec::len).sum()
+    }
+
+    /// Returns whether no process has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+}
+
+impl Default for ProcessTree {
+    fn default() -> Self {
+        Self::new(DEFAULT_REUSE_WINDOW)
+    }
+}
+
+/// Returns whether more than `window` passed from `from` to `to`.
+///
+/// **Parameters:**
+///
+/// * `from`: The earlier time.
+/// * `to`: The later time.
+/// * `window`: The allowed gap.
+fn is_idle_past(from: SystemTime, to: SystemTime, window: Duration) -> bool {
+    to.duration_since(from).is_ok_and(|gap| gap > window)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+
+    fn exec(secs: u64, pid: &str, ppid: &str, exe: &str) -> AuditEvent {
+        let record = RecordBuilder::syscall()
+            .ts_secs(secs)
+            .field("syscall", "59")
+            .field("pid", pid)
+            .field("ppid", ppid)
+            .field("exe", &format!("\"{exe}\""))
+            .field("ses", "3")
+            .build();
+        AuditEvent {
+            timestamp: record.timestamp,
+            serial: 1,
+            record_count: 1,
+            records: vec![record],
+            amendment: false,
+        }
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn reconstructs_parent_child_grandchild_chain() {
+        let mut tree = ProcessTree::default();
+        tree.add_event(&exec(1_000, "100", "1", "/usr/sbin/sshd"));
+        tree.add_event(&exec(1_010, "200", "100", "/usr/bin/bash"));
+        tree.add_event(&exec(1_020, "300", "200", "/usr/bin/curl"));
+
+        let ancestry = tree.ancestry(300, at(1_020));
+        let chain: Vec<(u32, Option<&str>)> = ancestry
+            .iter()
+            .map(|process| (process.pid, process.exe.as_deref()))
+            .collect();
+        assert_eq!(
+            chain,
+            [
+                (300, Some("/usr/bin/curl")),
+                (200, Some("/usr/bin/bash")),
+                (100, Some("/usr/sbin/sshd")),
+            ]
+        );
+        assert_eq!(ancestry[0].ses, Some(3));
+        assert_eq!(tree.len(), 3);
+        assert!(tree.ancestry(999, at(1_020)).is_empty());
+    }
+
+    #[test]
+    fn reused_pid_is_a_separate_process() {
+        let mut tree = ProcessTree::new(Duration::from_secs(60));
+        tree.add_event(&exec(1_000, "100", "1", "/usr/sbin/sshd"));
+        tree.add_event(&exec(1_001, "300", "100", "/usr/bin/ls"));
+        // Pid 100 reused an hour later by a child of cron (pid 50).
+        tree.add_event(&exec(4_600, "50", "1", "/usr/sbin/cron"));
+        tree.add_event(&exec(4_600, "100", "50", "/usr/bin/backup"));
+        tree.add_event(&exec(4_601, "300", "100", "/usr/bin/tar"));
+
+        let pids = |secs| -> Vec<u32> {
+            tree.ancestry(300, at(secs))
+                .iter()
+                .map(|process| process.pid)
+                .collect()
+        };
+        assert_eq!(pids(1_001), [300, 100]);
+        assert_eq!(pids(4_601), [300, 100, 50]);
+        assert_eq!(
+            tree.process_at(100, at(4_601)).unwrap().exe.as_deref(),
+            Some("/usr/bin/backup")
+        );
+        assert_eq!(tree.len(), 5);
+    }
+}
@@ -0,0 +1,59 @@
+//! Severity scoring for audit events.
+//!
+//! Each `RecordType` carries a base severity hint (see
+//! [`RecordType::severity_hint`](crate::core::parser::RecordType::severity_hint)):
+//! AVC denials and anomaly records start high, logins and account changes
+//! medium, and so on. Some types, notably `SYSCALL`, have no fixed severity and
+//! are scored from their fields instead. The [`SeverityScorer`] combines the
+//! hints with field-based signals (failed operations) to score a whole event,
+//! and lets the `severity_overrides` config table replace the hint of any
+//! record type.
+
+mod severity;
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::core::parser::RecordType;
+
+/// How important an audit record or event is, from routine to critical.
+/// Ordered so that the higher severity compares greater.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+    strum::Display,
+    strum::EnumString,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum Severity {
+    /// Routine activity.
+    #[default]
+    Info,
+    /// Activity of minor interest, such as session start and end.
+    Low,
+    /// Security-relevant activity, such as logins and account changes.
+    Medium,
+    /// Likely policy violations, such as AVC denials and anomalies.
+    High,
+    /// High-severity activity that also failed or was escalated by its fields.
+    Critical,
+}
+
+/// Scores records and events by combining per-type severity hints, optional
+/// per-type overrides from the config, and field-based signals.
+#[derive(Debug, Clone, Default)]
+pub struct SeverityScorer {
+    /// Severities that replace the built-in hint for a record type.
+    overrides: HashMap<RecordType, Severity>,
+}
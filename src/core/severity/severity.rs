@@ -0,0 +1,197 @@
+//! Implementation of the severity scorer.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+
+use crate::core::correlator::AuditEvent;
+use crate::core::parser::{ParsedAuditRecord, RecordType};
+use crate::core::severity::{Severity, SeverityScorer};
+
+impl Severity {
+    /// Returns the next higher severity, saturating at `Critical`.
+    pub fn raised(self) -> Severity {
+        match self {
+            Severity::Info => Severity::Low,
+            Severity::Low => Severity::Medium,
+            Severity::Medium => Severity::High,
+            Severity::High | Severity::Critical => Severity::Critical,
+        }
+    }
+
+    /// Returns the matching syslog priority (as used by journald's `PRIORITY`
+    /// field): `Critical` is `2` (crit) down to `Info` at `6` (info).
+    pub fn syslog_priority(self) -> u8 {
+        match self {
+            Severity::Critical => 2,
+            Severity::High => 3,
+            Severity::Medium => 4,
+            Severity::Low => 5,
+            Severity::Info => 6,
+        }
+    }
+}
+
+impl SeverityScorer {
+    /// Creates a scorer from the `severity_overrides` config table, which maps
+    /// auditd record type names (e.g. `"USER_LOGIN"`) to a severity.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `overrides`: Record type names and the severity to use for them.
+    pub fn new(overrides: &HashMap<String, Severity>) -> Result<Self> {
+        let mut by_type = HashMap::new();
+        for (name, severity) in overrides {
+            let record_type = RecordType::from_audit_str(name);
+            if record_type == RecordType::Unknown(0) {
+                bail!("Unknown record type '{}' in severity_overrides", name);
+            }
+            by_type.insert(record_type, *severity);
+        }
+        Ok(Self { overrides: by_type })
+    }
+
+    /// Returns the base severity of a record type: the configured override if
+    /// there is one, otherwise the type's built-in hint.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record_type`: The record type to look up.
+    pub fn base_severity(&self, record_type: RecordType) -> Option<Severity> {
+        self.overrides
+            .get(&record_type)
+            .copied()
+            .or_else(|| record_type.severity_hint())
+    }
+
+    /// Scores a single record. Types without a base severity start at `Low`;
+    /// a failed operation (`success=no` or `res=failed`/`res=0`) raises the
+    /// severity one level.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record`: The record to score.
+    pub fn record_severity(&self, record: &ParsedAuditRecord) -> Severity {
+        let base = self
+            .base_severity(record.record_type)
+            .unwrap_or(Severity::Low);
+        if record_failed(record) {
+            base.raised()
+        } else {
+            base
+        }
+    }
+
+    /// Scores an event as the highest base severity of its records (`Low` for
+    /// types without one), raised one level if the event's
+    /// [primary record](AuditEvent::primary_record) reports a failed
+    /// operation. An event without records is `Info`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to score.
+    pub fn event_severity(&self, event: &AuditEvent) -> Severity {
+        let Some(primary) = event.primary_record() else {
+            return Severity::default();
+        };
+        let base = event
+            .records
+            .iter()
+            .map(|record| {
+                self.base_severity(record.record_type)
+                    .unwrap_or(Severity::Low)
+            })
+            .max()
+            .unwrap_or_default();
+        if record_failed(primary) {
+            base.raised()
+        } else {
+            base
+        }
+    }
+}
+
+/// Returns whether the record reports a failed operation.
+///
+/// **Parameters:**
+///
+/// * `record`: The record to inspect.
+fn record_failed(record: &ParsedAuditRecord) -> bool {
+    record.succeeded() == Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use std::time::SystemTime;
+
+    fn record(record_type: RecordType, fields: &[(&str, &str)]) -> ParsedAuditRecord {
+        fields
+            .iter()
+            .fold(RecordBuilder::new(record_type), |record, (k, v)| {
+                record.field(k, v)
+            })
+            .serial(1)
+            .build()
+    }
+
+    #[test]
+    fn anom_abend_contributes_high_base_severity() {
+        let scorer = SeverityScorer::default();
+        assert_eq!(
+            RecordType::AnomalyAbend.severity_hint(),
+            Some(Severity::High)
+        );
+        let event = AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial: 1,
+            record_count: 2,
+            records: vec![
+                record(RecordType::Syscall, &[("success", "yes")]),
+                record(RecordType::AnomalyAbend, &[("sig", "11")]),
+            ],
+            amendment: false,
+        };
+        assert_eq!(scorer.event_severity(&event), Severity::High);
+    }
+
+    #[test]
+    fn failed_primary_record_raises_event_severity() {
+        let scorer = SeverityScorer::default();
+        let event = AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial: 1,
+            record_count: 2,
+            records: vec![
+                record(RecordType::Path, &[("name", "/etc/shadow")]),
+                record(RecordType::Syscall, &[("success", "no")]),
+            ],
+            amendment: false,
+        };
+        assert_eq!(scorer.event_severity(&event), Severity::Medium);
+    }
+
+    #[test]
+    fn fields_and_overrides_adjust_severity() {
+        let scorer = SeverityScorer::default();
+        assert_eq!(RecordType::Syscall.severity_hint(), None);
+        assert_eq!(
+            scorer.record_severity(&record(RecordType::Syscall, &[("success", "yes")])),
+            Severity::Low
+        );
+        assert_eq!(
+            scorer.record_severity(&record(RecordType::Syscall, &[("success", "no")])),
+            Severity::Medium
+        );
+
+        let overrides = HashMap::from([("user_login".to_owned(), Severity::Info)]);
+        let scorer = SeverityScorer::new(&overrides).unwrap();
+        assert_eq!(
+            scorer.record_severity(&record(RecordType::UserLogin, &[("res", "success")])),
+            Severity::Info
+        );
+        let overrides = HashMap::from([("NOT_A_TYPE".to_owned(), Severity::Info)]);
+        assert!(SeverityScorer::new(&overrides).is_err());
+    }
+}
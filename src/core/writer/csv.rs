@@ -0,0 +1,236 @@
+//! CSV rendering for `AuditEvent`s, one row per record.
+//!
+//! Every row starts with `timestamp`, `serial` and `type`, followed by one
+//! column per audit field. When all records share a record type with an entry
+//! in [`CSV_TEMPLATES`], the template gives the field columns and their order
+//! (e.g. `arch, syscall, success, exit, uid, comm, exe, key` for SYSCALL);
+//! fields outside the template are left out. Otherwise the field columns are
+//! the union of all field names, sorted. Missing fields are empty cells.
+//!
+//! Values are quoted as in RFC 4180 when they contain a comma, a double quote
+//! or a line break.
+
+use std::collections::BTreeSet;
+
+use crate::core::correlator::AuditEvent;
+use crate::core::parser::{ParsedAuditRecord, RecordType};
+use crate::utils::systemtime_to_utc_string;
+
+/// Columns every row starts with.
+pub const CSV_LEADING_COLUMNS: &[&str] = &["timestamp", "serial", "type"];
+
+/// Default field columns, in order, for output of a single record type.
+pub const CSV_TEMPLATES: &[(RecordType, &[&str])] = &[
+    (
+        RecordType::Syscall,
+        &[
+            "arch", "syscall", "success", "exit", "uid", "comm", "exe", "key",
+        ],
+    ),
+    (
+        RecordType::Path,
+        &["item", "name", "inode", "mode", "ouid", "ogid", "nametype"],
+    ),
+    (RecordType::Cwd, &["cwd"]),
+    (RecordType::Execve, &["argc", "a0", "a1", "a2", "a3"]),
+    (
+        RecordType::UserLogin,
+        &[
+            "pid", "uid", "auid", "ses", "id", "exe", "hostname", "addr", "terminal", "res",
+        ],
+    ),
+    (
+        RecordType::Seccomp,
+        &[
+            "pid", "uid", "auid", "comm", "exe", "arch", "syscall", "code",
+        ],
+    ),
+];
+
+/// Returns the default field columns for records of `record_type`, if it has a
+/// template.
+///
+/// **Parameters:**
+///
+/// * `record_type`: The record type to look up.
+pub fn template_for(record_type: RecordType) -> Option<&'static [&'static str]> {
+    CSV_TEMPLATES
+        .iter()
+        .find(|(rt, _)| *rt == record_type)
+        .map(|(_, columns)| *columns)
+}
+
+/// Returns the field columns for `events`: the record type's template when all
+/// records share one type that has a template, otherwise the sorted union of
+/// field names.
+///
+/// **Parameters:**
+///
+/// * `events`: The events to be written.
+pub fn csv_columns(events: &[AuditEvent]) -> Vec<String> {
+    let mut records = events.iter().flat_map(|e| e.records.iter());
+    let single_type = records.next().and_then(|first| {
+        records
+            .all(|r| r.record_type == first.record_type)
+            .then_some(first.record_type)
+    });
+    if let Some(columns) = single_type.and_then(template_for) {
+        return columns.iter().map(|c| c.to_string()).collect();
+    }
+    let keys: BTreeSet<&String> = events
+        .iter()
+        .flat_map(|e| e.records.iter())
+        .flat_map(|r| r.fields.keys())
+        .collect();
+    keys.into_iter().cloned().collect()
+}
+
+/// Formats `events` as CSV with a header line, one row per record.
+///
+/// **Parameters:**
+///
+/// * `events`: The events to format.
+pub fn format_csv(events: &[AuditEvent]) -> String {
+    let columns = csv_columns(events);
+    let header: Vec<&str> = CSV_LEADING_COLUMNS
+        .iter()
+        .copied()
+        .chain(columns.iter().map(String::as_str))
+        .collect();
+    let mut out = csv_line(header);
+    for record in events.iter().flat_map(|e| e.records.iter()) {
+        out.push_str(&csv_row(record, &columns));
+    }
+    out
+}
+
+/// Formats one record as a CSV row (with a trailing newline).
+///
+/// **Parameters:**
+///
+/// * `record`: The record to format.
+/// * `columns`: The field columns.
+fn csv_row(record: &ParsedAuditRecord, columns: &[String]) -> String {
+    let timestamp = systemtime_to_utc_string(record.timestamp);
+    let serial = record.serial.to_string();
+    let leading = [
+        timestamp.as_str(),
+        serial.as_str(),
+        record.record_type.as_audit_str(),
+    ];
+    let fields = columns
+        .iter()
+        .map(|c| record.fields.get(c).map_or("", String::as_str));
+    csv_line(leading.into_iter().chain(fields))
+}
+
+/// Joins `values` into one CSV line, quoting where needed.
+///
+/// **Parameters:**
+///
+/// * `values`: The cell values.
+fn csv_line<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    let mut line = values.into_iter().map(escape).collect::<Vec<_>>().join(",");
+    line.push('\n');
+    line
+}
+
+/// Quotes `value` if it contains a comma, a double quote or a line break,
+/// doubling any double quotes.
+///
+/// **Parameters:**
+///
+/// * `value`: The cell value.
+fn escape(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use std::time::SystemTime;
+
+    fn event(serial: u16, records: Vec<(RecordType, &[(&str, &str)])>) -> AuditEvent {
+        let records: Vec<ParsedAuditRecord> = records
+            .into_iter()
+            .map(|(record_type, fields)| {
+                fields
+                    .iter()
+                    .fold(RecordBuilder::new(record_type), |record, (k, v)| {
+                        record.field(k, v)
+                    })
+                    .serial(serial)
+                    .build()
+            })
+            .collect();
+        AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial,
+            record_count: records.len() as u16,
+            records,
+            amendment: false,
+        }
+    }
+
+    #[test]
+    fn syscall_only_output_uses_template_columns() {
+        let events = vec![
+            event(
+                1,
+                vec![(
+                    RecordType::Syscall,
+                    &[
+                        ("key", "exec"),
+                        ("syscall", "59"),
+                        ("arch", "c000003e"),
+                        ("comm", "ls"),
+                        ("pid", "42"),
+                        ("exe", "/usr/bin/ls"),
+                        ("uid", "0"),
+                        ("success", "yes"),
+                        ("exit", "0"),
+                    ],
+                )],
+            ),
+            event(
+                2,
+                vec![(RecordType::Syscall, &[("syscall", "2"), ("comm", "a,b")])],
+            ),
+        ];
+
+        assert_eq!(
+            csv_columns(&events),
+            [
+                "arch", "syscall", "success", "exit", "uid", "comm", "exe", "key"
+            ]
+        );
+        let csv = format_csv(&events);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp,serial,type,arch,syscall,success,exit,uid,comm,exe,key"
+        );
+        assert!(lines[1].ends_with(",1,SYSCALL,c000003e,59,yes,0,0,ls,/usr/bin/ls,exec"));
+        assert!(lines[2].ends_with(",2,SYSCALL,,2,,,,\"a,b\",,"));
+    }
+
+    #[test]
+    fn mixed_types_use_union_of_keys() {
+        let events = vec![event(
+            1,
+            vec![
+                (RecordType::Syscall, &[("syscall", "59"), ("pid", "42")]),
+                (RecordType::Cwd, &[("cwd", "/root")]),
+            ],
+        )];
+
+        assert_eq!(csv_columns(&events), ["cwd", "pid", "syscall"]);
+        let csv = format_csv(&events);
+        assert!(csv.lines().nth(2).unwrap().ends_with(",1,CWD,/root,,"));
+    }
+}
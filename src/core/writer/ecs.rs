@@ -0,0 +1,303 @@
+//! Elastic Common Schema (ECS) rendering for `AuditEvent`s.
+//!
+//! Each event becomes one compact JSON document per line (NDJSON), ready for
+//! Filebeat/Elasticsearch ingestion alongside auditbeat data. Audit fields are
+//! mapped to ECS names using [`ECS_FIELD_MAP`]:
+//!
+//! | Audit field | ECS field                   | Typical record   |
+//! |-------------|-----------------------------|------------------|
+//! | `pid`       | `process.pid`               | SYSCALL          |
+//! | `ppid`      | `process.parent.pid`        | SYSCALL          |
+//! | `comm`      | `process.name`              | SYSCALL          |
+//! | `exe`       | `process.executable`        | SYSCALL          |
+//! | `cwd`       | `process.working_directory` | CWD              |
+//! | `uid`       | `user.id`                   | SYSCALL, USER_*  |
+//! | `euid`      | `user.effective.id`         | SYSCALL          |
+//! | `auid`      | `user.audit.id`             | SYSCALL, USER_*  |
+//! | `acct`      | `user.name`                 | USER_*           |
+//! | `gid`       | `group.id`                  | SYSCALL          |
+//! | `egid`      | `group.effective.id`        | SYSCALL          |
+//! | `name`      | `file.path`                 | PATH             |
+//! | `inode`     | `file.inode`                | PATH             |
+//! | `mode`      | `file.mode`                 | PATH             |
+//! | `addr`      | `source.ip`                 | USER_*           |
+//! | `hostname`  | `source.domain`             | USER_*           |
+//!
+//! The first record providing a mapped field wins. Everything else (unmapped
+//! fields and later duplicates) is kept under `auditrs.<record_type>`, where
+//! the record type is lowercased (e.g. `auditrs.path.nametype`); when an event
+//! has several records of the same type the value is an array of objects.
+//!
+//! `event.action` is the name of the syscall (e.g. `execve`), resolved from
+//! the `syscall` and `arch` fields; the number itself stays under
+//! `auditrs.syscall.syscall`. Without a resolvable syscall it falls back to
+//! the lowercased primary record type. `event.outcome` is derived from
+//! `success=yes|no` or `res=success|failed` (see
+//! [`ParsedAuditRecord::succeeded`](crate::core::parser::ParsedAuditRecord::succeeded)).
+//!
+//! Since the mapping is lossy, `event.original` holds the event's records as
+//! legacy lines, from which
+//! [`parse_ecs_events`](crate::utils::parse_ecs_events) reads events back.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde_json::{Map, Value, json};
+
+use crate::config::EnrichedFormat;
+use crate::core::correlator::AuditEvent;
+use crate::core::writer::AuditLogWriter;
+use crate::utils::{syscall_name_for_arch, systemtime_to_utc_string};
+
+/// Mapping from audit field names to dotted ECS field names.
+pub const ECS_FIELD_MAP: &[(&str, &str)] = &[
+    ("pid", "process.pid"),
+    ("ppid", "process.parent.pid"),
+    ("comm", "process.name"),
+    ("exe", "process.executable"),
+    ("cwd", "process.working_directory"),
+    ("uid", "user.id"),
+    ("euid", "user.effective.id"),
+    ("auid", "user.audit.id"),
+    ("acct", "user.name"),
+    ("gid", "group.id"),
+    ("egid", "group.effective.id"),
+    ("name", "file.path"),
+    ("inode", "file.inode"),
+    ("mode", "file.mode"),
+    ("addr", "source.ip"),
+    ("hostname", "source.domain"),
+];
+
+/// Looks up the ECS field name for an audit field, if it has one.
+///
+/// **Parameters:**
+///
+/// * `field`: The audit field name (e.g. `pid`).
+pub fn ecs_field_name(field: &str) -> Option<&'static str> {
+    ECS_FIELD_MAP
+        .iter()
+        .find(|(audit, _)| *audit == field)
+        .map(|(_, ecs)| *ecs)
+}
+
+/// Builds the ECS document for a single event.
+///
+/// **Parameters:**
+///
+/// * `event`: The `AuditEvent` to map.
+pub fn ecs_document(event: &AuditEvent) -> Result<Value> {
+    let mut doc = Map::new();
+    doc.insert(
+        "@timestamp".to_string(),
+        Value::String(systemtime_to_utc_string(event.timestamp)),
+    );
+    insert_dotted(&mut doc, "event.kind", json!("event"));
+    insert_dotted(&mut doc, "event.module", json!("auditd"));
+    insert_dotted(&mut doc, "event.sequence", json!(event.serial));
+
+    let mut unmapped: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+    let mut original = String::new();
+    for record in &event.records {
+        // Sort for stable output; record fields are stored in a HashMap.
+        let mut fields: Vec<_> = record.fields.iter().collect();
+        fields.sort();
+        original.push_str(&AuditLogWriter::format_legacy_record(
+            event,
+            record,
+            fields.iter().copied(),
+            EnrichedFormat::default(),
+        )?);
+
+        let mut extra = Map::new();
+        for (key, value) in fields {
+            if key == "syscall"
+                && get_dotted(&doc, "event.action").is_none()
+                && let Ok(id) = value.parse::<u32>()
+                && let Some(name) =
+                    syscall_name_for_arch(record.fields.get("arch").map(String::as_str), id)
+            {
+                insert_dotted(&mut doc, "event.action", json!(name));
+            }
+            match ecs_field_name(key) {
+                Some(ecs) if get_dotted(&doc, ecs).is_none() => {
+                    insert_dotted(&mut doc, ecs, Value::String(value.clone()));
+                }
+                _ => {
+                    extra.insert(key.clone(), Value::String(value.clone()));
+                }
+            }
+        }
+        if get_dotted(&doc, "event.outcome").is_none()
+            && let Some(succeeded) = record.succeeded()
+        {
+            let outcome = if succeeded { "success" } else { "failure" };
+            insert_dotted(&mut doc, "event.outcome", json!(outcome));
+        }
+        unmapped
+            .entry(record.record_type.as_audit_str().to_ascii_lowercase())
+            .or_default()
+            .push(Value::Object(extra));
+    }
+
+    if get_dotted(&doc, "event.action").is_none()
+        && let Some(primary) = event.primary_record()
+    {
+        let action = primary.record_type.as_audit_str().to_ascii_lowercase();
+        insert_dotted(&mut doc, "event.action", Value::String(action));
+    }
+    insert_dotted(&mut doc, "event.original", Value::String(original));
+
+    let mut auditrs = Map::new();
+    auditrs.insert("serial".to_string(), json!(event.serial));
+    auditrs.insert("record_count".to_string(), json!(event.record_count));
+    for (record_type, mut records) in unmapped {
+        let value = if records.len() == 1 {
+            records.remove(0)
+        } else {
+            Value::Array(records)
+        };
+        auditrs.insert(record_type, value);
+    }
+    doc.insert("auditrs".to_string(), Value::Object(auditrs));
+    Ok(Value::Object(doc))
+}
+
+/// Formats a single event as one line of ECS NDJSON (including the trailing
+/// newline).
+///
+/// **Parameters:**
+///
+/// * `event`: The `AuditEvent` to format.
+pub fn format_ecs_event(event: &AuditEvent) -> Result<String> {
+    Ok(format!(
+        "{}\n",
+        serde_json::to_string(&ecs_document(event)?)?
+    ))
+}
+
+/// Inserts `value` at a dotted path, creating intermediate objects.
+fn insert_dotted(doc: &mut Map<String, Value>, path: &str, value: Value) {
+    let mut current = doc;
+    let mut parts = path.split('.').peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            current.insert(part.to_string(), value);
+            return;
+        }
+        let entry = current
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if !entry.is_object() {
+            *entry = Value::Object(Map::new());
+        }
+        current = entry
+            .as_object_mut()
+            .expect("entry was just made an object");
+    }
+}
+
+/// Looks up the value at a dotted path.
+fn get_dotted<'a>(doc: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    let (first, rest) = match path.split_once('.') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (path, None),
+    };
+    let value = doc.get(first)?;
+    match rest {
+        Some(rest) => get_dotted(value.as_object()?, rest),
+        None => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use crate::core::parser::{ParsedAuditRecord, RecordType};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn record(record_type: RecordType, fields: &[(&str, &str)]) -> ParsedAuditRecord {
+        fields
+            .iter()
+            .fold(RecordBuilder::new(record_type), |record, (k, v)| {
+                record.field(k, v)
+            })
+            .ts_secs(1_700_000_000)
+            .serial(42)
+            .build()
+    }
+
+    #[test]
+    fn syscall_and_path_map_to_ecs_fields() {
+        let event = AuditEvent {
+            timestamp: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+            serial: 42,
+            record_count: 2,
+            records: vec![
+                record(
+                    RecordType::Syscall,
+                    &[
+                        ("syscall", "59"),
+                        ("success", "yes"),
+                        ("pid", "1234"),
+                        ("ppid", "1"),
+                        ("uid", "1000"),
+                        ("exe", "/usr/bin/cat"),
+                        ("a0", "7ffd"),
+                    ],
+                ),
+                record(
+                    RecordType::Path,
+                    &[
+                        ("name", "/etc/passwd"),
+                        ("inode", "131"),
+                        ("nametype", "NORMAL"),
+                    ],
+                ),
+            ],
+            amendment: false,
+        };
+
+        let doc = ecs_document(&event).unwrap();
+        assert_eq!(doc["@timestamp"], "2023-11-14T22:13:20.000Z");
+        assert_eq!(doc["event"]["action"], "execve");
+        assert_eq!(doc["event"]["outcome"], "success");
+        assert_eq!(doc["event"]["sequence"], 42);
+        assert_eq!(doc["process"]["pid"], "1234");
+        assert_eq!(doc["process"]["parent"]["pid"], "1");
+        assert_eq!(doc["process"]["executable"], "/usr/bin/cat");
+        assert_eq!(doc["user"]["id"], "1000");
+        assert_eq!(doc["file"]["path"], "/etc/passwd");
+        assert_eq!(doc["file"]["inode"], "131");
+        assert_eq!(doc["auditrs"]["syscall"]["a0"], "7ffd");
+        assert_eq!(doc["auditrs"]["syscall"]["syscall"], "59");
+        assert_eq!(doc["auditrs"]["syscall"]["success"], "yes");
+        assert_eq!(doc["auditrs"]["path"]["nametype"], "NORMAL");
+    }
+
+    #[test]
+    fn duplicate_mapped_fields_fall_back_to_namespace() {
+        let event = AuditEvent {
+            timestamp: UNIX_EPOCH,
+            serial: 1,
+            record_count: 2,
+            records: vec![
+                record(RecordType::Path, &[("name", "/tmp")]),
+                record(RecordType::Path, &[("name", "/tmp/x")]),
+            ],
+            amendment: false,
+        };
+
+        let doc = ecs_document(&event).unwrap();
+        assert_eq!(doc["file"]["path"], "/tmp");
+        assert_eq!(doc["event"]["action"], "path");
+        assert_eq!(doc["auditrs"]["path"][1]["name"], "/tmp/x");
+        assert_eq!(
+            doc["event"]["original"],
+            "type=PATH msg=audit(0.000:1): name=/tmp\ntype=PATH msg=audit(0.000:1): name=/tmp/x\n"
+        );
+        let line = format_ecs_event(&event).unwrap();
+        assert!(line.ends_with('\n') && !line.trim_end().contains('\n'));
+    }
+}
@@ -0,0 +1,340 @@
+//! Implementation of the journald sink, which writes events to the systemd
+//! journal using its native protocol.
+//!
+//! Each event is sent as one datagram holding one entry. An entry too large
+//! for a datagram is written to a sealed memfd instead, whose descriptor is
+//! passed to journald, as the protocol specifies. Every field is
+//! encoded either as `NAME=value\n` or, when the value contains a newline, as
+//! `NAME\n` followed by the value length (64-bit little endian), the value and
+//! `\n`. Entries carry `MESSAGE` (the event in legacy format), `PRIORITY`
+//! (from the event severity), `SYSLOG_IDENTIFIER=auditrs`, the event serial and
+//! timestamp, and per record `AUDIT_TYPE` plus one `AUDIT_<FIELD>` per record
+//! field. Multi-record events repeat these fields, which the journal stores as
+//! multiple values, so `journalctl AUDIT_TYPE=PATH` matches any event with a
+//! PATH record.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::core::correlator::AuditEvent;
+use crate::core::writer::{AuditLogWriter, JournaldSink};
+use crate::utils::systemtime_to_timestamp_string;
+
+/// The journald native protocol socket.
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Maximum length of a journal field name.
+const MAX_FIELD_NAME_LEN: usize = 64;
+
+impl JournaldSink {
+    /// Connects to the systemd journal. Fails if this is not a Linux host or
+    /// journald is not running (the socket does not exist).
+    pub fn connect() -> Result<Self> {
+        if !cfg!(target_os = "linux") {
+            anyhow::bail!("journald is only available on Linux");
+        }
+        Self::connect_to(Path::new(JOURNALD_SOCKET))
+    }
+
+    /// Connects to a journal socket at `path`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The journald native protocol socket.
+    pub fn connect_to(path: &Path) -> Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket
+            .connect(path)
+            .with_context(|| format!("journald socket {} is unavailable", path.display()))?;
+        Ok(Self { socket })
+    }
+
+    /// Sends one event to the journal as a structured entry.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to send.
+    /// * `priority`: The syslog priority (`0` emergency to `7` debug).
+    pub fn send_event(&self, event: &AuditEvent, priority: u8) -> Result<()> {
+        let entry = encode_journal_entry(event, priority)?;
+        match self.socket.send(&entry) {
+            Ok(_) => Ok(()),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EMSGSIZE | libc::ENOBUFS)) => {
+                self.send_via_memfd(&entry)
+            }
+            Err(e) => Err(e).context("failed to send entry to journald"),
+        }
+    }
+
+    /// Sends an entry too large for a datagram: writes it to a sealed memfd
+    /// and passes the descriptor to journald in an otherwise empty datagram.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `entry`: The encoded entry.
+    fn send_via_memfd(&self, entry: &[u8]) -> Result<()> {
+        let fd = unsafe {
+            libc::memfd_create(
+                c"auditrs-journal".as_ptr(),
+                libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error()).context("failed to create memfd for journald");
+        }
+        let mut memfd = unsafe { File::from_raw_fd(fd) };
+        memfd
+            .write_all(entry)
+            .context("failed to write entry to memfd")?;
+        let seals =
+            libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_WRITE | libc::F_SEAL_SEAL;
+        if unsafe { libc::fcntl(fd, libc::F_ADD_SEALS, seals) } < 0 {
+            return Err(io::Error::last_os_error()).context("failed to seal memfd for journald");
+        }
+        send_fd(&self.socket, memfd.as_raw_fd()).context("failed to pass memfd to journald")
+    }
+}
+
+/// Sends an empty datagram carrying `fd` as `SCM_RIGHTS` ancillary data.
+///
+/// **Parameters:**
+///
+/// * `socket`: The connected journald socket.
+/// * `fd`: The descriptor to pass.
+fn send_fd(socket: &UnixDatagram, fd: RawFd) -> io::Result<()> {
+    let fd_len = std::mem::size_of::<RawFd>() as u32;
+    let space = unsafe { libc::CMSG_SPACE(fd_len) } as usize;
+    // u64 elements keep the control buffer aligned for `cmsghdr`.
+    let mut control = vec![0u64; space.div_ceil(8)];
+    let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+    msg.msg_control = control.as_mut_ptr().cast();
+    msg.msg_controllen = space as _;
+    unsafe {
+        let cmsg = libc::CMSG_FIRSTHDR(&msg);
+        (*cmsg).cmsg_level = libc::SOL_SOCKET;
+        (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+        (*cmsg).cmsg_len = libc::CMSG_LEN(fd_len) as _;
+        std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>(), fd);
+    }
+    if unsafe { libc::sendmsg(socket.as_raw_fd(), &msg, libc::MSG_NOSIGNAL) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Encodes an event as a journal entry in the native protocol format.
+///
+/// **Parameters:**
+///
+/// * `event`: The event to encode.
+/// * `priority`: The syslog priority (`0` emergency to `7` debug).
+pub fn encode_journal_entry(event: &AuditEvent, priority: u8) -> Result<Vec<u8>> {
+    let mut entry = Vec::new();
+    let message = AuditLogWriter::format_legacy_event(event)?;
+    push_field(&mut entry, "MESSAGE", message.trim_end());
+    push_field(&mut entry, "PRIORITY", &priority.to_string());
+    push_field(&mut entry, "SYSLOG_IDENTIFIER", "auditrs");
+    push_field(&mut entry, "AUDIT_EVENT_SERIAL", &event.serial.to_string());
+    push_field(
+        &mut entry,
+        "AUDIT_EVENT_TIMESTAMP",
+        &systemtime_to_timestamp_string(event.timestamp)?,
+    );
+    for record in &event.records {
+        push_field(&mut entry, "AUDIT_TYPE", record.record_type.as_audit_str());
+        let mut fields: Vec<_> = record.fields.iter().collect();
+        fields.sort();
+        for (key, value) in fields {
+            if let Some(name) = journal_field_name(key) {
+                push_field(&mut entry, &name, value);
+            }
+        }
+    }
+    Ok(entry)
+}
+
+/// Maps an audit field name to a journal field name: `AUDIT_` followed by the
+/// name uppercased, with characters outside `[A-Z0-9_]` replaced by `_`.
+/// Returns `None` for an empty name.
+///
+/// **Parameters:**
+///
+/// * `key`: The audit field name.
+fn journal_field_name(key: &str) -> Option<String> {
+    if key.is_empty() {
+        return None;
+    }
+    let mut name = String::from("AUDIT_");
+    for c in key.chars() {
+        name.push(if c.is_ascii_alphanumeric() {
+            c.to_ascii_uppercase()
+        } else {
+            '_'
+        });
+    }
+    name.truncate(MAX_FIELD_NAME_LEN);
+    Some(name)
+}
+
+/// Appends one field in the native protocol encoding.
+///
+/// **Parameters:**
+///
+/// * `entry`: The entry being built.
+/// * `name`: A valid journal field name.
+/// * `value`: The field value.
+fn push_field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use crate::core::parser::{ParsedAuditRecord, RecordType};
+    use std::io::{Read, Seek, SeekFrom};
+    use std::time::{Duration, SystemTime};
+
+    fn record(record_type: RecordType, fields: &[(&str, &str)]) -> ParsedAuditRecord {
+        fields
+            .iter()
+            .fold(RecordBuilder::new(record_type), |record, (k, v)| {
+                record.field(k, v)
+            })
+            .serial(7)
+            .build()
+    }
+
+    fn event(records: Vec<ParsedAuditRecord>) -> AuditEvent {
+        AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_123),
+            serial: 7,
+            record_count: records.len() as u16,
+            records,
+            amendment: false,
+        }
+    }
+
+    #[test]
+    fn single_record_uses_plain_fields() {
+        let event = event(vec![record(
+            RecordType::Syscall,
+            &[("syscall", "59"), ("a0-x", "1")],
+        )]);
+        let entry = String::from_utf8(encode_journal_entry(&event, 6).unwrap()).unwrap();
+        let message = "type=SYSCALL msg=audit(1700000000.123:7):";
+        assert!(entry.starts_with(&format!("MESSAGE={message}")));
+        assert!(entry.ends_with(concat!(
+            "PRIORITY=6\n",
+            "SYSLOG_IDENTIFIER=auditrs\n",
+            "AUDIT_EVENT_SERIAL=7\n",
+            "AUDIT_EVENT_TIMESTAMP=1700000000.123\n",
+            "AUDIT_TYPE=SYSCALL\n",
+            "AUDIT_A0_X=1\n",
+            "AUDIT_SYSCALL=59\n",
+        )));
+    }
+
+    #[test]
+    fn multiline_values_are_length_prefixed() {
+        let event = event(vec![
+            record(RecordType::Syscall, &[("syscall", "2")]),
+            record(RecordType::Path, &[("name", "/tmp/a")]),
+        ]);
+        let entry = encode_journal_entry(&event, 3).unwrap();
+        let message = AuditLogWriter::format_legacy_event(&event).unwrap();
+        let message = message.trim_end();
+
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        expected.extend_from_slice(message.as_bytes());
+        expected.push(b'\n');
+        assert!(entry.starts_with(&expected));
+
+        let rest = String::from_utf8(entry[expected.len()..].to_vec()).unwrap();
+        assert!(rest.ends_with(concat!(
+            "AUDIT_TYPE=SYSCALL\n",
+            "AUDIT_SYSCALL=2\n",
+            "AUDIT_TYPE=PATH\n",
+            "AUDIT_NAME=/tmp/a\n",
+        )));
+    }
+
+    #[test]
+    fn sink_sends_one_datagram_per_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.socket");
+        let server = UnixDatagram::bind(&path).unwrap();
+        assert!(JournaldSink::connect_to(&dir.path().join("missing")).is_err());
+
+        let sink = JournaldSink::connect_to(&path).unwrap();
+        let event = event(vec![record(RecordType::Syscall, &[("syscall", "59")])]);
+        sink.send_event(&event, 5).unwrap();
+
+        let mut buf = [0u8; 4096];
+        let len = server.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..len], encode_journal_entry(&event, 5).unwrap());
+    }
+
+    #[test]
+    fn oversized_entry_is_passed_as_memfd() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("journal.socket");
+        let server = UnixDatagram::bind(&path).unwrap();
+        let sink = JournaldSink::connect_to(&path).unwrap();
+        // Far above the default socket send buffer, so the datagram fails
+        // with EMSGSIZE.
+        let proctitle = "a".repeat(4 * 1024 * 1024);
+        let event = event(vec![record(
+            RecordType::Proctitle,
+            &[("proctitle", &proctitle)],
+        )]);
+        sink.send_event(&event, 6).unwrap();
+
+        let (len, fd) = recv_fd(&server);
+        assert_eq!(len, 0);
+        let mut memfd = unsafe { File::from_raw_fd(fd.unwrap()) };
+        let seals = unsafe { libc::fcntl(memfd.as_raw_fd(), libc::F_GET_SEALS) };
+        assert_ne!(seals & libc::F_SEAL_WRITE, 0);
+        // The descriptor shares the sender's file offset, which is at the end.
+        memfd.seek(SeekFrom::Start(0)).unwrap();
+        let mut entry = Vec::new();
+        memfd.read_to_end(&mut entry).unwrap();
+        assert!(entry == encode_journal_entry(&event, 6).unwrap());
+    }
+
+    /// Receives one datagram, returning its length and the descriptor passed
+    /// with it, if any.
+    fn recv_fd(socket: &UnixDatagram) -> (usize, Option<RawFd>) {
+        let mut buf = [0u8; 16];
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr().cast(),
+            iov_len: buf.len(),
+        };
+        let mut control = [0u64; 8];
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr().cast();
+        msg.msg_controllen = std::mem::size_of_val(&control) as _;
+        let len = unsafe { libc::recvmsg(socket.as_raw_fd(), &mut msg, 0) };
+        assert!(len >= 0, "{}", io::Error::last_os_error());
+        let cmsg = unsafe { libc::CMSG_FIRSTHDR(&msg) };
+        let fd = (!cmsg.is_null() && unsafe { (*cmsg).cmsg_type } == libc::SCM_RIGHTS)
+            .then(|| unsafe { std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<RawFd>()) });
+        (len as usize, fd)
+    }
+}
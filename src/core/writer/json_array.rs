@@ -0,0 +1,170 @@
+//! Implementation of `JsonArrayWriter`, the streaming JSON array output.
+
+use std::io::Write;
+
+use anyhow::Result;
+
+use crate::core::correlator::AuditEvent;
+use crate::core::writer::{AuditLogWriter, JsonArrayWriter};
+
+impl<W: Write> JsonArrayWriter<W> {
+    /// Starts a new, empty JSON array on `inner`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `inner`: The output to write the document to.
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            has_elements: false,
+            finished: false,
+        }
+    }
+
+    /// Continues an unterminated array whose output ends right after an
+    /// element (e.g. output truncated back to a checkpoint).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `inner`: The output, positioned at the end of the last element.
+    /// * `has_elements`: Whether the opening `[` and at least one element have
+    ///   already been written.
+    pub fn resume(inner: W, has_elements: bool) -> Self {
+        Self {
+            inner,
+            has_elements,
+            finished: false,
+        }
+    }
+
+    /// Appends one event to the array.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to serialize.
+    pub fn write_event(&mut self, event: &AuditEvent) -> Result<()> {
+        if self.finished {
+            anyhow::bail!("JSON array has already been closed");
+        }
+        let separator = if self.has_elements { ",\n" } else { "[\n" };
+        write!(
+            self.inner,
+            "{}{}",
+            separator,
+            AuditLogWriter::format_json_event_pretty(event)?
+        )?;
+        self.has_elements = true;
+        Ok(())
+    }
+
+    /// Mutable access to the underlying output (e.g. to query its position).
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Closes the array and flushes the output. An array with no elements is
+    /// written as `[]`. Calling this more than once is a no-op.
+    pub fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        if self.has_elements {
+            writeln!(self.inner, "\n]")?;
+        } else {
+            writeln!(self.inner, "[]")?;
+        }
+        self.finished = true;
+        self.inner.flush()?;
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for JsonArrayWriter<W> {
+    /// Best-effort close so a dropped writer still leaves a valid document.
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use crate::core::parser::RecordType;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn event(serial: u16) -> AuditEvent {
+        let timestamp = UNIX_EPOCH + Duration::from_millis(1_700_000_000_123);
+        AuditEvent {
+            timestamp,
+            serial,
+            record_count: 1,
+            records: vec![
+                RecordBuilder::syscall()
+                    .ts(timestamp)
+                    .serial(serial)
+                    .field("syscall", "59")
+                    .build(),
+            ],
+            amendment: false,
+        }
+    }
+
+    #[test]
+    fn output_parses_back_to_event_list() {
+        let mut out = Vec::new();
+        {
+            let mut writer = JsonArrayWriter::new(&mut out);
+            for serial in 1..=3 {
+                writer.write_event(&event(serial)).unwrap();
+            }
+            writer.finish().unwrap();
+        }
+
+        let events: Vec<AuditEvent> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(events.len(), 3);
+        for (event, serial) in events.iter().zip(1..) {
+            assert_eq!(event.serial, serial);
+            assert_eq!(
+                event.timestamp,
+                UNIX_EPOCH + Duration::from_millis(1_700_000_000_123)
+            );
+            assert_eq!(event.records[0].record_type, RecordType::Syscall);
+            assert_eq!(event.records[0].fields["syscall"], "59");
+        }
+    }
+
+    #[test]
+    fn empty_and_dropped_arrays_are_closed() {
+        let mut out = Vec::new();
+        JsonArrayWriter::new(&mut out).finish().unwrap();
+        assert_eq!(String::from_utf8_lossy(&out), "[]\n");
+
+        let mut out = Vec::new();
+        {
+            let mut writer = JsonArrayWriter::new(&mut out);
+            writer.write_event(&event(1)).unwrap();
+        }
+        let events: Vec<AuditEvent> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn resume_continues_an_open_array() {
+        let mut out = Vec::new();
+        JsonArrayWriter::new(&mut out)
+            .write_event(&event(1))
+            .unwrap();
+        // Simulate the output being cut back to just after the first element.
+        let open_len = out.iter().rposition(|b| *b == b']').unwrap() - 1;
+        out.truncate(open_len);
+
+        let mut writer = JsonArrayWriter::resume(&mut out, true);
+        writer.write_event(&event(2)).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let events: Vec<AuditEvent> = serde_json::from_slice(&out).unwrap();
+        assert_eq!(events.iter().map(|e| e.serial).collect::<Vec<_>>(), [1, 2]);
+    }
+}
@@ -1,11 +1,35 @@
 //! Writer module for auditrs, responsible for writing events to disk.
+//!
+//! - `csv`: CSV rendering with per-record-type default columns, used by `search
+//!   --format csv`.
+//! - `ecs`: Elastic Common Schema mapping used by `LogFormat::Ecs`.
+//! - `journald`: the `WriteDestination::Journald` sink, which sends events to
+//!   the systemd journal over its native protocol.
+//! - `retry`: retries with backoff for failed event writes, and the dead-letter
+//!   file events go to once retries are exhausted.
+//! - `rollup`: rollup mode, which replaces per-event output with per-interval
+//!   counts.
 
+pub mod csv;
+pub mod ecs;
+mod journald;
+mod json_array;
+mod retry;
+mod rollup;
+mod sink_pool;
 mod writer;
 
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::fs::File;
+use std::io::Write;
+use std::os::unix::net::UnixDatagram;
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
 
-use crate::config::LogFormat;
+use serde::Deserialize;
+
+use crate::config::{EnrichedFormat, LogFormat, RecordSeparator};
+use crate::core::severity::{Severity, SeverityScorer};
 use crate::state::*;
 
 /// Main writer for audit logs, handles writing to the active log, journal, and
@@ -14,6 +38,18 @@ use crate::state::*;
 pub struct AuditLogWriter {
     /// The log format to use for the active log.
     log_format: LogFormat,
+    /// The separator written after each legacy record or simple-format line.
+    record_separator: RecordSeparator,
+    /// How enriched companion fields are written in legacy logs.
+    enriched_format: EnrichedFormat,
+    /// Whether legacy logs get a synthetic `EOE` record after each event.
+    legacy_eoe: bool,
+    /// Whether raw values are replaced with interpreted ones before writing.
+    interpret: bool,
+    /// Whether interpreted values keep the original under `<field>_raw`.
+    interpret_keep_raw: bool,
+    /// Whether JSON logs show interpreted and raw values side by side.
+    interpret_side_by_side: bool,
     /// The directory to write the active log to.
     active_directory: PathBuf,
     /// The directory to write the journal to.
@@ -32,10 +68,194 @@ pub struct AuditLogWriter {
     journal: AuditJournal,
     /// The primary log.
     primary: AuditPrimary,
+    /// Open handles for primary and routed log files, capped by
+    /// `max_open_sinks`.
+    sinks: SinkPool,
+    /// The journald sink, when `WriteDestination::Journald` is configured and
+    /// the journal socket is reachable.
+    journald: Option<JournaldSink>,
+    /// Scores events; used for the journal `PRIORITY` field and to pick the
+    /// `WriteDestination::File` sinks an event goes to.
+    severity: SeverityScorer,
+    /// The configured additional destinations.
+    destinations: Vec<WriteDestination>,
+    /// File destination writes that failed since the last
+    /// `AuditLogWriter::take_destination_failures`.
+    destination_failures: u64,
+    /// The rollup aggregating events in rollup mode (`rollup_interval > 0`),
+    /// whose summaries are written instead of the events themselves.
+    rollup: Option<Rollup>,
     /// The state of the auditrs configuration.
     state: State,
 }
 
+/// An additional destination written events are copied to, besides the
+/// active log (configured as `destinations` in the config file).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WriteDestination {
+    /// The systemd journal, as structured entries (`AUDIT_TYPE=SYSCALL`, ...)
+    /// sent over the journald native protocol socket. Linux only.
+    Journald,
+    /// A log file, in the active log's format, that receives only the events
+    /// scored between `min_severity` and `max_severity`, e.g.
+    /// `{ file = { path = "/var/log/auditrs/alerts.log", min_severity = "high"
+    /// } }`. Two files with adjacent ranges split output into tiers, such
+    /// as an alert file and bulk storage. A failed write to the file is
+    /// reported and counted but does not fail the event's write. In rollup
+    /// mode no events are written, so the file receives nothing.
+    File {
+        /// The file to append events to.
+        path: PathBuf,
+        /// Lowest severity written (default `info`, i.e. every event).
+        #[serde(default)]
+        min_severity: Severity,
+        /// Highest severity written; `None` (the default) has no upper bound.
+        #[serde(default)]
+        max_severity: Option<Severity>,
+    },
+}
+
+/// How failed event writes are retried (configured as `write_retries`,
+/// `write_retry_backoff_ms` and `dead_letter_path`).
+///
+/// A failed write is retried up to `max_retries` times, waiting
+/// `initial_backoff` before the first retry and twice as long before each
+/// further one (capped at `max_backoff`). An event that still cannot be
+/// written is appended to the dead-letter file, if one is configured, as one
+/// JSON object per line, which `auditrs convert` reads back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Retries after the first failed attempt; `0` disables retrying.
+    pub max_retries: u32,
+    /// Wait before the first retry.
+    pub initial_backoff: Duration,
+    /// Upper bound for the wait between retries.
+    pub max_backoff: Duration,
+    /// File events are appended to once every retry failed; `None` drops
+    /// them.
+    pub dead_letter: Option<PathBuf>,
+}
+
+/// How an event ended up after [`RetryPolicy`] was applied to its write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The event was written, after `retries` failed attempts.
+    Written {
+        /// Number of failed attempts before the successful one.
+        retries: u32,
+    },
+    /// Every attempt failed and the event was written to the dead-letter file.
+    DeadLettered,
+}
+
+/// The sink writes of one formatted event that have not succeeded yet, from
+/// [`AuditLogWriter::prepare_event`]. [`AuditLogWriter::write_pending`] drops
+/// each write once it succeeded, so calling it again after a failure only
+/// repeats the writes that failed.
+#[derive(Debug)]
+pub struct PendingWrites {
+    /// The event as formatted for the configured log format.
+    event_str: String,
+    /// The sinks still to be written, in order.
+    sinks: VecDeque<SinkTarget>,
+}
+
+/// A sink a formatted event is written to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SinkTarget {
+    /// The active log.
+    Active,
+    /// The primary log, for events matching a watch.
+    Primary,
+    /// A `WriteDestination::File` whose severity range includes the event's.
+    Destination(PathBuf),
+}
+
+/// An event attribute rollup summaries are grouped by (configured as
+/// `rollup_dimensions`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RollupDimension {
+    /// The type of the event's first record, e.g. `SYSCALL`.
+    RecordType,
+    /// The audit rule key (`key` field).
+    Key,
+    /// The user id (`uid` field).
+    Uid,
+    /// The login user id (`auid` field).
+    Auid,
+    /// The executable (`exe` field).
+    Exe,
+    /// The command name (`comm` field).
+    Comm,
+}
+
+/// Counts events per group of [`RollupDimension`] values over fixed
+/// intervals. Each group's count is emitted as a [`RollupSummary`] when the
+/// interval ends (and for the partial interval on shutdown).
+#[derive(Debug)]
+pub struct Rollup {
+    /// Length of an interval.
+    interval: Duration,
+    /// The dimensions events are grouped by, in output order.
+    dimensions: Vec<RollupDimension>,
+    /// When the current interval started.
+    interval_start: SystemTime,
+    /// Event counts of the current interval, keyed by the dimension values.
+    counts: BTreeMap<Vec<String>, u64>,
+}
+
+/// The number of events of one group over one rollup interval.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RollupSummary {
+    /// Start of the interval.
+    pub interval_start: SystemTime,
+    /// End of the interval (earlier than a full interval for partial rollups).
+    pub interval_end: SystemTime,
+    /// The group: each configured dimension with its value.
+    pub group: Vec<(RollupDimension, String)>,
+    /// Number of events in the group.
+    pub count: u64,
+}
+
+/// A connection to the systemd journal's native protocol socket
+/// (`/run/systemd/journal/socket`).
+#[derive(Debug)]
+pub struct JournaldSink {
+    /// Datagram socket connected to the journal.
+    socket: UnixDatagram,
+}
+
+/// A bounded set of open append-mode file handles for sink files (primary and
+/// routed logs). When more than `max_open` files are in use, the
+/// least-recently-written handle is closed; it is reopened in append mode the
+/// next time that file is written, so no data is lost.
+#[derive(Debug)]
+pub struct SinkPool {
+    /// Maximum number of handles kept open at once.
+    max_open: usize,
+    /// Open handles keyed by path, with the tick of their last use.
+    handles: HashMap<PathBuf, (File, u64)>,
+    /// Monotonic use counter used to find the least-recently-written handle.
+    tick: u64,
+}
+
+/// Streams events into a single JSON array document (`[event, event, ...]`)
+/// without rewriting earlier output: the opening `[` is written with the
+/// first element, each later element is preceded by `,`, and `]` is written
+/// by [`JsonArrayWriter::finish`] (or on drop, so the document is closed on
+/// graceful shutdown). The layout matches the daemon's JSON logs.
+pub struct JsonArrayWriter<W: Write> {
+    /// The underlying output.
+    inner: W,
+    /// Whether at least one element has been written (so the next one needs a
+    /// leading comma).
+    has_elements: bool,
+    /// Whether the closing `]` has been written.
+    finished: bool,
+}
+
 /// Represents the active log immediately written to by the daemon.
 /// Since writes are frequent, this struct contains a file handle for
 /// efficient writing.
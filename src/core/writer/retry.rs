@@ -0,0 +1,190 @@
+//! Implementation of write retries with exponential backoff and the
+//! dead-letter file.
+//!
+//! The daemon retries [`AuditLogWriter::write_pending`], which only repeats
+//! the sink writes that failed: when the active log was written but the
+//! primary log was not, only the primary log write is retried.
+//!
+//! [`AuditLogWriter::write_pending`]: crate::core::writer::AuditLogWriter::write_pending
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::config::AuditConfig;
+use crate::core::correlator::AuditEvent;
+use crate::core::writer::{RetryPolicy, WriteOutcome};
+
+/// Upper bound for the wait between retries.
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+
+impl RetryPolicy {
+    /// Builds the policy from the `write_retries`, `write_retry_backoff_ms`
+    /// and `dead_letter_path` settings.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `cfg`: The configuration to read.
+    pub fn from_config(cfg: &AuditConfig) -> Self {
+        Self {
+            max_retries: cfg.write_retries,
+            initial_backoff: Duration::from_millis(cfg.write_retry_backoff_ms),
+            max_backoff: MAX_BACKOFF,
+            dead_letter: cfg.dead_letter_path.as_ref().map(Into::into),
+        }
+    }
+
+    /// Returns the wait before retry number `retry` (starting at `0`).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `retry`: The zero-based retry number.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    /// Calls `write` until it succeeds or the retries are exhausted, then
+    /// appends `event` to the dead-letter file. Fails only if the event could
+    /// be neither written nor dead-lettered.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event being written, for the dead-letter file.
+    /// * `write`: Writes the event to its sinks; each call should only repeat
+    ///   the writes that failed before.
+    pub async fn write<F>(&self, event: &AuditEvent, mut write: F) -> Result<WriteOutcome>
+    where
+        F: FnMut() -> Result<()>,
+    {
+        let mut retries = 0;
+        let error = loop {
+            match write() {
+                Ok(()) => return Ok(WriteOutcome::Written { retries }),
+                Err(e) if retries >= self.max_retries => break e,
+                Err(e) => {
+                    eprintln!(
+                        "Failed to write audit event (retry {} of {}): {:?}",
+                        retries + 1,
+                        self.max_retries,
+                        e
+                    );
+                    tokio::time::sleep(self.backoff(retries)).await;
+                    retries += 1;
+                }
+            }
+        };
+
+        let Some(path) = &self.dead_letter else {
+            return Err(error);
+        };
+        append_dead_letter(path, event)
+            .with_context(|| format!("event could not be written ({:#})", error))?;
+        Ok(WriteOutcome::DeadLettered)
+    }
+}
+
+/// Appends `event` to the dead-letter file at `path` as one JSON line,
+/// creating the file if needed.
+///
+/// **Parameters:**
+///
+/// * `path`: The dead-letter file.
+/// * `event`: The event that could not be written.
+fn append_dead_letter(path: &Path, event: &AuditEvent) -> Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Could not open dead-letter file {}", path.display()))?;
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::correlator::RecordBuilder;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    fn event() -> AuditEvent {
+        AuditEvent {
+            timestamp: SystemTime::UNIX_EPOCH,
+            serial: 7,
+            record_count: 1,
+            records: vec![
+                RecordBuilder::syscall()
+                    .serial(7)
+                    .field("syscall", "59")
+                    .build(),
+            ],
+            amendment: false,
+        }
+    }
+
+    fn policy(dead_letter: Option<PathBuf>) -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(4),
+            dead_letter,
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_then_succeeding_sink_is_retried() {
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letter = dir.path().join("dead.jsonl");
+        let mut attempts = 0;
+        let outcome = policy(Some(dead_letter.clone()))
+            .write(&event(), || {
+                attempts += 1;
+                if attempts < 3 {
+                    anyhow::bail!("disk full");
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(outcome, WriteOutcome::Written { retries: 2 });
+        assert_eq!(attempts, 3);
+        assert!(!dead_letter.exists());
+        assert_eq!(policy(None).backoff(5), Duration::from_millis(4));
+    }
+
+    #[tokio::test]
+    async fn permanently_failing_sink_dead_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let dead_letter = dir.path().join("dead.jsonl");
+        let mut attempts = 0;
+        let mut failing = || {
+            attempts += 1;
+            anyhow::bail!("sink unreachable")
+        };
+
+        let outcome = policy(Some(dead_letter.clone()))
+            .write(&event(), &mut failing)
+            .await
+            .unwrap();
+        assert_eq!(outcome, WriteOutcome::DeadLettered);
+        assert!(policy(None).write(&event(), &mut failing).await.is_err());
+        assert_eq!(attempts, 8);
+
+        let content = std::fs::read_to_string(&dead_letter).unwrap();
+        let events: Vec<AuditEvent> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].serial, 7);
+        assert_eq!(events[0].records, event().records);
+    }
+}
@@ -0,0 +1,156 @@
+//! Implementation of `SinkPool`, the LRU cache of open sink file handles.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::core::writer::SinkPool;
+
+impl SinkPool {
+    /// Creates an empty pool that keeps at most `max_open` files open (a cap of
+    /// `0` is treated as `1`).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `max_open`: Maximum number of simultaneously open handles.
+    pub fn new(max_open: usize) -> Self {
+        Self {
+            max_open: max_open.max(1),
+            handles: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    /// Returns the handle for `path`, opening it in append mode (creating the
+    /// file if needed) and evicting the least-recently-written handle when the
+    /// pool is full.
+    ///
+    /// Handles are opened readable as well, since JSON logs rewrite their
+    /// trailing `]` in place.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The sink file to write to.
+    pub fn get(&mut self, path: &Path) -> Result<&mut File> {
+        self.tick += 1;
+        if !self.handles.contains_key(path) {
+            while self.handles.len() >= self.max_open {
+                self.evict_lru();
+            }
+            let file = OpenOptions::new()
+                .create(true)
+                .read(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("failed to open sink {}", path.display()))?;
+            self.handles.insert(path.to_path_buf(), (file, 0));
+        }
+        let (file, last_used) = self
+            .handles
+            .get_mut(path)
+            .expect("handle was inserted above");
+        *last_used = self.tick;
+        Ok(file)
+    }
+
+    /// Appends `data` to the sink at `path` and flushes it.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The sink file to write to.
+    /// * `data`: The bytes to append.
+    pub fn write_all(&mut self, path: &Path, data: &[u8]) -> Result<()> {
+        let file = self.get(path)?;
+        file.write_all(data)?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Changes the cap, closing least-recently-written handles until the pool
+    /// fits.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `max_open`: The new maximum number of open handles.
+    pub fn set_max_open(&mut self, max_open: usize) {
+        self.max_open = max_open.max(1);
+        while self.handles.len() > self.max_open {
+            self.evict_lru();
+        }
+    }
+
+    /// Closes every open handle (e.g. after the sink directories change).
+    pub fn close_all(&mut self) {
+        self.handles.clear();
+    }
+
+    /// Returns the number of currently open handles.
+    pub fn open_count(&self) -> usize {
+        self.handles.len()
+    }
+
+    /// Closes the least-recently-written handle.
+    fn evict_lru(&mut self) {
+        let lru = self
+            .handles
+            .iter()
+            .min_by_key(|(_, (_, last_used))| *last_used)
+            .map(|(path, _)| path.clone());
+        if let Some(path) = lru {
+            self.handles.remove(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reopens_evicted_sinks_without_data_loss() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<_> = ["key_a", "key_b", "key_c"]
+            .iter()
+            .map(|key| dir.path().join(format!("{key}.log")))
+            .collect();
+
+        let mut pool = SinkPool::new(2);
+        for round in 0..3 {
+            for path in &paths {
+                pool.write_all(path, format!("line {round}\n").as_bytes())
+                    .unwrap();
+                assert!(pool.open_count() <= 2);
+            }
+        }
+
+        for path in &paths {
+            let contents = std::fs::read_to_string(path).unwrap();
+            assert_eq!(contents, "line 0\nline 1\nline 2\n");
+        }
+    }
+
+    #[test]
+    fn evicts_least_recently_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.log");
+        let b = dir.path().join("b.log");
+        let c = dir.path().join("c.log");
+
+        let mut pool = SinkPool::new(2);
+        pool.write_all(&a, b"a").unwrap();
+        pool.write_all(&b, b"b").unwrap();
+        pool.write_all(&a, b"a").unwrap();
+        pool.write_all(&c, b"c").unwrap();
+
+        assert_eq!(pool.open_count(), 2);
+        assert!(pool.handles.contains_key(&a));
+        assert!(!pool.handles.contains_key(&b));
+
+        pool.set_max_open(1);
+        assert_eq!(pool.open_count(), 1);
+        assert!(pool.handles.contains_key(&c));
+    }
+}
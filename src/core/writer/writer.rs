@@ -14,19 +14,44 @@
 
 use anyhow::Result;
 use serde_json;
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
 use std::fs::{File, OpenOptions, create_dir_all};
 use std::io::{Seek, SeekFrom, Write};
 use std::os::unix::fs::FileExt;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-use crate::config::{AuditConfig, LogFormat};
+use crate::config::{AuditConfig, EnrichedFormat, LogFormat, RecordSeparator};
 use crate::core::{
-    correlator::AuditEvent,
-    writer::{AuditActive, AuditJournal, AuditLogWriter, AuditPrimary},
+    correlator::{AuditEvent, JoinDetection},
+    enricher::{interpret_event, interpret_event_as_companions, interpreted_fields},
+    metrics::PipelineWarning,
+    parser::{ENRICHED_SEPARATORS, ParsedAuditRecord, RecordType, is_enriched_field},
+    severity::{Severity, SeverityScorer},
+    writer::{
+        AuditActive,
+        AuditJournal,
+        AuditLogWriter,
+        AuditPrimary,
+        JournaldSink,
+        PendingWrites,
+        Rollup,
+        RollupSummary,
+        SinkPool,
+        SinkTarget,
+        WriteDestination,
+        ecs::format_ecs_event,
+    },
 };
 use crate::rules::FilterAction;
 use crate::state::{Rules, State};
-use crate::utils::{current_utc_string, systemtime_to_timestamp_string, systemtime_to_utc_string};
+use crate::utils::{
+    current_utc_string,
+    escape_control_chars,
+    systemtime_to_timestamp_string,
+    systemtime_to_utc_string,
+};
 
 // TODO: this whole module needs to be closely looked over, a lot of IO is
 // happening here and we want to make sure its not wasting resources.
@@ -79,6 +104,12 @@ impl AuditLogWriter {
 
         let mut writer = Self {
             log_format: config.log_format,
+            record_separator: config.record_separator,
+            enriched_format: config.enriched_format,
+            legacy_eoe: config.legacy_eoe,
+            interpret: config.interpret,
+            interpret_keep_raw: config.interpret_keep_raw,
+            interpret_side_by_side: config.interpret_side_by_side,
             active_directory,
             journal_directory,
             primary_directory,
@@ -92,6 +123,12 @@ impl AuditLogWriter {
             },
             journal: AuditJournal { paths: Vec::new() },
             primary: AuditPrimary { paths: Vec::new() },
+            sinks: SinkPool::new(config.max_open_sinks),
+            journald: connect_journald(&config.destinations),
+            severity: SeverityScorer::new(&config.severity_overrides)?,
+            destinations: config.destinations.clone(),
+            destination_failures: 0,
+            rollup: new_rollup(config),
             state: state,
         };
         // Immediately check if the log file is too large and create a new one if it is
@@ -101,7 +138,24 @@ impl AuditLogWriter {
     }
 
     /// Writes a single correlated `AuditEvent` to the active log (and
-    /// optionally to the primary log).
+    /// optionally to the primary log and file destinations), as
+    /// [`AuditLogWriter::prepare_event`] followed by
+    /// [`AuditLogWriter::write_pending`].
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The `AuditEvent` to be written.
+    pub fn write_event(&mut self, event: AuditEvent) -> Result<()> {
+        match self.prepare_event(&event)? {
+            Some(mut pending) => self.write_pending(&mut pending),
+            None => Ok(()),
+        }
+    }
+
+    /// Filters, interprets and formats an event, sends it to journald, and
+    /// returns the writes it needs: the active log, the primary log if the
+    /// event matches a watch, and each file destination whose severity range
+    /// includes the event's.
     ///
     /// The concrete output format is determined by `log_format`:
     ///
@@ -109,20 +163,87 @@ impl AuditLogWriter {
     /// - `LogFormat::Simple`: human-readable summary via `Display` on
     ///   `AuditEvent`.
     /// - `LogFormat::Json`: JSON representation (not yet implemented).
+    /// - `LogFormat::Ecs`: one Elastic Common Schema document per line.
+    ///
+    /// Legacy and simple output is framed with the configured
+    /// `record_separator`; JSON output is a single array document and ECS
+    /// output is NDJSON, so both always use LF.
     ///
-    /// After writing, this function also enforces the active log size limit,
-    /// rotating the file into the journal when necessary.
+    /// In rollup mode the event is only counted (see
+    /// [`AuditLogWriter::tick_rollup`]) and `None` is returned: nothing is
+    /// written to the active or primary log or to file destinations, while
+    /// journald still receives every event.
     ///
     /// **Parameters:**
     ///
-    /// * `event`: The `AuditEvent` to be written.
-    pub fn write_event(&mut self, mut event: AuditEvent) -> Result<()> {
+    /// * `event`: The `AuditEvent` to be written; it is only copied if filters
+    ///   or interpretation change it.
+    pub fn prepare_event(&mut self, event: &AuditEvent) -> Result<Option<PendingWrites>> {
+        let mut event = Cow::Borrowed(event);
         self.apply_filters(&mut event);
         let write_primary = self.check_watch_events(&event);
-        match self.log_format {
-            LogFormat::Legacy => self.write_event_legacy(event, write_primary)?,
-            LogFormat::Simple => self.write_event_simple(event, write_primary)?,
-            LogFormat::Json => self.write_event_json(event, write_primary)?,
+        if self.interpret {
+            if self.log_format == LogFormat::Legacy {
+                event = Cow::Owned(interpret_event_as_companions(event.into_owned()));
+            } else if !self.json_side_by_side() {
+                event = Cow::Owned(interpret_event(event.into_owned(), self.interpret_keep_raw));
+            }
+        }
+        let severity = self.severity.event_severity(&event);
+        if let Some(journald) = &self.journald {
+            let priority = severity.syslog_priority();
+            if let Err(e) = journald.send_event(&event, priority) {
+                eprintln!("Failed to write event to journald: {:?}", e);
+            }
+        }
+        if let Some(rollup) = &mut self.rollup {
+            rollup.add(&event);
+            self.tick_rollup()?;
+            return Ok(None);
+        }
+        let mut sinks = VecDeque::from([SinkTarget::Active]);
+        if write_primary {
+            sinks.push_back(SinkTarget::Primary);
+        }
+        sinks.extend(
+            self.destinations
+                .iter()
+                .filter_map(|destination| destination.file_for(severity))
+                .map(|path| SinkTarget::Destination(path.to_path_buf())),
+        );
+        Ok(Some(PendingWrites {
+            event_str: self.format_event(&event)?,
+            sinks,
+        }))
+    }
+
+    /// Performs the writes of `pending` in order, removing each one that
+    /// succeeded, and then enforces the active log size limit, rotating the
+    /// file into the journal when necessary. After a failure, calling this
+    /// again retries only the writes that did not succeed, so no sink
+    /// receives the event twice.
+    ///
+    /// File destinations are best effort: a failed destination write is
+    /// reported and counted (see
+    /// [`AuditLogWriter::take_destination_failures`]) and does not fail the
+    /// event's write.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `pending`: The writes from [`AuditLogWriter::prepare_event`].
+    pub fn write_pending(&mut self, pending: &mut PendingWrites) -> Result<()> {
+        while let Some(target) = pending.sinks.front() {
+            match target {
+                SinkTarget::Active => self.write_active(&pending.event_str)?,
+                SinkTarget::Primary => self.write_primary(&pending.event_str)?,
+                SinkTarget::Destination(path) => {
+                    if let Err(e) = self.write_sink(path, &pending.event_str, "destination") {
+                        self.destination_failures += 1;
+                        eprintln!("Failed to write event to {}: {:?}", path.display(), e);
+                    }
+                }
+            }
+            pending.sinks.pop_front();
         }
         // TODO: We should be checking to see if writing an event would exceed the log
         // size limit. if so, log rotation should be triggered then rather than
@@ -130,82 +251,190 @@ impl AuditLogWriter {
         self.check_log_size()
     }
 
-    /// Writes an `AuditEvent` using the legacy audit log format.
-    ///
-    /// The output takes the form:
-    /// ```ignore
-    /// type=... msg=audit(<timestamp>:<serial>): key1=val1 key2=val2 ...
-    /// ```
+    /// Returns the number of failed file destination writes since the last
+    /// call, and resets it.
+    pub fn take_destination_failures(&mut self) -> u64 {
+        std::mem::take(&mut self.destination_failures)
+    }
+
+    /// In rollup mode, writes the rollup summaries once the current interval
+    /// has ended. Called for every event and periodically by the daemon, so
+    /// intervals also end while no events arrive.
+    pub fn tick_rollup(&mut self) -> Result<()> {
+        let now = SystemTime::now();
+        if self
+            .rollup
+            .as_ref()
+            .is_some_and(|rollup| rollup.is_due(now))
+        {
+            self.flush_rollup()?;
+        }
+        Ok(())
+    }
+
+    /// In rollup mode, ends the current interval now and writes its
+    /// summaries, even if the interval is not over yet (e.g. on shutdown).
+    pub fn flush_rollup(&mut self) -> Result<()> {
+        let Some(rollup) = &mut self.rollup else {
+            return Ok(());
+        };
+        let summaries = rollup.take(SystemTime::now());
+        if summaries.is_empty() {
+            return Ok(());
+        }
+        self.write_rollup_summaries(&summaries)?;
+        self.check_log_size()
+    }
+
+    /// Writes rollup summaries to the active log: legacy and simple logs get
+    /// `type=ROLLUP` lines, JSON and ECS logs one JSON object per summary.
     ///
     /// **Parameters:**
     ///
-    /// * `event`: The correlated `AuditEvent` to render.
-    /// * `write_primary`: When `true`, the same formatted line is also written
-    ///   to the primary log in addition to the active log.
-    pub fn write_event_legacy(&mut self, event: AuditEvent, write_primary: bool) -> Result<()> {
-        let event_str = Self::format_legacy_event(&event)?;
+    /// * `summaries`: The summaries to write.
+    fn write_rollup_summaries(&mut self, summaries: &[RollupSummary]) -> Result<()> {
+        self.write_synthetic_records(
+            summaries,
+            RollupSummary::to_legacy_line,
+            RollupSummary::to_json,
+        )
+    }
 
-        write!(self.active.file_handle, "{}", event_str)?;
+    /// Writes records that auditrs produces itself (rollup summaries, join
+    /// detections, warnings) to the active log: legacy and simple logs get
+    /// one line per record, JSON and ECS logs one JSON object per record.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `records`: The records to write.
+    /// * `legacy_line`: Formats a record as a legacy line (with a trailing
+    ///   newline).
+    /// * `json`: Returns a record as a JSON object.
+    fn write_synthetic_records<T>(
+        &mut self,
+        records: &[T],
+        legacy_line: impl Fn(&T) -> Result<String>,
+        json: impl Fn(&T) -> serde_json::Value,
+    ) -> Result<()> {
+        for record in records {
+            match self.log_format {
+                LogFormat::Legacy | LogFormat::Simple => {
+                    let line = self.record_separator.apply(&legacy_line(record)?);
+                    write!(self.active.file_handle, "{}", line)?;
+                }
+                LogFormat::Json => {
+                    let element = serde_json::to_string_pretty(&json(record))?;
+                    Self::append_json_array_element(
+                        &mut self.active.file_handle,
+                        &element,
+                        "active",
+                    )?;
+                }
+                LogFormat::Ecs => writeln!(self.active.file_handle, "{}", json(record))?,
+            }
+        }
         self.active.file_handle.flush()?;
+        Ok(())
+    }
 
-        if write_primary {
-            self.write_primary(event_str)?;
-        }
+    /// Writes join detections to the active log, formatted like rollup
+    /// summaries: `type=JOIN` lines in legacy and simple logs, one JSON object
+    /// per detection in JSON and ECS logs.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `detections`: The detections to write.
+    pub fn write_join_detections(&mut self, detections: &[JoinDetection]) -> Result<()> {
+        self.write_synthetic_records(
+            detections,
+            JoinDetection::to_legacy_line,
+            JoinDetection::to_json,
+        )?;
+        self.check_log_size()
+    }
 
-        Ok(())
+    /// Writes pipeline warnings (`warning_events`) to the active log, formatted
+    /// like join detections: `type=AUDITRS_WARNING` lines in legacy and simple
+    /// logs, one JSON object per warning in JSON and ECS logs. Warnings are
+    /// written even in rollup mode.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `warnings`: The warnings to write.
+    pub fn write_warnings(&mut self, warnings: &[PipelineWarning]) -> Result<()> {
+        self.write_synthetic_records(
+            warnings,
+            PipelineWarning::to_legacy_line,
+            PipelineWarning::to_json,
+        )?;
+        self.check_log_size()
     }
 
-    /// Writes an `AuditEvent` using the "simple" format.
+    /// Formats an `AuditEvent` as written to the active log.
     ///
-    /// The output takes the form:
+    /// The legacy output takes the form:
+    /// ```ignore
+    /// type=... msg=audit(<timestamp>:<serial>): key1=val1 key2=val2 ...
+    /// ```
+    ///
+    /// The simple output takes the form:
     /// ```ignore
     /// [UTC timestamp][Record Count: <number of records>][Audit Event Group <serial>]:
     ///     Record: <record type> <record data>
     ///     ...
     ///     Record: <record type> <record data>
     /// ```
-    /// This format delegates to the `Display` implementation of `AuditEvent`,
-    /// producing a concise, human-readable representation.
+    /// delegating to the `Display` implementation of `AuditEvent`. JSON logs
+    /// get one array element per event and ECS logs one document per line
+    /// (see [`crate::core::writer::ecs`] for the field mapping).
     ///
     /// **Parameters:**
     ///
-    /// * `event`: The event to format and write.
-    /// * `write_primary`: When `true`, also mirrors the simple-formatted event
-    ///   into the primary log.
-    fn write_event_simple(&mut self, event: AuditEvent, write_primary: bool) -> Result<()> {
-        let event_str = Self::format_simple_event(&event);
-
-        write!(self.active.file_handle, "{}", event_str)?;
-        self.active.file_handle.flush()?;
-
-        if write_primary {
-            self.write_primary(event_str)?;
-        }
-
-        Ok(())
+    /// * `event`: The correlated `AuditEvent` to render.
+    fn format_event(&self, event: &AuditEvent) -> Result<String> {
+        Ok(match self.log_format {
+            LogFormat::Legacy => {
+                let mut event_str = Self::format_legacy_event_as(event, self.enriched_format)?;
+                if self.legacy_eoe {
+                    event_str.push_str(&Self::format_synthetic_eoe(event)?);
+                }
+                self.record_separator.apply(&event_str)
+            }
+            LogFormat::Simple => {
+                self.record_separator
+                    .apply(&Self::format_simple_event(event))
+            }
+            // TODO: We should add an option for condensed JSON to save space.
+            LogFormat::Json => Self::format_json_event_as(event, self.json_side_by_side())?,
+            LogFormat::Ecs => format_ecs_event(event)?,
+        })
     }
 
-    /// Writes an `AuditEvent` as JSON.
-    ///
-    /// This is a placeholder for a future JSON log format implementation.
+    /// Appends a formatted event to the active log: as a JSON array element
+    /// in JSON logs, otherwise as is.
     ///
     /// **Parameters:**
     ///
-    /// * `event`: The event to serialize as JSON.
-    /// * `write_primary`: When `true`, the JSON representation will also be
-    ///   written to the primary log.
-    fn write_event_json(&mut self, event: AuditEvent, write_primary: bool) -> Result<()> {
-        // TODO: We should add an option for condensed JSON to save space.
-        let event_str = Self::format_json_event_pretty(&event)?;
-
-        Self::append_json_array_element(&mut self.active.file_handle, &event_str, "active")?;
-
-        if write_primary {
-            self.write_primary(event_str)?;
+    /// * `event_str`: The formatted event.
+    fn write_active(&mut self, event_str: &str) -> Result<()> {
+        if self.log_format == LogFormat::Json {
+            return Self::append_json_array_element(
+                &mut self.active.file_handle,
+                event_str,
+                "active",
+            );
         }
-
+        write!(self.active.file_handle, "{}", event_str)?;
+        self.active.file_handle.flush()?;
         Ok(())
     }
+
+    /// Returns whether JSON logs show interpreted values next to the raw ones
+    /// instead of interpreting events in place.
+    fn json_side_by_side(&self) -> bool {
+        self.interpret && self.interpret_side_by_side && self.log_format == LogFormat::Json
+    }
+
     /// Appends a single log line to the primary log.
     ///
     /// If no primary log file exists yet for the current configuration, this
@@ -215,7 +444,7 @@ impl AuditLogWriter {
     /// **Parameters:**
     ///
     /// * `line`: Fully formatted log line to be appended to the primary log.
-    fn write_primary(&mut self, line: String) -> Result<()> {
+    fn write_primary(&mut self, line: &str) -> Result<()> {
         // Get the latest primary log path, creating one if it doesn't exist yet.
         let path: PathBuf = if let Some(last) = self.primary.paths.last() {
             last.clone()
@@ -229,48 +458,156 @@ impl AuditLogWriter {
             new_path
         };
 
-        let mut file_handle = OpenOptions::new()
-            .create(true)
-            .read(true)
-            .write(true)
-            .append(true)
-            .open(&path)?;
-        // A little messy but ok for now
+        self.write_sink(&path, line, "primary")
+    }
+
+    /// Appends a formatted event to the sink file at `path`: as a JSON array
+    /// element in JSON logs, otherwise as is.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The sink file.
+    /// * `event_str`: The formatted event.
+    /// * `target`: Name of the sink for error messages, e.g. `primary`.
+    fn write_sink(&mut self, path: &Path, event_str: &str, target: &str) -> Result<()> {
         if self.log_format == LogFormat::Json {
-            Self::append_json_array_element(&mut file_handle, &line, "primary")?;
-            return Ok(());
+            let file_handle = self.sinks.get(path)?;
+            return Self::append_json_array_element(file_handle, event_str, target);
         }
+        self.sinks.write_all(path, event_str.as_bytes())
+    }
 
-        write!(file_handle, "{}", line)?;
-        file_handle.flush()?;
-        Ok(())
+    /// Formats a single [`AuditEvent`] as a legacy audit log string (including
+    /// trailing newlines per record), preserving enriched companion fields
+    /// (see [`AuditLogWriter::format_legacy_event_as`]).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The `AuditEvent` to format.
+    pub(crate) fn format_legacy_event(event: &AuditEvent) -> Result<String> {
+        Self::format_legacy_event_as(event, EnrichedFormat::default())
     }
 
     /// Formats a single [`AuditEvent`] as a legacy audit log string (including
     /// trailing newlines per record).
     ///
+    /// Control characters in field names and values are escaped (see
+    /// [`crate::utils::escape_control_chars`]) so every record stays on one
+    /// line and the only enriched separator is the one written here.
+    ///
     /// **Parameters:**
     ///
     /// * `event`: The `AuditEvent` to format.
-    fn format_legacy_event(event: &AuditEvent) -> Result<String> {
+    /// * `enriched_format`: Whether enriched companion fields follow a `\x1d`
+    ///   separator or are written like the raw fields.
+    pub(crate) fn format_legacy_event_as(
+        event: &AuditEvent,
+        enriched_format: EnrichedFormat,
+    ) -> Result<String> {
         let mut event_str = String::new();
         for record in &event.records {
-            let mut prefix = String::new();
-            let mut fields = String::new();
-            prefix.push_str(&format!(
-                "type={} msg=audit({}:{}):",
-                record.record_type.as_audit_str(),
-                systemtime_to_timestamp_string(event.timestamp)?,
-                event.serial
-            ));
-            for field in &record.fields {
-                fields.push_str(&format!(" {}={}", field.0, field.1));
-            }
-            event_str.push_str(&format!("{}{}\n", prefix, fields));
+            event_str.push_str(&Self::format_legacy_record(
+                event,
+                record,
+                &record.fields,
+                enriched_format,
+            )?);
         }
         Ok(event_str)
     }
 
+    /// Formats one record of `event` as a legacy audit log line (including the
+    /// trailing newline), writing `fields` in the order given.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event the record belongs to; its timestamp and serial are
+    ///   written in the `msg=audit(...)` prefix.
+    /// * `record`: The record to format.
+    /// * `fields`: The record's fields, in output order.
+    /// * `enriched_format`: Whether enriched companion fields follow a `\x1d`
+    ///   separator or are written like the raw fields.
+    pub(crate) fn format_legacy_record<'a>(
+        event: &AuditEvent,
+        record: &ParsedAuditRecord,
+        fields: impl IntoIterator<Item = (&'a String, &'a String)>,
+        enriched_format: EnrichedFormat,
+    ) -> Result<String> {
+        let prefix = format!(
+            "type={} msg=audit({}:{}):",
+            record.record_type.as_audit_str(),
+            systemtime_to_timestamp_string(event.timestamp)?,
+            event.serial
+        );
+        let mut raw = String::new();
+        let mut enriched = String::new();
+        for (key, value) in fields {
+            let target = if enriched_format == EnrichedFormat::Preserve && is_enriched_field(key) {
+                &mut enriched
+            } else {
+                &mut raw
+            };
+            target.push_str(&format!(
+                " {}={}",
+                escape_control_chars(key),
+                escape_control_chars(value)
+            ));
+        }
+        if let Some(enriched) = enriched.strip_prefix(' ') {
+            raw.push(ENRICHED_SEPARATORS[0]);
+            raw.push_str(enriched);
+        }
+        Ok(format!("{}{}\n", prefix, raw))
+    }
+
+    /// Returns the fields of `record` as a JSON object in which each
+    /// interpretable field is `{"raw": ..., "interpreted": ...}`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `record`: The record whose fields to convert.
+    fn side_by_side_fields(record: &ParsedAuditRecord) -> serde_json::Value {
+        let interpreted: HashMap<String, String> = interpreted_fields(record).into_iter().collect();
+        let fields: serde_json::Map<String, serde_json::Value> = record
+            .fields
+            .iter()
+            .map(|(field, raw)| {
+                let value = match interpreted.get(field) {
+                    Some(interpreted) => {
+                        serde_json::json!({
+                            "raw": raw,
+                            "interpreted": interpreted,
+                        })
+                    }
+                    None => serde_json::json!(raw),
+                };
+                (field.clone(), value)
+            })
+            .collect();
+        serde_json::Value::Object(fields)
+    }
+
+    /// Formats the `type=EOE` line that ends `event` in legacy output, or an
+    /// empty string if the event already has an `EOE` record.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to terminate.
+    fn format_synthetic_eoe(event: &AuditEvent) -> Result<String> {
+        if event
+            .records
+            .iter()
+            .any(|record| record.record_type == RecordType::Eoe)
+        {
+            return Ok(String::new());
+        }
+        Ok(format!(
+            "type=EOE msg=audit({}:{}):\n",
+            systemtime_to_timestamp_string(event.timestamp)?,
+            event.serial
+        ))
+    }
+
     /// Formats a single [`AuditEvent`] in the simple (human-readable) format.
     ///
     /// **Parameters:**
@@ -286,7 +623,19 @@ impl AuditLogWriter {
     /// **Parameters:**
     ///
     /// * `event`: The `AuditEvent` to format.
-    fn format_json_event_pretty(event: &AuditEvent) -> Result<String> {
+    pub(crate) fn format_json_event_pretty(event: &AuditEvent) -> Result<String> {
+        Self::format_json_event_as(event, false)
+    }
+
+    /// Pretty-printed JSON for one [`AuditEvent`], optionally writing each
+    /// interpretable field as `{"raw": ..., "interpreted": ...}`.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The `AuditEvent` to format, with raw (uninterpreted) values.
+    /// * `side_by_side`: Whether interpretable fields show both values; other
+    ///   fields stay plain strings.
+    pub(crate) fn format_json_event_as(event: &AuditEvent, side_by_side: bool) -> Result<String> {
         let mut event_json = serde_json::json!({
             "timestamp": systemtime_to_utc_string(event.timestamp), // TODO: Is UTC string the right choice?
             "serial": event.serial,
@@ -300,7 +649,11 @@ impl AuditLogWriter {
                 "record_type": record.record_type.as_audit_str(),
                 "timestamp": systemtime_to_utc_string(event.timestamp),
                 "serial": record.serial, // TODO: take this out, redundant?
-                "fields": record.fields,
+                "fields": if side_by_side {
+                    Self::side_by_side_fields(record)
+                } else {
+                    serde_json::json!(record.fields)
+                },
             });
 
             // The "cmd" field gets encoded into hex, we should decode for readability.
@@ -310,6 +663,13 @@ impl AuditLogWriter {
             // }
             records_array.push(record_json);
         }
+        let paths = Self::ordered_paths(event);
+        if !paths.is_empty() {
+            event_json["paths"] = serde_json::json!(paths);
+        }
+        if event.amendment {
+            event_json["amendment"] = serde_json::json!(true);
+        }
         // Tab are added for more accurate JSON pretty print formatting.
         let event_str = serde_json::to_string_pretty(&event_json)?
             .lines()
@@ -319,6 +679,29 @@ impl AuditLogWriter {
         Ok(event_str)
     }
 
+    /// Returns the fields of the event's PATH records ordered by their `item`
+    /// index, so JSON consumers can rely on `paths[0]` being item 0. Records
+    /// without a numeric `item` sort last, in their original order.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The `AuditEvent` whose PATH records are collected.
+    fn ordered_paths(event: &AuditEvent) -> Vec<&HashMap<String, String>> {
+        let mut paths: Vec<_> = event
+            .records
+            .iter()
+            .filter(|record| record.record_type == RecordType::Path)
+            .map(|record| &record.fields)
+            .collect();
+        paths.sort_by_key(|fields| {
+            fields
+                .get("item")
+                .and_then(|item| item.parse::<u32>().ok())
+                .unwrap_or(u32::MAX)
+        });
+        paths
+    }
+
     /// Append a JSON element into a file that is maintained as a single
     /// top-level JSON array.
     ///
@@ -392,6 +775,20 @@ impl AuditLogWriter {
         Ok(())
     }
 
+    /// Writes `events` to `path` as Elastic Common Schema NDJSON.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The path to the file to write the events to.
+    /// * `events`: The `AuditEvent`s to write.
+    pub fn write_events_ecs<W: Write>(w: &mut W, events: &[AuditEvent]) -> Result<()> {
+        for event in events {
+            write!(w, "{}", format_ecs_event(event)?)?;
+        }
+        w.flush()?;
+        Ok(())
+    }
+
     /// Writes `events` to `path` as a single top-level JSON array ).
     /// Uses the same incremental array layout as active logs.
     ///
@@ -428,14 +825,18 @@ impl AuditLogWriter {
     ///
     /// **Parameters:**
     ///
-    /// * `event`: The `AuditEvent` to apply the filters to.
-    fn apply_filters(&mut self, event: &mut AuditEvent) {
+    /// * `event`: The `AuditEvent` to apply the filters to; it is only copied
+    ///   if a record is blocked.
+    fn apply_filters(&self, event: &mut Cow<'_, AuditEvent>) {
         let filters = &self.state.rules.filters.0;
-        event.records.retain(|record| {
-            !filters.iter().any(|filter| {
+        let blocked = |record: &ParsedAuditRecord| {
+            filters.iter().any(|filter| {
                 filter.record_type == record.record_type && filter.action == FilterAction::Block
             })
-        });
+        };
+        if event.records.iter().any(blocked) {
+            event.to_mut().records.retain(|record| !blocked(record));
+        }
     }
 
     /// Check if the audit event contains a record with a key identifier that
@@ -575,11 +976,40 @@ impl AuditLogWriter {
         let new_journal_dir = PathBuf::from(&cfg.journal_directory);
         let new_primary_dir = PathBuf::from(&cfg.primary_directory);
         let new_format = cfg.log_format;
+        let severity = SeverityScorer::new(&cfg.severity_overrides)?;
+
+        // Summaries of the running interval use the old settings and log.
+        let rollup_unchanged = match &self.rollup {
+            Some(rollup) => {
+                rollup.has_settings(
+                    Duration::from_secs(cfg.rollup_interval),
+                    &cfg.rollup_dimensions,
+                )
+            }
+            None => cfg.rollup_interval == 0,
+        };
+        if !rollup_unchanged {
+            self.flush_rollup()?;
+            self.rollup = new_rollup(cfg);
+        }
 
         // Apply size and toggle changes
+        self.severity = severity;
+        // (Re)connect when journald was enabled or could not be reached before.
+        if cfg.destinations.contains(&WriteDestination::Journald) != self.journald.is_some() {
+            self.journald = connect_journald(&cfg.destinations);
+        }
+        self.destinations = cfg.destinations.clone();
+        self.record_separator = cfg.record_separator;
+        self.enriched_format = cfg.enriched_format;
+        self.legacy_eoe = cfg.legacy_eoe;
+        self.interpret = cfg.interpret;
+        self.interpret_keep_raw = cfg.interpret_keep_raw;
+        self.interpret_side_by_side = cfg.interpret_side_by_side;
         self.log_size = cfg.log_size;
         self.journal_size = cfg.journal_size;
         self.primary_size = cfg.primary_size;
+        self.sinks.set_max_open(cfg.max_open_sinks);
 
         // Ensure the (possibly new) directories exist
         create_dir_all(&new_active_dir)?;
@@ -598,6 +1028,7 @@ impl AuditLogWriter {
 
         if format_changed || active_dir_changed || journal_dir_changed || primary_dir_changed {
             let _ = self.rotate_active_into_journal();
+            self.sinks.close_all();
         }
 
         // Apply new settings
@@ -615,12 +1046,81 @@ impl AuditLogWriter {
     }
 }
 
+/// Builds the rollup for `cfg`, or `None` when rollup mode is off
+/// (`rollup_interval` is `0`).
+///
+/// **Parameters:**
+///
+/// * `cfg`: The configuration to read the rollup settings from.
+fn new_rollup(cfg: &AuditConfig) -> Option<Rollup> {
+    (cfg.rollup_interval > 0).then(|| {
+        Rollup::new(
+            Duration::from_secs(cfg.rollup_interval),
+            cfg.rollup_dimensions.clone(),
+            SystemTime::now(),
+        )
+    })
+}
+
+impl WriteDestination {
+    /// Returns the file of a `File` destination whose severity range includes
+    /// `severity`, or `None` for other destinations and severities.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `severity`: The severity of the event to route.
+    pub fn file_for(&self, severity: Severity) -> Option<&Path> {
+        match self {
+            WriteDestination::File {
+                path,
+                min_severity,
+                max_severity,
+            } if severity >= *min_severity && max_severity.is_none_or(|max| severity <= max) => {
+                Some(path.as_path())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Connects to journald if `destinations` includes
+/// `WriteDestination::Journald`. An unreachable journal is reported and
+/// skipped rather than failing the writer.
+///
+/// **Parameters:**
+///
+/// * `destinations`: The configured write destinations.
+fn connect_journald(destinations: &[WriteDestination]) -> Option<JournaldSink> {
+    if !destinations.contains(&WriteDestination::Journald) {
+        return None;
+    }
+    match JournaldSink::connect() {
+        Ok(sink) => Some(sink),
+        Err(e) => {
+            eprintln!("journald destination disabled: {:?}", e);
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{
-        core::parser::{ParsedAuditRecord, RecordType},
+        config::DEFAULT_MAX_OPEN_SINKS,
+        core::{
+            correlator::CorrelationMode,
+            metrics::PipelineMetrics,
+            parser::{
+                ParseOptions,
+                ParsedAuditRecord,
+                RecordType,
+                parser::{DEFAULT_MAX_FIELD_LEN, parse_line},
+            },
+            writer::{RollupDimension, csv::format_csv},
+        },
         rules::{AuditWatch, Filters, WatchAction, Watches},
+        utils::{parse_ecs_events, parse_json_events, parse_legacy_events, parse_simple_events},
     };
     use serial_test::serial;
     use std::{collections::HashMap, path::Path, time::SystemTime};
@@ -635,6 +1135,33 @@ mod tests {
                 journal_size: 10,
                 log_format: LogFormat::Legacy,
                 primary_size: 1024,
+                stats_interval: 0,
+                no_events_warning: 60,
+                record_separator: RecordSeparator::Lf,
+                max_open_sinks: DEFAULT_MAX_OPEN_SINKS,
+                interpret: false,
+                interpret_keep_raw: true,
+                interpret_side_by_side: false,
+                severity_overrides: HashMap::new(),
+                destinations: Vec::new(),
+                rollup_interval: 0,
+                rollup_dimensions: Vec::new(),
+                correlation_mode: CorrelationMode::Batch,
+                correlation_quantize_timestamps: false,
+                joins: Vec::new(),
+                memory_budget: 0,
+                warning_events: false,
+                enriched_format: EnrichedFormat::Preserve,
+                legacy_eoe: false,
+                write_retries: 0,
+                write_retry_backoff_ms: 100,
+                dead_letter_path: None,
+                max_field_len: DEFAULT_MAX_FIELD_LEN,
+                audit_rules_dir: None,
+                audit_rules_delete_all: false,
+                tail_log: None,
+                tail_state_path: String::new(),
+                metrics_file: None,
             },
             rules: Rules {
                 filters: Filters(Vec::new()),
@@ -662,12 +1189,18 @@ mod tests {
                         serial: 1,
                         record_type: RecordType::AddGroup,
                         fields: HashMap::from([("key".to_string(), "value".to_string())]),
+                        quoted: Default::default(),
+                        truncated: Default::default(),
+                        nested: Default::default(),
                     },
                     ParsedAuditRecord {
                         timestamp: timestamp,
                         serial: 1,
                         record_type: RecordType::DelGroup,
                         fields: HashMap::from([("key_2".to_string(), "value_2".to_string())]),
+                        quoted: Default::default(),
+                        truncated: Default::default(),
+                        nested: Default::default(),
                     },
                 ]
             } else {
@@ -676,8 +1209,12 @@ mod tests {
                     serial: 1,
                     record_type: RecordType::AddGroup,
                     fields: HashMap::from([("key".to_string(), "value".to_string())]),
+                    quoted: Default::default(),
+                    truncated: Default::default(),
+                    nested: Default::default(),
                 }]
             },
+            amendment: false,
         }
     }
 
@@ -695,7 +1232,11 @@ mod tests {
                     "key".to_string(),
                     "auditrs_watch_1234567890".to_string(),
                 )]),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
             }],
+            amendment: false,
         }
     }
 
@@ -739,6 +1280,156 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    /// Timestamps are written at the precision they were parsed with, so
+    /// microsecond and nanosecond fractions read back unchanged.
+    fn legacy_timestamps_keep_sub_millisecond_precision() {
+        for (fraction, nanos) in [
+            ("123", 123_000_000),
+            ("123456", 123_456_000),
+            ("123456789", 123_456_789),
+        ] {
+            let line = format!(
+                "type=SYSCALL msg=audit(1700000000.{}:7): syscall=59\n",
+                fraction
+            );
+            let events = parse_legacy_events(&line).unwrap();
+            assert_eq!(
+                events[0].timestamp,
+                SystemTime::UNIX_EPOCH + Duration::new(1_700_000_000, nanos)
+            );
+            assert_eq!(
+                AuditLogWriter::format_legacy_event(&events[0]).unwrap(),
+                line
+            );
+        }
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// Rollup mode writes counts instead of events, including the partial
+    /// interval when flushed.
+    fn rollup_mode_writes_summaries() {
+        let mut state = get_state();
+        state.config.rollup_interval = 3600;
+        state.config.rollup_dimensions = vec![RollupDimension::RecordType, RollupDimension::Key];
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        for _ in 0..3 {
+            writer.write_event(create_event(false)).unwrap();
+        }
+        let path = Path::new("./tmp/auditrs/active/auditrs.log");
+        assert_eq!(std::fs::read_to_string(path).unwrap(), "");
+
+        writer.flush_rollup().unwrap();
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert_eq!(contents.lines().count(), 1);
+        assert!(contents.starts_with("type=ROLLUP msg=audit("));
+        assert!(contents.ends_with(" record_type=ADD_GROUP key=value count=3\n"));
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// A drop counted in the metrics shows up as a warning record between the
+    /// events around it.
+    fn dropped_record_produces_warning_record() {
+        let mut writer = AuditLogWriter::new(Some(get_state())).unwrap();
+        let metrics = PipelineMetrics::new();
+        let previous = metrics.snapshot();
+        writer.write_event(create_event(false)).unwrap();
+        metrics.dropped();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let warnings = metrics.snapshot().warnings_since(&previous, now);
+        writer.write_warnings(&warnings).unwrap();
+        writer.write_event(create_event(false)).unwrap();
+
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert_eq!(
+            contents,
+            "type=ADD_GROUP msg=audit(0.000:1): key=value\n\
+             type=AUDITRS_WARNING msg=audit(1.000:0): kind=dropped count=1\n\
+             type=ADD_GROUP msg=audit(0.000:1): key=value\n"
+        );
+        cleanup();
+    }
+
+    #[test]
+    /// Enriched input written back out keeps exactly one `\x1d` before the
+    /// companion fields when preserved, none when flattened, and parses back
+    /// to the same fields either way.
+    fn enriched_fields_round_trip() {
+        let line = "type=SYSCALL msg=audit(1700000000.000:7): arch=c000003e syscall=59 \
+                    auid=1000 key=(null)\x1dARCH=x86_64 SYSCALL=execve AUID=\"alice\"";
+        let record = parse_line(line, &ParseOptions::default()).unwrap();
+        assert_eq!(record.fields["key"], "(null)");
+        assert_eq!(record.fields["AUID"], "alice");
+        let event = AuditEvent {
+            timestamp: record.timestamp,
+            serial: record.serial,
+            record_count: 1,
+            records: vec![record.clone()],
+            amendment: false,
+        };
+
+        let preserved =
+            AuditLogWriter::format_legacy_event_as(&event, EnrichedFormat::Preserve).unwrap();
+        let (raw, enriched) = preserved.trim_end().split_once('\x1d').unwrap();
+        assert!(!enriched.contains(ENRICHED_SEPARATORS));
+        assert!(raw.contains(" syscall=59") && !raw.contains("SYSCALL="));
+        assert!(
+            enriched
+                .split(' ')
+                .all(|pair| is_enriched_field(pair.split('=').next().unwrap()))
+        );
+
+        let flattened =
+            AuditLogWriter::format_legacy_event_as(&event, EnrichedFormat::Flatten).unwrap();
+        assert!(!flattened.contains(ENRICHED_SEPARATORS));
+        assert!(flattened.contains(" SYSCALL=execve"));
+
+        for output in [preserved, flattened] {
+            let reparsed = parse_line(output.trim_end(), &ParseOptions::default()).unwrap();
+            assert_eq!(reparsed.fields, record.fields);
+        }
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// NUL-separated legacy output frames each record with `\0` and never
+    /// contains a newline.
+    fn write_event_legacy_nul_separated() {
+        let mut state = get_state();
+        state.config.record_separator = RecordSeparator::Nul;
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        writer.write_event(create_event(true)).unwrap();
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert!(!contents.contains('\n'));
+        assert_eq!(
+            contents.split('\0').collect::<Vec<_>>(),
+            vec![
+                "type=ADD_GROUP msg=audit(0.000:1): key=value",
+                "type=DEL_GROUP msg=audit(0.000:1): key_2=value_2",
+                "",
+            ]
+        );
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    fn write_event_legacy_crlf_separated() {
+        let mut state = get_state();
+        state.config.record_separator = RecordSeparator::Crlf;
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        writer.write_event(create_event(false)).unwrap();
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert_eq!(contents, "type=ADD_GROUP msg=audit(0.000:1): key=value\r\n");
+        cleanup();
+    }
+
     #[test]
     #[serial(writer)]
     /// Test an event with multiple records within it. Legacy formatting does
@@ -779,6 +1470,121 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    #[serial(writer)]
+    /// Severity-ranged file destinations split events into an alert tier and
+    /// a bulk tier, while the active log keeps every event.
+    fn file_destinations_split_events_by_severity() {
+        let mut state = get_state();
+        let alerts = PathBuf::from("./tmp/auditrs/alerts.log");
+        let bulk = PathBuf::from("./tmp/auditrs/bulk.log");
+        state.config.destinations = vec![
+            WriteDestination::File {
+                path: alerts.clone(),
+                min_severity: Severity::High,
+                max_severity: None,
+            },
+            WriteDestination::File {
+                path: bulk.clone(),
+                min_severity: Severity::Info,
+                max_severity: Some(Severity::Medium),
+            },
+        ];
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        let mut denial = create_event(false);
+        denial.serial = 2;
+        denial.records[0].record_type = RecordType::Avc;
+        writer.write_event(denial).unwrap();
+        writer.write_event(create_event(false)).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&alerts).unwrap(),
+            "type=AVC msg=audit(0.000:2): key=value\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(&bulk).unwrap(),
+            "type=ADD_GROUP msg=audit(0.000:1): key=value\n"
+        );
+        let active =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert_eq!(active.lines().count(), 2);
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// After a failed primary log write, writing the pending writes again
+    /// only repeats that write, so the active log gets the event once.
+    fn pending_writes_retry_only_failed_sinks() {
+        let mut writer = AuditLogWriter::new(Some(get_state())).unwrap();
+        let mut pending = writer
+            .prepare_event(&create_event_with_watch_key())
+            .unwrap()
+            .unwrap();
+        std::fs::remove_dir_all("./tmp/auditrs/primary").unwrap();
+
+        assert!(writer.write_pending(&mut pending).is_err());
+        assert_eq!(pending.sinks, [SinkTarget::Primary]);
+        create_dir_all("./tmp/auditrs/primary").unwrap();
+        writer.write_pending(&mut pending).unwrap();
+
+        assert!(pending.sinks.is_empty());
+        let line = "type=ADD_GROUP msg=audit(0.000:1): key=auditrs_watch_1234567890\n";
+        let primary_path = writer.primary.paths.last().unwrap();
+        assert_eq!(std::fs::read_to_string(primary_path).unwrap(), line);
+        assert_eq!(
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap(),
+            line
+        );
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// A file destination that cannot be written is counted, while the event
+    /// is still written to the active log.
+    fn failed_destination_writes_are_counted_not_fatal() {
+        let mut state = get_state();
+        state.config.destinations = vec![WriteDestination::File {
+            path: PathBuf::from("./tmp/auditrs/missing/events.log"),
+            min_severity: Severity::Info,
+            max_severity: None,
+        }];
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+
+        writer.write_event(create_event(false)).unwrap();
+        writer.write_event(create_event(false)).unwrap();
+
+        assert_eq!(writer.take_destination_failures(), 2);
+        assert_eq!(writer.take_destination_failures(), 0);
+        let active =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert_eq!(active.lines().count(), 2);
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    /// With `legacy_eoe`, a compound event ends with a synthetic EOE record,
+    /// and an event that has its own EOE record gets no second one.
+    fn write_event_legacy_synthetic_eoe() {
+        let mut state = get_state();
+        state.config.legacy_eoe = true;
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        writer.write_event(create_event(true)).unwrap();
+        let mut terminated = create_event(false);
+        terminated.records[0].record_type = RecordType::Eoe;
+        writer.write_event(terminated).unwrap();
+
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        assert_eq!(
+            contents,
+            "type=ADD_GROUP msg=audit(0.000:1): key=value\ntype=DEL_GROUP msg=audit(0.000:1): key_2=value_2\ntype=EOE msg=audit(0.000:1):\ntype=EOE msg=audit(0.000:1): key=value\n"
+        );
+        cleanup();
+    }
+
     #[test]
     #[serial(writer)]
     fn write_event_legacy_mixed_items() {
@@ -952,6 +1758,136 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    fn json_paths_are_ordered_by_item() {
+        let timestamp = SystemTime::UNIX_EPOCH;
+        let record = |record_type, fields: &[(&str, &str)]| {
+            ParsedAuditRecord {
+                record_type,
+                timestamp,
+                serial: 1,
+                fields: fields
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
+            }
+        };
+        let event = AuditEvent {
+            timestamp,
+            serial: 1,
+            record_count: 3,
+            records: vec![
+                record(RecordType::Syscall, &[("syscall", "82"), ("items", "2")]),
+                record(RecordType::Path, &[("item", "1"), ("name", "/tmp/new")]),
+                record(RecordType::Path, &[("item", "0"), ("name", "/tmp/old")]),
+            ],
+            amendment: false,
+        };
+
+        let json = AuditLogWriter::format_json_event_pretty(&event).unwrap();
+        let obj: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let paths = obj["paths"].as_array().unwrap();
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[0]["item"], "0");
+        assert_eq!(paths[0]["name"], "/tmp/old");
+        assert_eq!(paths[1]["item"], "1");
+        assert_eq!(paths[1]["name"], "/tmp/new");
+        // The flat records array is kept in arrival order.
+        let records = obj["records"].as_array().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1]["fields"]["name"], "/tmp/new");
+
+        let json = AuditLogWriter::format_json_event_pretty(&create_event(true)).unwrap();
+        let obj: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(obj.get("paths").is_none());
+    }
+
+    #[test]
+    fn json_marks_streaming_amendments() {
+        let mut event = create_event(false);
+        let json = AuditLogWriter::format_json_event_pretty(&event).unwrap();
+        let obj: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(obj.get("amendment").is_none());
+
+        event.amendment = true;
+        let json = AuditLogWriter::format_json_event_pretty(&event).unwrap();
+        let obj: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(obj["amendment"], true);
+        assert_eq!(obj["serial"], 1);
+    }
+
+    #[test]
+    #[serial(writer)]
+    fn json_side_by_side_shows_raw_and_interpreted_values() {
+        let mut state = get_state();
+        state.config.log_format = LogFormat::Json;
+        state.config.interpret = true;
+        state.config.interpret_side_by_side = true;
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        let record = parse_line(
+            "type=SYSCALL msg=audit(0.000:1): uid=0 pid=42",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        writer
+            .write_event(AuditEvent {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_count: 1,
+                records: vec![record],
+                amendment: false,
+            })
+            .unwrap();
+
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.json")).unwrap();
+        let events: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        let fields = &events[0]["records"][0]["fields"];
+        assert_eq!(
+            fields["uid"],
+            serde_json::json!({"raw": "0", "interpreted": "root"})
+        );
+        // Fields without an interpretation stay plain values.
+        assert_eq!(fields["pid"], "42");
+        assert!(fields.get("uid_raw").is_none());
+        cleanup();
+    }
+
+    #[test]
+    #[serial(writer)]
+    fn interpreted_legacy_output_reads_back_raw_values() {
+        let mut state = get_state();
+        state.config.interpret = true;
+        let mut writer = AuditLogWriter::new(Some(state)).unwrap();
+        let record = parse_line(
+            "type=SYSCALL msg=audit(0.000:1): uid=0 pid=42",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+        writer
+            .write_event(AuditEvent {
+                timestamp: SystemTime::UNIX_EPOCH,
+                serial: 1,
+                record_count: 1,
+                records: vec![record],
+                amendment: false,
+            })
+            .unwrap();
+
+        let contents =
+            std::fs::read_to_string(Path::new("./tmp/auditrs/active/auditrs.log")).unwrap();
+        // Raw values stay in place; the names follow the enriched separator.
+        assert!(contents.contains(" uid=0"), "{contents:?}");
+        assert!(contents.ends_with("\u{1d}UID=root\n"), "{contents:?}");
+        let read_back = parse_line(contents.trim_end(), &ParseOptions::default()).unwrap();
+        assert_eq!(read_back.get_u64("uid"), Some(0));
+        assert_eq!(read_back.get("UID"), Some("root"));
+        cleanup();
+    }
+
     #[test]
     #[serial(writer)]
     fn write_event_json_multiple_top_level_events() {
@@ -1102,6 +2038,50 @@ mod tests {
         cleanup();
     }
 
+    #[test]
+    fn output_is_valid_utf8_for_non_utf8_input() {
+        let raw_title = b"cat\x00/tmp/\xff\xfe\nname";
+        let timestamp = SystemTime::UNIX_EPOCH;
+        let event = crate::core::enricher::enrich_event(AuditEvent {
+            timestamp,
+            serial: 7,
+            record_count: 1,
+            records: vec![ParsedAuditRecord {
+                timestamp,
+                serial: 7,
+                record_type: RecordType::Proctitle,
+                fields: HashMap::from([
+                    ("proctitle".to_string(), hex::encode(raw_title)),
+                    ("note".to_string(), "line\nbreak\u{7}".to_string()),
+                ]),
+                quoted: Default::default(),
+                truncated: Default::default(),
+                nested: Default::default(),
+            }],
+            amendment: false,
+        });
+
+        let mut legacy = Vec::new();
+        AuditLogWriter::write_events_legacy(&mut legacy, std::slice::from_ref(&event)).unwrap();
+        let mut ecs = Vec::new();
+        AuditLogWriter::write_events_ecs(&mut ecs, std::slice::from_ref(&event)).unwrap();
+
+        for output in [&legacy, &ecs] {
+            let text = std::str::from_utf8(output).expect("output is valid UTF-8");
+            assert!(!text.starts_with('\u{feff}'));
+            assert_eq!(text.lines().count(), 1);
+        }
+        let legacy = String::from_utf8(legacy).unwrap();
+        assert!(legacy.contains("note=line\\x0abreak\\x07"));
+
+        let title = &event.records[0].fields["proctitle_plaintext"];
+        assert_eq!(title, "cat /tmp/\\xff\\xfe\\x0aname");
+        assert_eq!(
+            crate::utils::unescape_bytes(title),
+            b"cat /tmp/\xff\xfe\nname"
+        );
+    }
+
     #[test]
     #[serial(writer)]
     fn reload_config() {
@@ -1116,6 +2096,33 @@ mod tests {
             journal_size: 100,
             log_format: LogFormat::Simple,
             primary_size: 10240,
+            stats_interval: 0,
+            no_events_warning: 60,
+            record_separator: RecordSeparator::Lf,
+            max_open_sinks: DEFAULT_MAX_OPEN_SINKS,
+            interpret: false,
+            interpret_keep_raw: true,
+            interpret_side_by_side: false,
+            severity_overrides: HashMap::new(),
+            destinations: Vec::new(),
+            rollup_interval: 0,
+            rollup_dimensions: Vec::new(),
+            correlation_mode: CorrelationMode::Batch,
+            correlation_quantize_timestamps: false,
+            joins: Vec::new(),
+            memory_budget: 0,
+            warning_events: false,
+            enriched_format: EnrichedFormat::Preserve,
+            legacy_eoe: false,
+            write_retries: 0,
+            write_retry_backoff_ms: 100,
+            dead_letter_path: None,
+            max_field_len: DEFAULT_MAX_FIELD_LEN,
+            audit_rules_dir: None,
+            audit_rules_delete_all: false,
+            tail_log: None,
+            tail_state_path: String::new(),
+            metrics_file: None,
         };
         writer.reload_config(&new_config).unwrap();
         assert!(Path::new("./tmp/auditrs/NEW_CONFIG/active/auditrs.slog").exists());
@@ -1149,4 +2156,193 @@ mod tests {
         assert_eq!(writer.state.rules, new_rules);
         cleanup();
     }
+
+    /// Events with single- and multi-record groups for the format round-trip
+    /// tests. Timestamps are whole milliseconds and values have no spaces or
+    /// quotes, so every format can represent them exactly.
+    fn round_trip_events() -> Vec<AuditEvent> {
+        let event = |millis: u64, serial: u16, records: &[(RecordType, &[(&str, &str)])]| {
+            let timestamp = SystemTime::UNIX_EPOCH + Duration::from_millis(millis);
+            let records: Vec<ParsedAuditRecord> = records
+                .iter()
+                .map(|(record_type, fields)| {
+                    ParsedAuditRecord {
+                        record_type: *record_type,
+                        timestamp,
+                        serial,
+                        fields: fields
+                            .iter()
+                            .map(|(k, v)| (k.to_string(), v.to_string()))
+                            .collect(),
+                        quoted: Default::default(),
+                        truncated: Default::default(),
+                        nested: Default::default(),
+                    }
+                })
+                .collect();
+            AuditEvent {
+                timestamp,
+                serial,
+                record_count: records.len() as u16,
+                records,
+                amendment: false,
+            }
+        };
+        vec![
+            event(
+                1_700_000_000_123,
+                42,
+                &[
+                    (
+                        RecordType::Syscall,
+                        &[
+                            ("arch", "c000003e"),
+                            ("syscall", "59"),
+                            ("success", "yes"),
+                            ("exit", "0"),
+                            ("pid", "4242"),
+                            ("uid", "0"),
+                            ("comm", "ls"),
+                            ("exe", "/usr/bin/ls"),
+                            ("key", "exec"),
+                        ],
+                    ),
+                    (RecordType::Cwd, &[("cwd", "/root")]),
+                    (
+                        RecordType::Path,
+                        &[
+                            ("item", "0"),
+                            ("name", "/usr/bin/ls"),
+                            ("nametype", "NORMAL"),
+                        ],
+                    ),
+                ],
+            ),
+            event(
+                1_700_000_001_456,
+                43,
+                &[(
+                    RecordType::UserLogin,
+                    &[
+                        ("pid", "2101"),
+                        ("uid", "0"),
+                        ("auid", "1000"),
+                        ("addr", "10.0.0.5"),
+                        ("terminal", "ssh"),
+                        ("res", "failed"),
+                    ],
+                )],
+            ),
+        ]
+    }
+
+    #[test]
+    fn formats_round_trip_through_their_readers() {
+        type Writer = fn(&[AuditEvent]) -> Result<String>;
+        type Reader = fn(&str) -> Result<Vec<AuditEvent>>;
+        let formats: [(&str, Writer, Reader); 5] = [
+            (
+                "legacy",
+                |events| {
+                    let mut out = Vec::new();
+                    AuditLogWriter::write_events_legacy(&mut out, events)?;
+                    Ok(String::from_utf8(out)?)
+                },
+                parse_legacy_events,
+            ),
+            (
+                "simple",
+                |events| {
+                    let mut out = Vec::new();
+                    AuditLogWriter::write_events_simple(&mut out, events)?;
+                    Ok(String::from_utf8(out)?)
+                },
+                parse_simple_events,
+            ),
+            (
+                "json",
+                |events| {
+                    let elements = events
+                        .iter()
+                        .map(AuditLogWriter::format_json_event_pretty)
+                        .collect::<Result<Vec<_>>>()?;
+                    Ok(format!("[\n{}\n]\n", elements.join(",\n")))
+                },
+                parse_json_events,
+            ),
+            (
+                "json report",
+                |events| Ok(serde_json::to_string_pretty(events)?),
+                parse_json_events,
+            ),
+            (
+                "ecs",
+                |events| {
+                    let mut out = Vec::new();
+                    AuditLogWriter::write_events_ecs(&mut out, events)?;
+                    Ok(String::from_utf8(out)?)
+                },
+                parse_ecs_events,
+            ),
+        ];
+
+        let events = round_trip_events();
+        for (name, write, read) in formats {
+            let output = write(&events).unwrap();
+            let read_back = read(&output).unwrap_or_else(|e| panic!("{name}: {e:?}"));
+            assert_eq!(read_back.len(), events.len(), "{name}");
+            for (read, written) in read_back.iter().zip(&events) {
+                assert_eq!(
+                    (read.timestamp, read.serial, read.record_count),
+                    (written.timestamp, written.serial, written.record_count),
+                    "{name}"
+                );
+                assert_eq!(read.records, written.records, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn mapped_formats_match_golden_output() {
+        let events = round_trip_events();
+
+        let mut ecs = Vec::new();
+        AuditLogWriter::write_events_ecs(&mut ecs, &events).unwrap();
+        assert_eq!(
+            String::from_utf8(ecs).unwrap(),
+            concat!(
+                r#"{"@timestamp":"2023-11-14T22:13:20.123Z","auditrs":{"cwd":{},"#,
+                r#""path":{"item":"0","nametype":"NORMAL"},"record_count":3,"serial":42,"#,
+                r#""syscall":{"arch":"c000003e","exit":"0","key":"exec","success":"yes","#,
+                r#""syscall":"59"}},"event":{"action":"execve","kind":"event","module":"auditd","#,
+                r#""original":"type=SYSCALL msg=audit(1700000000.123:42): arch=c000003e "#,
+                r#"comm=ls exe=/usr/bin/ls exit=0 key=exec pid=4242 success=yes syscall=59 uid=0"#,
+                r#"\ntype=CWD msg=audit(1700000000.123:42): cwd=/root\ntype=PATH "#,
+                r#"msg=audit(1700000000.123:42): item=0 name=/usr/bin/ls nametype=NORMAL\n","#,
+                r#""outcome":"success","sequence":42},"file":{"path":"/usr/bin/ls"},"#,
+                r#""process":{"executable":"/usr/bin/ls","name":"ls","pid":"4242","#,
+                r#""working_directory":"/root"},"user":{"id":"0"}}"#,
+                "\n",
+                r#"{"@timestamp":"2023-11-14T22:13:21.456Z","auditrs":{"record_count":1,"#,
+                r#""serial":43,"user_login":{"res":"failed","terminal":"ssh"}},"#,
+                r#""event":{"action":"user_login","kind":"event","module":"auditd","#,
+                r#""original":"type=USER_LOGIN msg=audit(1700000001.456:43): addr=10.0.0.5 "#,
+                r#"auid=1000 pid=2101 res=failed terminal=ssh uid=0\n","#,
+                r#""outcome":"failure","sequence":43},"process":{"pid":"2101"},"#,
+                r#""source":{"ip":"10.0.0.5"},"user":{"audit":{"id":"1000"},"id":"0"}}"#,
+                "\n",
+            )
+        );
+
+        assert_eq!(
+            format_csv(&events),
+            "timestamp,serial,type,addr,arch,auid,comm,cwd,exe,exit,item,key,name,nametype,\
+             pid,res,success,syscall,terminal,uid\n\
+             2023-11-14T22:13:20.123Z,42,SYSCALL,,c000003e,,ls,,/usr/bin/ls,0,,exec,,,4242,,yes,\
+             59,,0\n\
+             2023-11-14T22:13:20.123Z,42,CWD,,,,,/root,,,,,,,,,,,,\n\
+             2023-11-14T22:13:20.123Z,42,PATH,,,,,,,,0,,/usr/bin/ls,NORMAL,,,,,,\n\
+             2023-11-14T22:13:21.456Z,43,USER_LOGIN,10.0.0.5,,1000,,,,,,,,,2101,failed,,,ssh,0\n"
+        );
+    }
 }
@@ -9,6 +9,7 @@ use anyhow::{Context, Result, anyhow};
 use colorized::*;
 use std::time::Duration;
 
+use crate::daemon::WorkerOptions;
 use crate::daemon::daemon::{is_running, read_pid, start_daemon, stop_daemon};
 
 /// Starts the `auditrs` daemon if it is not already running.
@@ -24,7 +25,13 @@ use crate::daemon::daemon::{is_running, read_pid, start_daemon, stop_daemon};
 /// * `skip_auditd_preflight`: If true, do not fail when legacy `auditd` appears
 ///   to be running (CLI: `--force`). Unsafe if auditd actually holds the audit
 ///   session.
-pub fn start_auditrs(reboot: bool, skip_auditd_preflight: bool) -> Result<()> {
+/// * `options`: Worker options from the CLI (stats interval, correlation
+///   diagnostics).
+pub fn start_auditrs(
+    reboot: bool,
+    skip_auditd_preflight: bool,
+    options: WorkerOptions,
+) -> Result<()> {
     if is_running()? {
         if !reboot {
             colorize_println("Daemon is already running", Colors::BrightGreenFg);
@@ -37,7 +44,7 @@ pub fn start_auditrs(reboot: bool, skip_auditd_preflight: bool) -> Result<()> {
         );
     }
     println!("Starting auditrs...");
-    start_daemon(skip_auditd_preflight).context("Failed to start daemon")?;
+    start_daemon(skip_auditd_preflight, options).context("Failed to start daemon")?;
     if !reboot {
         colorize_println("Auditrs started successfully", Colors::BrightGreenFg);
     }
@@ -95,7 +102,8 @@ pub fn reboot_auditrs(skip_auditd_preflight: bool) -> Result<()> {
         waited_ms += step.as_millis() as u64;
     }
 
-    start_auditrs(true, skip_auditd_preflight).context("Failed to start daemon")?;
+    start_auditrs(true, skip_auditd_preflight, WorkerOptions::default())
+        .context("Failed to start daemon")?;
     colorize_println("Auditrs rebooted successfully", Colors::BrightGreenFg);
     Ok(())
 }
@@ -17,13 +17,18 @@
 use anyhow::{Context, Result, anyhow};
 use daemonize::{Daemonize, Outcome};
 use std::fs::{self, File};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use crate::config::{CONFIG_DIR, load_config};
-use crate::daemon::PID_FILE_NAME;
+use crate::daemon::{PID_FILE_NAME, WorkerOptions};
 use crate::daemon::auditd_preflight::ensure_auditd_not_running;
 use crate::daemon::worker::run_worker;
-use crate::rules::load_filters;
+use crate::rules::{
+    apply_audit_rule_set,
+    load_audit_rules_dir,
+    load_filters,
+    load_watches,
+};
 
 /// Starts the `auditrs` daemon as a background process.
 ///
@@ -32,12 +37,19 @@ use crate::rules::load_filters;
 /// - Verifies that the caller has root privileges.
 /// - Verifies the legacy `auditd` service is not running (does not stop it),
 ///   unless `skip_auditd_preflight` is true (CLI: `--force`).
+/// - If `audit_rules_dir` is configured, loads the auditctl `*.rules` files in
+///   it and applies the combined rule set to the kernel.
 /// - Forks into a background daemon using the `daemonize` crate.
 /// - In the parent process, briefly waits and then checks that the PID file
 ///   exists to confirm successful startup.
 /// - In the child process, runs the asynchronous worker loop and ensures the
 ///   PID file is cleaned up on exit via `FileGuard`.
-pub fn start_daemon(skip_auditd_preflight: bool) -> Result<()> {
+///
+/// **Parameters:**
+///
+/// * `skip_auditd_preflight`: Skip the legacy `auditd` conflict check.
+/// * `options`: Worker options passed through to `run_worker`.
+pub fn start_daemon(skip_auditd_preflight: bool, options: WorkerOptions) -> Result<()> {
     is_root().context("User is not running with root privileges")?;
 
     if !skip_auditd_preflight {
@@ -48,6 +60,10 @@ pub fn start_daemon(skip_auditd_preflight: bool) -> Result<()> {
     // This makes `auditrs start` and `auditrs reboot` resilient to missing
     // `/etc/auditrs` or log directories (e.g. after manual cleanup).
     ensure_required_directories()?;
+
+    // Apply the augenrules-style rules directory before forking, so that a
+    // malformed rules file is reported to the operator directly.
+    apply_startup_audit_rules()?;
     
     let pid = pid_file_path();
     if let Some(parent) = pid.parent() {
@@ -88,7 +104,7 @@ pub fn start_daemon(skip_auditd_preflight: bool) -> Result<()> {
             match res {
                 Ok(_) => {
                     let rt = tokio::runtime::Runtime::new()?;
-                    rt.block_on(run_worker())?;
+                    rt.block_on(run_worker(options))?;
                     Ok(())
                 }
                 Err(e) => Err(anyhow::anyhow!("Failed to daemonize: {}", e)),
@@ -124,6 +140,26 @@ fn ensure_required_directories() -> Result<()> {
     Ok(())
 }
 
+/// Loads every `*.rules` file from the configured `audit_rules_dir` and
+/// applies the combined rule set to the kernel. Does nothing when no directory
+/// is configured; a missing or empty directory is not an error.
+fn apply_startup_audit_rules() -> Result<()> {
+    let cfg = load_config().context("Could not load config")?;
+    let Some(dir) = cfg.audit_rules_dir else {
+        return Ok(());
+    };
+    let set =
+        load_audit_rules_dir(Path::new(&dir)).context("Could not load audit rules directory")?;
+    if set.is_empty() {
+        return Ok(());
+    }
+    let watches = load_watches().context("Could not load watches")?;
+    apply_audit_rule_set(&set, &watches, cfg.audit_rules_delete_all)
+        .context("Could not apply audit rules")?;
+    println!("Loaded {} audit rule(s) from {}", set.rules().len(), dir);
+    Ok(())
+}
+
 /// Sends `SIGTERM` to the running daemon (used by `auditrs stop`).
 ///
 /// This reads the PID from the daemon's PID file, validates it, and then
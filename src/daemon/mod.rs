@@ -17,3 +17,14 @@ pub mod control;
 pub mod daemon;
 pub mod worker;
 pub(crate) const PID_FILE_NAME: &str = "auditrs.pid";
+
+/// Runtime options passed from `auditrs start` through to the worker.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WorkerOptions {
+    /// Seconds between periodic pipeline stats summaries (CLI: `--stats`).
+    /// `None` falls back to the `stats_interval` config setting.
+    pub stats_interval: Option<u64>,
+    /// Log the correlator's grouping decisions for every record (CLI:
+    /// `--debug-correlation`).
+    pub correlation_diagnostics: bool,
+}
@@ -11,23 +11,38 @@
 //!   propagating new values to interested components via `watch` channels.
 //! - **Handling shutdown signals** (`SIGTERM`, Ctrl‑C) and orchestrating a
 //!   graceful stop of background tasks.
+//! - **Reporting pipeline stats** periodically when a stats interval is
+//!   configured, and exporting them to a Prometheus metrics file when
+//!   `metrics_file` is set.
+//! - **Warning about a silent pipeline** when no audit record arrives within
+//!   `no_events_warning` seconds of startup.
 
 use anyhow::Result;
-use std::time::Duration;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::signal;
 use tokio::signal::unix::{SignalKind, signal};
 use tokio::sync::{mpsc, watch};
-use tokio::time::sleep;
+use tokio::time::{MissedTickBehavior, interval, sleep};
 
 use crate::core::enricher::enrich_event;
 use crate::core::{
-    correlator::{AuditEvent, Correlator},
-    netlink::{NetlinkAuditTransport, RawAuditRecord},
-    parser::ParsedAuditRecord,
-    writer::AuditLogWriter,
+    correlator::{AuditEvent, Correlator, EventJoiner, StandardKeyExtractor},
+    metrics::{MetricsSnapshot, PipelineMetrics},
+    netlink::{FileTailTransport, NetlinkAuditTransport, RawAuditRecord},
+    parser::{ParseOptions, ParsedAuditRecord},
+    writer::{AuditLogWriter, RetryPolicy, WriteOutcome},
 };
+use crate::daemon::WorkerOptions;
 use crate::state::{AuditConfig, Rules, State};
 
+/// How often the writer task checks whether the rollup interval has ended.
+const ROLLUP_TICK: Duration = Duration::from_secs(1);
+
+/// How often the metrics file is rewritten when `metrics_file` is configured.
+const METRICS_FILE_PERIOD: Duration = Duration::from_secs(15);
+
 /// Launches the daemon's asynchronous worker tasks and drives signal handling.
 ///
 /// The worker performs the following high-level steps:
@@ -35,7 +50,8 @@ use crate::state::{AuditConfig, Rules, State};
 /// - Loads initial `State` (configuration and rules) and exposes them on
 ///   `watch` channels so that downstream components can react to updates.
 /// - Constructs the core pipeline components: `AuditLogWriter`,
-///   `NetlinkAuditTransport`, and `Correlator`.
+///   `NetlinkAuditTransport` (or `FileTailTransport` when `tail_log` is
+///   configured), and `Correlator`.
 /// - Spawns three cooperative tasks:
 ///   - a **parser task** that consumes `RawAuditRecord`s and produces
 ///     `ParsedAuditRecord`s,
@@ -45,33 +61,101 @@ use crate::state::{AuditConfig, Rules, State};
 /// - Waits for termination signals (`SIGTERM`, `SIGHUP`, Ctrl‑C); on `SIGHUP`
 ///   it reloads state and publishes new config/rules; on termination signals it
 ///   aborts the background tasks and returns.
+/// - Optionally spawns a **stats task** that prints a metrics summary every
+///   `stats_interval` seconds, and a task that writes the metrics in the
+///   Prometheus text format to `metrics_file`.
+/// - Unless `no_events_warning` is `0`, spawns a task that warns once if no
+///   audit record arrived within that many seconds (e.g. no rules loaded).
 ///
 /// **Parameters:**
 ///
-/// This function does not take any parameters; it is intended to be the
-/// top-level entry point for the daemon's asynchronous runtime.
-pub async fn run_worker() -> Result<()> {
+/// * `options`: Worker options from the CLI.
+///   - `stats_interval` overrides the `stats_interval` config setting (`start
+///     --stats <SECONDS>`). `None` uses the config value; an interval of `0`
+///     disables the reporter. The interval is fixed for the lifetime of the
+///     worker and is not changed by `SIGHUP`.
+///   - `correlation_diagnostics` logs the correlator's grouping decisions
+///     (`start --debug-correlation`).
+pub async fn run_worker(options: WorkerOptions) -> Result<()> {
     // We watch to see if the config and rules files change; on reload, we
     // send the new values into watch channels to propagate to the necessary
     // components (currently the writer).
     let state = State::load_state()?;
+    let stats_interval = options
+        .stats_interval
+        .unwrap_or(state.config.stats_interval);
+    let correlation_mode = state.config.correlation_mode;
+    let no_events_warning = state.config.no_events_warning;
+    let quantize_timestamps = state.config.correlation_quantize_timestamps;
+    let memory_budget = state.config.memory_budget;
+    let tail_log = state.config.tail_log.clone();
+    let tail_state_path = state.config.tail_state_path.clone();
+    let metrics_file = state.config.metrics_file.clone();
+    let parse_options = ParseOptions {
+        max_field_len: state.config.max_field_len,
+        ..ParseOptions::default()
+    };
 
     let (config_tx, config_rx) = watch::channel(state.config);
     let (rules_tx, rules_rx) = watch::channel(state.rules);
 
+    let metrics = Arc::new(PipelineMetrics::new());
     let writer = AuditLogWriter::new(None)?;
-    let transport = NetlinkAuditTransport::new();
-    let raw_audit_rx = transport.into_receiver();
-    let correlator = Correlator::new();
+    let raw_audit_rx = match tail_log {
+        Some(log) => FileTailTransport::new(log, tail_state_path, metrics.clone())?.into_receiver(),
+        None => NetlinkAuditTransport::new(metrics.clone()).into_receiver(),
+    };
+    let mut correlator = Correlator::new();
+    correlator.set_diagnostics(options.correlation_diagnostics);
+    correlator.set_mode(correlation_mode);
+    correlator.set_key_extractor(Box::new(StandardKeyExtractor {
+        quantize_timestamps,
+    }));
+    correlator.set_memory_budget(memory_budget);
 
     let (parsed_audit_tx, parsed_audit_rx) = mpsc::channel(1000);
     let (correlated_event_tx, correlated_event_rx) = mpsc::channel(1000);
     let (enriched_event_tx, enriched_event_rx) = mpsc::channel(1000);
+    metrics.watch_channel("parsed", &parsed_audit_tx);
+    metrics.watch_channel("correlated", &correlated_event_tx);
+    metrics.watch_channel("enriched", &enriched_event_tx);
 
-    let parser_task = spawn_parser_task(raw_audit_rx, parsed_audit_tx);
-    let correlator_task = spawn_correlator_task(correlator, parsed_audit_rx, correlated_event_tx);
+    let parser_task = spawn_parser_task(
+        raw_audit_rx,
+        parsed_audit_tx,
+        parse_options,
+        metrics.clone(),
+    );
+    let correlator_task = spawn_correlator_task(
+        correlator,
+        parsed_audit_rx,
+        correlated_event_tx,
+        metrics.clone(),
+    );
     let enricher_task = spawn_enricher_task(correlated_event_rx, enriched_event_tx);
-    let writer_task = spawn_writer_task(writer, enriched_event_rx, config_rx, rules_rx);
+    let writer_task = spawn_writer_task(
+        writer,
+        enriched_event_rx,
+        config_rx,
+        rules_rx,
+        metrics.clone(),
+    );
+    let no_events_task = (no_events_warning > 0).then(|| {
+        tokio::spawn(run_no_events_watchdog(
+            metrics.clone(),
+            Duration::from_secs(no_events_warning),
+            |line| eprintln!("{line}"),
+        ))
+    });
+    let metrics_file_task = metrics_file.map(|path| {
+        tokio::spawn(run_metrics_file_writer(
+            metrics.clone(),
+            PathBuf::from(path),
+            METRICS_FILE_PERIOD,
+        ))
+    });
+    let stats_task = (stats_interval > 0)
+        .then(|| spawn_stats_task(metrics, Duration::from_secs(stats_interval)));
 
     let mut sigterm = signal(SignalKind::terminate())?;
     let mut sighup = signal(SignalKind::hangup())?;
@@ -96,8 +180,20 @@ pub async fn run_worker() -> Result<()> {
     parser_task.abort();
     correlator_task.abort();
     enricher_task.abort();
-    writer_task.abort();
-    let _ = tokio::join!(parser_task, correlator_task, enricher_task, writer_task);
+    if let Some(no_events_task) = no_events_task {
+        no_events_task.abort();
+    }
+    if let Some(metrics_file_task) = metrics_file_task {
+        metrics_file_task.abort();
+    }
+    if let Some(stats_task) = stats_task {
+        stats_task.abort();
+        let _ = stats_task.await;
+    }
+    let _ = tokio::join!(parser_task, correlator_task, enricher_task);
+    // Stopping the enricher closes the writer's channel; the writer drains it,
+    // writes any partial rollup and exits.
+    let _ = writer_task.await;
     Ok(())
 }
 
@@ -107,7 +203,7 @@ pub async fn run_worker() -> Result<()> {
 ///
 /// - Receives `RawAuditRecord`s from the netlink transport.
 /// - Converts each record into a `ParsedAuditRecord` via
-///   `ParsedAuditRecord::try_from`.
+///   `ParsedAuditRecord::from_raw`, truncating oversized field values.
 /// - Emits successfully parsed records on the provided `mpsc` channel for
 ///   downstream correlation.
 /// - Logs parse errors but continues processing subsequent records.
@@ -116,25 +212,37 @@ pub async fn run_worker() -> Result<()> {
 ///
 /// * `receiver`: `mpsc::Receiver<RawAuditRecord>` from which raw records are
 ///   pulled.
-/// * `sender`: `mpsc::Sender<ParsedAuditRecord>` used to forward successfully
-///   parsed records to the correlator stage.
+/// * `sender`: `mpsc::Sender` used to forward successfully parsed records, with
+///   the instant the transport received them, to the correlator stage.
+/// * `options`: Parse options; the field value cap applies.
+/// * `metrics`: Shared pipeline counters; parsed and dropped records and
+///   truncated field values are counted here.
 ///
 /// The returned `JoinHandle` can be used to manage or cancel the task.
 fn spawn_parser_task(
     mut receiver: mpsc::Receiver<RawAuditRecord>,
-    sender: mpsc::Sender<ParsedAuditRecord>,
+    sender: mpsc::Sender<(ParsedAuditRecord, Instant)>,
+    options: ParseOptions,
+    metrics: Arc<PipelineMetrics>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         while let Some(raw_record) = receiver.recv().await {
-            match ParsedAuditRecord::try_from(raw_record) {
+            let received_at = raw_record.received_at;
+            match ParsedAuditRecord::from_raw(raw_record, &options) {
                 Ok(parsed_record) => {
                     println!("Parsed record: {:?}", parsed_record);
-                    sender
-                        .send(parsed_record)
-                        .await
-                        .unwrap_or_else(|e| eprintln!("Failed to send parsed record: {:?}", e));
+                    metrics.record_parsed();
+                    let truncated = parsed_record.truncated_fields();
+                    if truncated > 0 {
+                        metrics.fields_truncated(truncated as u64);
+                    }
+                    if let Err(e) = sender.send((parsed_record, received_at)).await {
+                        metrics.dropped();
+                        eprintln!("Failed to send parsed record: {:?}", e);
+                    }
                 }
                 Err(e) => {
+                    metrics.dropped();
                     eprintln!("Failed to parse raw audit record: {:?}", e);
                     continue;
                 }
@@ -149,6 +257,10 @@ fn spawn_parser_task(
 ///
 /// - Listens for incoming `ParsedAuditRecord`s and pushes them into a
 ///   `Correlator` instance.
+/// - In streaming mode, forwards each event's initial update and amendments as
+///   soon as the records arrive; amendments are marked with
+///   `AuditEvent::amendment` and share the initial update's timestamp and
+///   serial.
 /// - Periodically (every 500ms) flushes expired or complete events from the
 ///   correlator and forwards them on an `mpsc` channel to the writer.
 ///
@@ -158,28 +270,55 @@ fn spawn_parser_task(
 /// **Parameters:**
 ///
 /// * `correlator`: The `Correlator` instance responsible for grouping related
-///   audit records into higher-level `AuditEvent`s.
-/// * `receiver`: `mpsc::Receiver<ParsedAuditRecord>` that supplies parsed
-///   records to be correlated.
+///   audit records into higher-level `AuditEvent`s. Any diagnostics it records
+///   are printed after each push or flush.
+/// * `receiver`: `mpsc::Receiver` that supplies parsed records to be
+///   correlated, with the instant the transport received them.
 /// * `sender`: `mpsc::Sender<AuditEvent>` used to publish completed or expired
 ///   events to the writer stage.
+/// * `metrics`: Shared pipeline counters; emitted events, their latency, the
+///   number of pending groups and records shed to stay within the memory budget
+///   are published here, and events the writer stage can no longer take are
+///   counted as drops.
 fn spawn_correlator_task(
     mut correlator: Correlator,
-    mut receiver: mpsc::Receiver<ParsedAuditRecord>,
+    mut receiver: mpsc::Receiver<(ParsedAuditRecord, Instant)>,
     sender: mpsc::Sender<AuditEvent>,
+    metrics: Arc<PipelineMetrics>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         loop {
             tokio::select! {
-                Some(record) = receiver.recv() => {
-                    correlator.push(record);
+                Some((record, received_at)) = receiver.recv() => {
+                    correlator.push_received(record, received_at);
+                    for update in correlator.drain_updates() {
+                        if !update.amendment {
+                            metrics.events_emitted(1);
+                            metrics.event_latency(update.received_at.elapsed());
+                        }
+                        if let Err(e) = sender.send(update.event).await {
+                            metrics.dropped();
+                            eprintln!("Failed to send event update: {:?}", e);
+                        }
+                    }
                 }
                 _ = sleep(Duration::from_millis(500)) => {
-                    for event in correlator.flush_expired() {
-                        sender.send(event).await.unwrap();
+                    let events = correlator.flush_expired_observed();
+                    metrics.events_emitted(events.len() as u64);
+                    for (event, observed_at) in events {
+                        metrics.event_latency(observed_at.elapsed());
+                        if let Err(e) = sender.send(event).await {
+                            metrics.dropped();
+                            eprintln!("Failed to send correlated event: {:?}", e);
+                        }
                     }
                 }
             }
+            for line in correlator.drain_diagnostics() {
+                println!("{}", line);
+            }
+            metrics.records_shed(correlator.take_shed_records());
+            metrics.set_pending_groups(correlator.pending_groups() as u64);
         }
     })
 }
@@ -210,7 +349,18 @@ fn spawn_enricher_task(
 ///   applying updated configuration and rules to the `AuditLogWriter` as they
 ///   arrive (typically triggered by `SIGHUP`).
 ///
-/// The task runs until the event channel is closed, after which it exits
+/// - Retries the failed sink writes of an event according to the configured
+///   [`RetryPolicy`] and dead-letters events that still fail.
+/// - Matches each event against the configured `joins` and writes the
+///   detections they complete.
+/// - In rollup mode, checks every second whether the rollup interval has ended,
+///   so summaries are written even while no events arrive.
+/// - With `warning_events` enabled, writes a warning record every second for
+///   each problem counter in `metrics` that grew (and for connection changes),
+///   so warnings appear in the output next to the events they concern.
+///
+/// The task runs until the event channel is closed, after which it writes the
+/// partial rollup (if any) and the warnings not yet written, and exits
 /// cleanly.
 ///
 /// **Parameters:**
@@ -223,23 +373,58 @@ fn spawn_enricher_task(
 ///   configuration updates.
 /// * `rules_rx`: `watch::Receiver<Rules>` that delivers live rule changes used
 ///   by the writer.
+/// * `metrics`: Shared pipeline counters; dead-lettered events and failed file
+///   destination writes are counted, and events that could not be dead-lettered
+///   either are counted as drops. Also the source of warning records.
 fn spawn_writer_task(
     mut writer: AuditLogWriter,
     mut receiver: mpsc::Receiver<AuditEvent>,
     mut config_rx: watch::Receiver<AuditConfig>,
     mut rules_rx: watch::Receiver<Rules>,
+    metrics: Arc<PipelineMetrics>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
+        let mut retry = RetryPolicy::from_config(&config_rx.borrow());
+        let mut joiner = EventJoiner::new(config_rx.borrow().joins.clone());
+        let mut rollup_ticker = interval(ROLLUP_TICK);
+        rollup_ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut warnings_checked = metrics.snapshot();
         loop {
             tokio::select! {
                 maybe_event = receiver.recv() => {
                     let Some(event) = maybe_event else { break; };
-                    if let Err(e) = writer.write_event(event) {
-                        eprintln!("Failed to write audit event: {:?}", e);
+                    let written = match writer.prepare_event(&event) {
+                        Ok(Some(mut pending)) => {
+                            retry.write(&event, || writer.write_pending(&mut pending)).await
+                        }
+                        Ok(None) => Ok(WriteOutcome::Written { retries: 0 }),
+                        Err(e) => Err(e),
+                    };
+                    metrics.destination_failures(writer.take_destination_failures());
+                    match written {
+                        Ok(WriteOutcome::Written { .. }) => {}
+                        Ok(WriteOutcome::DeadLettered) => {
+                            metrics.dead_lettered();
+                            eprintln!("Audit event dead-lettered after failed writes");
+                        }
+                        Err(e) => {
+                            metrics.dropped();
+                            eprintln!("Failed to write audit event: {:?}", e);
+                        }
+                    }
+                    let detections = joiner.observe(&event);
+                    if !detections.is_empty()
+                        && let Err(e) = writer.write_join_detections(&detections)
+                    {
+                        eprintln!("Failed to write join detections: {:?}", e);
                     }
                 }
                 Ok(()) = config_rx.changed() => {
                     let cfg = config_rx.borrow_and_update().clone();
+                    retry = RetryPolicy::from_config(&cfg);
+                    if !joiner.has_rules(&cfg.joins) {
+                        joiner = EventJoiner::new(cfg.joins.clone());
+                    }
                     if let Err(e) = writer.reload_config(&cfg) {
                         eprintln!("Failed to apply config reload: {:?}", e);
                     }
@@ -248,7 +433,221 @@ fn spawn_writer_task(
                     let rules = rules_rx.borrow_and_update().clone();
                     writer.reload_rules(&rules);
                 }
+                _ = rollup_ticker.tick() => {
+                    if let Err(e) = writer.tick_rollup() {
+                        eprintln!("Failed to write rollup: {:?}", e);
+                    }
+                    let enabled = config_rx.borrow().warning_events;
+                    write_new_warnings(&mut writer, &metrics, &mut warnings_checked, enabled);
+                }
             }
         }
+        if let Err(e) = writer.flush_rollup() {
+            eprintln!("Failed to write partial rollup: {:?}", e);
+        }
+        // Drops and dead-letters of the last second before shutdown.
+        let enabled = config_rx.borrow().warning_events;
+        write_new_warnings(&mut writer, &metrics, &mut warnings_checked, enabled);
     })
 }
+
+/// Writes a warning record for each problem `metrics` reports since the
+/// `checked` snapshot, then moves `checked` to the current snapshot.
+///
+/// **Parameters:**
+///
+/// * `writer`: The writer the warning records go to.
+/// * `metrics`: Shared pipeline counters to snapshot.
+/// * `checked`: The snapshot of the previous check.
+/// * `enabled`: Whether warning records are written (`warning_events`); the
+///   snapshot moves on either way.
+fn write_new_warnings(
+    writer: &mut AuditLogWriter,
+    metrics: &PipelineMetrics,
+    checked: &mut MetricsSnapshot,
+    enabled: bool,
+) {
+    let current = metrics.snapshot();
+    if enabled {
+        let warnings = current.warnings_since(checked, SystemTime::now());
+        if !warnings.is_empty()
+            && let Err(e) = writer.write_warnings(&warnings)
+        {
+            eprintln!("Failed to write warning records: {:?}", e);
+        }
+    }
+    *checked = current;
+}
+
+/// Spawns the stats task that periodically prints a pipeline metrics summary
+/// to the daemon's stdout.
+///
+/// **Parameters:**
+///
+/// * `metrics`: Shared pipeline counters to snapshot.
+/// * `period`: Time between summaries; must be non-zero.
+fn spawn_stats_task(
+    metrics: Arc<PipelineMetrics>,
+    period: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(run_stats_reporter(metrics, period, |line| {
+        println!("{line}")
+    }))
+}
+
+/// Emits a summary line every `period`, with rates computed over the interval
+/// since the previous summary. Runs until the surrounding task is aborted.
+///
+/// **Parameters:**
+///
+/// * `metrics`: Shared pipeline counters to snapshot.
+/// * `period`: Time between summaries; must be non-zero.
+/// * `emit`: Sink for each summary line (stdout in the daemon, a buffer in
+///   tests).
+async fn run_stats_reporter<F: FnMut(String)>(
+    metrics: Arc<PipelineMetrics>,
+    period: Duration,
+    mut emit: F,
+) {
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately; use it to take the baseline.
+    ticker.tick().await;
+    let mut previous = metrics.snapshot();
+    loop {
+        ticker.tick().await;
+        let current = metrics.snapshot();
+        emit(current.summary_since(&previous));
+        previous = current;
+    }
+}
+
+/// Rewrites the Prometheus metrics file every `period`, starting right away.
+/// A failed write is logged and retried at the next period. Runs until the
+/// surrounding task is aborted.
+///
+/// **Parameters:**
+///
+/// * `metrics`: Shared pipeline counters to snapshot.
+/// * `path`: The metrics file.
+/// * `period`: Time between writes; must be non-zero.
+async fn run_metrics_file_writer(metrics: Arc<PipelineMetrics>, path: PathBuf, period: Duration) {
+    let mut ticker = interval(period);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        if let Err(e) = metrics.snapshot().write_prometheus_file(&path) {
+            eprintln!("Failed to write metrics file: {:?}", e);
+        }
+    }
+}
+
+/// Waits `timeout` and then, if the audit connection is up but no audit record
+/// has been received yet, emits a warning that no audit rules may be loaded.
+/// Emits at most once.
+///
+/// **Parameters:**
+///
+/// * `metrics`: Shared pipeline counters, read for received records.
+/// * `timeout`: Time since startup after which silence is reported.
+/// * `emit`: Sink for the warning (stderr in the daemon, a buffer in tests).
+async fn run_no_events_watchdog<F: FnOnce(String)>(
+    metrics: Arc<PipelineMetrics>,
+    timeout: Duration,
+    emit: F,
+) {
+    sleep(timeout).await;
+    let snapshot = metrics.snapshot();
+    if snapshot.connected && snapshot.records_received == 0 {
+        emit(format!(
+            "warning: no audit records received in the {}s since startup; the audit \
+             connection is up, but no audit rules may be loaded (check with `auditctl -l`). \
+             Set no_events_warning = 0 to silence this warning.",
+            timeout.as_secs()
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{advance, timeout};
+
+    #[tokio::test(start_paused = true)]
+    async fn no_events_warning_fires_after_timeout_without_records() {
+        let metrics = Arc::new(PipelineMetrics::new());
+        metrics.set_connected(true);
+        let (lines_tx, mut lines) = mpsc::unbounded_channel();
+        let watchdog = tokio::spawn(run_no_events_watchdog(
+            metrics.clone(),
+            Duration::from_secs(30),
+            move |line| lines_tx.send(line).unwrap(),
+        ));
+        // Let the watchdog start its timer before moving the clock.
+        tokio::task::yield_now().await;
+
+        // Nothing is emitted before the timeout.
+        advance(Duration::from_secs(29)).await;
+        assert!(lines.try_recv().is_err());
+        advance(Duration::from_secs(1)).await;
+        watchdog.await.unwrap();
+        let line = lines.try_recv().unwrap();
+        assert!(line.contains("in the 30s since startup"), "{}", line);
+        assert!(line.contains("auditctl -l"), "{}", line);
+
+        // Nothing is emitted once records flow.
+        metrics.record_received();
+        let watchdog = tokio::spawn(run_no_events_watchdog(
+            metrics,
+            Duration::from_secs(30),
+            |line| panic!("unexpected warning: {line}"),
+        ));
+        tokio::task::yield_now().await;
+        advance(Duration::from_secs(30)).await;
+        watchdog.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stats_reporter_fires_at_configured_interval() {
+        let metrics = Arc::new(PipelineMetrics::new());
+        metrics.record_parsed();
+        let mut lines = Vec::new();
+
+        // With the clock paused, time only advances to the next timer, so a
+        // bounded run of 350ms with a 100ms interval emits exactly three
+        // summaries (at 100, 200 and 300ms).
+        let run = run_stats_reporter(metrics, Duration::from_millis(100), |line| lines.push(line));
+        assert!(timeout(Duration::from_millis(350), run).await.is_err());
+
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        assert!(lines.iter().all(|line| line.starts_with("stats: ")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn metrics_file_is_rewritten_every_period() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auditrs.prom");
+        let metrics = Arc::new(PipelineMetrics::new());
+        let writer = tokio::spawn(run_metrics_file_writer(
+            metrics.clone(),
+            path.clone(),
+            Duration::from_secs(15),
+        ));
+
+        sleep(Duration::from_secs(1)).await;
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            text.contains("auditrs_records_received_total 0\n"),
+            "{text}"
+        );
+
+        metrics.record_received();
+        sleep(Duration::from_secs(15)).await;
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(
+            text.contains("auditrs_records_received_total 1\n"),
+            "{text}"
+        );
+        writer.abort();
+    }
+}
@@ -0,0 +1,825 @@
+//! Load augenrules-style directories of auditctl `*.rules` files (e.g.
+//! `/etc/audit/rules.d/`) and apply the combined rule set to the kernel.
+//!
+//! Files are read in sorted file-name order and concatenated, as `augenrules`
+//! does. Within the combined set:
+//! - `-D` requests a flush of existing kernel rules before loading, wherever it
+//!   appears (`-D -k <key>` instead drops earlier rules carrying that key). The
+//!   flush removes rules auditrs did not load, so it is only carried out when
+//!   `audit_rules_delete_all` is set; otherwise it is ignored with a warning;
+//! - status settings (`-b`, `-f`, `-r`, `-e`, `--backlog_wait_time`) keep their
+//!   last value;
+//! - an exact duplicate of an earlier rule is dropped with a warning (auditctl
+//!   would reject it with "Rule exists");
+//! - `-d`/`-W` remove a matching earlier rule.
+//!
+//! Supported rule lines are `-w`/`-W` watches and `-a`/`-A`/`-d` rules with
+//! `-S`, `-F` and `-k` options. Valid auditctl syntax beyond that (fields such
+//! as `exit`, `exe`, `filetype`, `a0`-`a3` or the `subj_*`/`obj_*` labels, and
+//! options such as `-c`, `-i`, `-C` or `--loginuid-immutable`) is skipped with
+//! a warning naming the file and line. Anything auditctl itself would reject,
+//! such as an unknown option or field or a bad value, is an error naming the
+//! file and line that failed.
+
+use anyhow::{Context, Result, anyhow, bail};
+use audit::packet::{
+    RuleAction,
+    RuleField,
+    RuleFieldFlags,
+    RuleFlags,
+    RuleMessage,
+    RuleSyscalls,
+    constants::{AUDIT_PERM_ATTR, AUDIT_PERM_EXEC, AUDIT_PERM_READ, AUDIT_PERM_WRITE},
+};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use syscalls::Sysno;
+
+use crate::core::netlink::{apply_audit_rule_message, delete_all_audit_rules};
+use crate::rules::kernel_watches::audit_watch_to_rule_message;
+use crate::rules::{
+    AuditRule,
+    AuditRuleDirective,
+    AuditRuleField,
+    AuditRuleList,
+    AuditRuleSet,
+    UnsupportedDirective,
+    Watches,
+};
+
+/// Comparison operators accepted by `-F`, longest first so that `!=` is not
+/// read as `!` followed by `=`.
+const FIELD_OPERATORS: &[&str] = &["!=", "<=", ">=", "&=", "=", "<", ">", "&"];
+
+/// auditctl options that may appear in a rules file but are not applied by
+/// auditrs.
+const UNSUPPORTED_OPTIONS: &[&str] = &[
+    "-c",
+    "-i",
+    "-t",
+    "--loginuid-immutable",
+    "--reset-lost",
+    "--reset_backlog_wait_time_actual",
+];
+
+/// `-F` fields auditctl accepts but auditrs does not translate into kernel
+/// rule fields.
+const UNSUPPORTED_FIELDS: &[&str] = &[
+    "a0",
+    "a1",
+    "a2",
+    "a3",
+    "devmajor",
+    "devminor",
+    "exe",
+    "exit",
+    "filetype",
+    "fstype",
+    "inode",
+    "obj_gid",
+    "obj_lev_high",
+    "obj_lev_low",
+    "obj_role",
+    "obj_type",
+    "obj_uid",
+    "obj_user",
+    "saddr_fam",
+    "subj_clr",
+    "subj_role",
+    "subj_sen",
+    "subj_type",
+    "subj_user",
+];
+
+/// `-F arch=b64` / `-F arch=b32` values for the host architecture.
+#[cfg(target_arch = "x86_64")]
+const HOST_ARCHES: (u32, u32) = (0xC000_003E, 0x4000_0003);
+#[cfg(target_arch = "aarch64")]
+const HOST_ARCHES: (u32, u32) = (0xC000_00B7, 0x4000_0028);
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+const HOST_ARCHES: (u32, u32) = (0, 0);
+
+impl AuditRuleSet {
+    /// Returns the rules to load, in order, each with its `file:line` origin.
+    pub fn rules(&self) -> &[(AuditRule, String)] {
+        &self.rules
+    }
+
+    /// Returns `true` when the set contains no rules, settings or `-D`.
+    pub fn is_empty(&self) -> bool {
+        !self.delete_all && self.controls.is_empty() && self.rules.is_empty()
+    }
+
+    /// Reads a single auditctl rules file and merges its directives into the
+    /// set.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The rules file to read; named in any error.
+    pub fn add_rules_file(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read rules file '{}'", path.display()))?;
+        self.add_rules(&content, path)
+    }
+
+    /// Parses auditctl rule lines and merges them into the set. Blank lines and
+    /// `#` comments are skipped, as are lines using auditctl syntax auditrs
+    /// does not support (with a warning).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `content`: The rules file content.
+    /// * `path`: The file the content came from, used in diagnostics.
+    pub fn add_rules(&mut self, content: &str, path: &Path) -> Result<()> {
+        for (line_num, line) in content.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let origin = format!("{}:{}", path.display(), line_num + 1);
+            let directive = match parse_audit_rule_line(trimmed) {
+                Ok(directive) => directive,
+                Err(e) if e.is::<UnsupportedDirective>() => {
+                    eprintln!("warning: {}: skipping '{}': {}", origin, trimmed, e);
+                    continue;
+                }
+                Err(e) => bail!("{}: malformed rule '{}': {}", origin, trimmed, e),
+            };
+            self.apply_directive(directive, origin);
+        }
+        Ok(())
+    }
+
+    /// Merges one directive into the set following augenrules/auditctl
+    /// semantics (see the module documentation).
+    ///
+    /// **Parameters:**
+    ///
+    /// * `directive`: The parsed directive.
+    /// * `origin`: The `file:line` the directive came from.
+    fn apply_directive(&mut self, directive: AuditRuleDirective, origin: String) {
+        match directive {
+            AuditRuleDirective::DeleteAll => self.delete_all = true,
+            AuditRuleDirective::DeleteKey(key) => {
+                self.rules
+                    .retain(|(rule, _)| !rule.keys().any(|k| k == key));
+            }
+            AuditRuleDirective::Control(option, value) => {
+                match self.controls.iter_mut().find(|(o, _)| *o == option) {
+                    Some(existing) => existing.1 = value,
+                    None => self.controls.push((option, value)),
+                }
+            }
+            AuditRuleDirective::Add(rule, prepend) => {
+                if let Some((_, first)) = self.rules.iter().find(|(r, _)| *r == rule) {
+                    eprintln!(
+                        "warning: {}: duplicate of rule at {}, skipping: {}",
+                        origin, first, rule
+                    );
+                } else if prepend {
+                    self.rules.insert(0, (rule, origin));
+                } else {
+                    self.rules.push((rule, origin));
+                }
+            }
+            AuditRuleDirective::Delete(rule) => {
+                match self.rules.iter().position(|(r, _)| *r == rule) {
+                    Some(index) => {
+                        self.rules.remove(index);
+                    }
+                    None => {
+                        eprintln!(
+                            "warning: {}: no earlier rule matches, ignoring delete: {}",
+                            origin, rule
+                        )
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AuditRule {
+    /// Returns the rule's `-k` keys.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.fields
+            .iter()
+            .filter(|f| f.name == "key")
+            .map(|f| f.value.as_str())
+    }
+
+    /// Builds the kernel [`RuleMessage`] for this rule.
+    pub fn to_rule_message(&self) -> Result<RuleMessage> {
+        let mut rule = RuleMessage::default();
+        rule.flags = match self.list {
+            AuditRuleList::Exit => RuleFlags::FilterExit,
+            AuditRuleList::User => RuleFlags::FilterUser,
+            AuditRuleList::Task => RuleFlags::FilterTask,
+            AuditRuleList::Exclude => RuleFlags::FilterType,
+            AuditRuleList::Filesystem => RuleFlags::FilterFs,
+        };
+        rule.action = if self.always {
+            RuleAction::Always
+        } else {
+            RuleAction::Never
+        };
+
+        // The kernel accepts a single filter key; multiple `-k` options are
+        // joined with `\x01`, as auditctl does.
+        let keys: Vec<&str> = self.keys().collect();
+        for field in self.fields.iter().filter(|f| f.name != "key") {
+            rule.fields
+                .push((field_to_rule_field(field)?, operator_flags(&field.op)?));
+        }
+        if !keys.is_empty() {
+            rule.fields.push((
+                RuleField::Filterkey(keys.join("\x01")),
+                RuleFieldFlags::Equal,
+            ));
+        }
+
+        rule.syscalls = if self.syscalls.is_empty() {
+            RuleSyscalls::new_maxed()
+        } else {
+            let mut syscalls = RuleSyscalls::new_zeroed();
+            for syscall in &self.syscalls {
+                syscalls.set(*syscall);
+            }
+            syscalls
+        };
+        Ok(rule)
+    }
+}
+
+/// Renders the rule in auditctl argument form (keys as `-k`).
+impl fmt::Display for AuditRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = if self.always { "always" } else { "never" };
+        write!(f, "-a {},{}", action, self.list)?;
+        if !self.syscalls.is_empty() {
+            let syscalls: Vec<String> = self.syscalls.iter().map(u32::to_string).collect();
+            write!(f, " -S {}", syscalls.join(","))?;
+        }
+        for field in self.fields.iter().filter(|f| f.name != "key") {
+            write!(f, " -F {}{}{}", field.name, field.op, field.value)?;
+        }
+        for key in self.keys() {
+            write!(f, " -k {}", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a single auditctl rule line (without the `auditctl` binary name).
+///
+/// **Parameters:**
+///
+/// * `line`: The trimmed, non-comment rule line.
+pub fn parse_audit_rule_line(line: &str) -> Result<AuditRuleDirective> {
+    let mut tokens = line.split_whitespace();
+    let command = tokens.next().ok_or_else(|| anyhow!("empty rule"))?;
+
+    match command {
+        "-D" => {
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (None, _, _) => Ok(AuditRuleDirective::DeleteAll),
+                (Some("-k"), Some(key), None) => Ok(AuditRuleDirective::DeleteKey(key.to_string())),
+                _ => bail!("-D only accepts an optional '-k <key>'"),
+            }
+        }
+        "-b" | "-f" | "-r" | "-e" | "--backlog_wait_time" => {
+            let value = tokens
+                .next()
+                .ok_or_else(|| anyhow!("{} requires a value", command))?;
+            let number: u32 = value
+                .parse()
+                .map_err(|_| anyhow!("{} value '{}' is not a number", command, value))?;
+            if matches!(command, "-f" | "-e") && number > 2 {
+                bail!("{} value must be 0, 1 or 2", command);
+            }
+            if let Some(extra) = tokens.next() {
+                bail!("unexpected argument '{}'", extra);
+            }
+            Ok(AuditRuleDirective::Control(
+                command.to_string(),
+                value.to_string(),
+            ))
+        }
+        "-w" | "-W" => {
+            let path = tokens
+                .next()
+                .ok_or_else(|| anyhow!("{} requires a path", command))?;
+            let rule = parse_watch(path, tokens)?;
+            Ok(if command == "-w" {
+                AuditRuleDirective::Add(rule, false)
+            } else {
+                AuditRuleDirective::Delete(rule)
+            })
+        }
+        "-a" | "-A" | "-d" => {
+            let list_action = tokens
+                .next()
+                .ok_or_else(|| anyhow!("{} requires 'list,action'", command))?;
+            let rule = parse_rule(list_action, tokens)?;
+            Ok(match command {
+                "-a" => AuditRuleDirective::Add(rule, false),
+                "-A" => AuditRuleDirective::Add(rule, true),
+                _ => AuditRuleDirective::Delete(rule),
+            })
+        }
+        other if UNSUPPORTED_OPTIONS.contains(&other) => {
+            bail!(UnsupportedDirective(format!(
+                "option '{}' is not supported by auditrs",
+                other
+            )))
+        }
+        other => bail!("unsupported option '{}'", other),
+    }
+}
+
+/// Loads every `*.rules` file in `dir`, in sorted file-name order, into one
+/// combined [`AuditRuleSet`]. A missing directory yields an empty set.
+///
+/// **Parameters:**
+///
+/// * `dir`: The rules directory (e.g. `/etc/audit/rules.d`).
+pub fn load_audit_rules_dir(dir: &Path) -> Result<AuditRuleSet> {
+    let mut set = AuditRuleSet::default();
+    if !dir.exists() {
+        return Ok(set);
+    }
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read rules directory '{}'", dir.display()))?
+    {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "rules") {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    for file in files {
+        set.add_rules_file(&file)?;
+    }
+    Ok(set)
+}
+
+/// Applies a combined rule set to the kernel. With `allow_delete_all`, a
+/// `-D` flushes existing kernel rules first and then re-adds auditrs's own
+/// watches, which the flush also removed; otherwise the `-D` is ignored with a
+/// warning. Rules the kernel already holds are skipped; status settings are
+/// reported but not applied.
+///
+/// **Parameters:**
+///
+/// * `set`: The rule set from [`load_audit_rules_dir`].
+/// * `watches`: The auditrs watches to restore after a `-D` flush.
+/// * `allow_delete_all`: Whether a `-D` may flush the kernel rules
+///   (`audit_rules_delete_all`).
+pub fn apply_audit_rule_set(
+    set: &AuditRuleSet,
+    watches: &Watches,
+    allow_delete_all: bool,
+) -> Result<()> {
+    if set.delete_all && !allow_delete_all {
+        eprintln!(
+            "warning: ignoring '-D': set audit_rules_delete_all = true to flush existing kernel rules"
+        );
+    } else if set.delete_all {
+        let removed = delete_all_audit_rules()?;
+        println!("Deleted {} existing kernel audit rule(s) (-D)", removed);
+        for watch in watches.as_slice() {
+            apply_audit_rule_message(audit_watch_to_rule_message(watch), false)
+                .with_context(|| format!("failed to restore watch '{}'", watch.key))?;
+        }
+    }
+
+    for (option, value) in &set.controls {
+        eprintln!(
+            "warning: ignoring '{} {}': kernel status settings are not managed by auditrs",
+            option, value
+        );
+    }
+
+    for (rule, origin) in &set.rules {
+        let message = rule
+            .to_rule_message()
+            .with_context(|| format!("{}: invalid rule '{}'", origin, rule))?;
+        if let Err(e) = apply_audit_rule_message(message, false) {
+            if is_rule_exists_error(&e) {
+                eprintln!(
+                    "warning: {}: rule already loaded, skipping: {}",
+                    origin, rule
+                );
+                continue;
+            }
+            return Err(e.context(format!("{}: failed to load rule '{}'", origin, rule)));
+        }
+    }
+    Ok(())
+}
+
+/// Parses the options of a `-w`/`-W` line into the equivalent exit rule.
+///
+/// **Parameters:**
+///
+/// * `path`: The watched path.
+/// * `tokens`: The remaining tokens of the line.
+fn parse_watch<'a>(path: &str, mut tokens: impl Iterator<Item = &'a str>) -> Result<AuditRule> {
+    // auditctl watches directories with `dir`, everything else with `path`.
+    let name = if path.ends_with('/') || Path::new(path).is_dir() {
+        "dir"
+    } else {
+        "path"
+    };
+    let mut fields = vec![field(name, "=", path.trim_end_matches('/'))];
+    let mut perm = None;
+    let mut keys = Vec::new();
+
+    while let Some(option) = tokens.next() {
+        let value = tokens
+            .next()
+            .ok_or_else(|| anyhow!("{} requires a value", option))?;
+        match option {
+            "-p" => {
+                validate_perm(value)?;
+                perm = Some(value);
+            }
+            "-k" => keys.push(field("key", "=", value)),
+            other => bail!("unsupported watch option '{}'", other),
+        }
+    }
+
+    fields.push(field("perm", "=", perm.unwrap_or("rwxa")));
+    fields.extend(keys);
+    Ok(AuditRule {
+        list: AuditRuleList::Exit,
+        always: true,
+        syscalls: Vec::new(),
+        fields,
+    })
+}
+
+/// Parses the `list,action` argument and options of an `-a`/`-A`/`-d` line.
+///
+/// **Parameters:**
+///
+/// * `list_action`: `list,action` or `action,list` (e.g. `always,exit`).
+/// * `tokens`: The remaining tokens of the line.
+fn parse_rule<'a>(
+    list_action: &str,
+    mut tokens: impl Iterator<Item = &'a str>,
+) -> Result<AuditRule> {
+    let (first, second) = list_action
+        .split_once(',')
+        .ok_or_else(|| anyhow!("expected 'list,action', got '{}'", list_action))?;
+    let (list, action) = if matches!(first, "always" | "never") {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    let list = AuditRuleList::from_str(list).map_err(|_| anyhow!("unknown list '{}'", list))?;
+    let always = match action {
+        "always" => true,
+        "never" => false,
+        other => bail!("unknown action '{}'", other),
+    };
+
+    let mut syscalls = Vec::new();
+    let mut fields = Vec::new();
+    let mut keys = Vec::new();
+    while let Some(option) = tokens.next() {
+        let value = tokens
+            .next()
+            .ok_or_else(|| anyhow!("{} requires a value", option))?;
+        match option {
+            "-S" => {
+                for name in value.split(',') {
+                    if name == "all" {
+                        syscalls.clear();
+                        break;
+                    }
+                    syscalls.push(parse_syscall(name)?);
+                }
+            }
+            "-F" => {
+                let parsed = parse_field(value)?;
+                if parsed.name == "key" {
+                    keys.push(parsed);
+                } else {
+                    fields.push(parsed);
+                }
+            }
+            "-k" => keys.push(field("key", "=", value)),
+            "-C" => {
+                bail!(UnsupportedDirective(
+                    "field comparisons (-C) are not supported by auditrs".to_string()
+                ))
+            }
+            other => bail!("unsupported rule option '{}'", other),
+        }
+    }
+
+    syscalls.sort_unstable();
+    syscalls.dedup();
+    fields.extend(keys);
+    Ok(AuditRule {
+        list,
+        always,
+        syscalls,
+        fields,
+    })
+}
+
+/// Parses and validates a single `-F <name><op><value>` argument.
+///
+/// **Parameters:**
+///
+/// * `arg`: The argument following `-F`.
+fn parse_field(arg: &str) -> Result<AuditRuleField> {
+    let start = arg
+        .find(['!', '<', '>', '&', '='])
+        .ok_or_else(|| anyhow!("field '{}' has no comparison operator", arg))?;
+    let op = FIELD_OPERATORS
+        .iter()
+        .find(|op| arg[start..].starts_with(*op))
+        .ok_or_else(|| anyhow!("field '{}' has an invalid operator", arg))?;
+    let name = match &arg[..start] {
+        "loginuid" => "auid",
+        name => name,
+    };
+    let value = &arg[start + op.len()..];
+    if name.is_empty() || value.is_empty() {
+        bail!("field '{}' needs a name and a value", arg);
+    }
+
+    let parsed = field(name, op, value);
+    // Validate by building the kernel field now, so errors point at the file.
+    field_to_rule_field(&parsed)?;
+    operator_flags(op)?;
+    if matches!(name, "path" | "dir" | "perm" | "key") && *op != "=" {
+        bail!("field '{}' only supports '='", name);
+    }
+    Ok(parsed)
+}
+
+/// Converts a validated rule field to its kernel representation.
+///
+/// **Parameters:**
+///
+/// * `field`: The rule field to convert.
+fn field_to_rule_field(field: &AuditRuleField) -> Result<RuleField> {
+    let value = field.value.as_str();
+    Ok(match field.name.as_str() {
+        "pid" => RuleField::Pid(parse_number(value)?),
+        "ppid" => RuleField::Ppid(parse_number(value)?),
+        "uid" => RuleField::Uid(parse_id(value)?),
+        "euid" => RuleField::Euid(parse_id(value)?),
+        "suid" => RuleField::Suid(parse_id(value)?),
+        "fsuid" => RuleField::Fsuid(parse_id(value)?),
+        "gid" => RuleField::Gid(parse_id(value)?),
+        "egid" => RuleField::Egid(parse_id(value)?),
+        "sgid" => RuleField::Sgid(parse_id(value)?),
+        "fsgid" => RuleField::Fsgid(parse_id(value)?),
+        "auid" => RuleField::Loginuid(parse_id(value)?),
+        "sessionid" => RuleField::Sessionid(parse_id(value)?),
+        "pers" => RuleField::Pers(parse_number(value)?),
+        "msgtype" => RuleField::Msgtype(parse_number(value)?),
+        "arch" => RuleField::Arch(parse_arch(value)?),
+        "success" => {
+            RuleField::Success(match value {
+                "yes" | "1" => 1,
+                "no" | "0" => 0,
+                other => bail!("success value '{}' must be yes or no", other),
+            })
+        }
+        "path" => RuleField::Watch(value.to_string()),
+        "dir" => RuleField::Dir(value.to_string()),
+        "perm" => RuleField::Perm(validate_perm(value)?),
+        "key" => RuleField::Filterkey(value.to_string()),
+        other if UNSUPPORTED_FIELDS.contains(&other) => {
+            bail!(UnsupportedDirective(format!(
+                "field '{}' is not supported by auditrs",
+                other
+            )))
+        }
+        other => bail!("unsupported field '{}'", other),
+    })
+}
+
+/// Maps an `-F` comparison operator to its kernel flags.
+fn operator_flags(op: &str) -> Result<RuleFieldFlags> {
+    Ok(match op {
+        "=" => RuleFieldFlags::Equal,
+        "!=" => RuleFieldFlags::NotEqual,
+        "<" => RuleFieldFlags::LessThan,
+        ">" => RuleFieldFlags::GreaterThan,
+        "<=" => RuleFieldFlags::LessThanOrEqual,
+        ">=" => RuleFieldFlags::GreaterThanOrEqual,
+        "&" => RuleFieldFlags::BitMask,
+        "&=" => RuleFieldFlags::BitTest,
+        other => bail!("invalid operator '{}'", other),
+    })
+}
+
+/// Parses a decimal or `0x`-prefixed hexadecimal number.
+fn parse_number(value: &str) -> Result<u32> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| anyhow!("'{}' is not a number", value))
+}
+
+/// Parses a numeric uid/gid/session id; `unset` and `-1` mean "not set".
+fn parse_id(value: &str) -> Result<u32> {
+    match value {
+        "unset" | "-1" => Ok(u32::MAX),
+        _ => {
+            value
+                .parse()
+                .map_err(|_| anyhow!("'{}' is not a numeric id", value))
+        }
+    }
+}
+
+/// Resolves an `arch=` value (`b64`, `b32`, a machine name, or a number).
+fn parse_arch(value: &str) -> Result<u32> {
+    let arch = match value {
+        "b64" => HOST_ARCHES.0,
+        "b32" => HOST_ARCHES.1,
+        "x86_64" => 0xC000_003E,
+        "i386" | "i686" => 0x4000_0003,
+        "aarch64" => 0xC000_00B7,
+        "arm" => 0x4000_0028,
+        other => parse_number(other).map_err(|_| anyhow!("unknown arch '{}'", other))?,
+    };
+    if arch == 0 {
+        bail!("arch '{}' is not supported on this host", value);
+    }
+    Ok(arch)
+}
+
+/// Resolves a syscall name or number for the host architecture.
+fn parse_syscall(name: &str) -> Result<u32> {
+    if let Ok(number) = name.parse::<u32>() {
+        return Ok(number);
+    }
+    name.parse::<Sysno>()
+        .map(|sysno| sysno.id() as u32)
+        .map_err(|_| anyhow!("unknown syscall '{}'", name))
+}
+
+/// Validates an `rwxa` permission string and returns its kernel bit mask.
+fn validate_perm(value: &str) -> Result<u32> {
+    let mut perm = 0;
+    for c in value.chars() {
+        perm |= match c {
+            'r' => AUDIT_PERM_READ,
+            'w' => AUDIT_PERM_WRITE,
+            'x' => AUDIT_PERM_EXEC,
+            'a' => AUDIT_PERM_ATTR,
+            other => bail!("invalid permission '{}' (expected r, w, x or a)", other),
+        };
+    }
+    if perm == 0 {
+        bail!("permissions are empty");
+    }
+    Ok(perm)
+}
+
+impl fmt::Display for UnsupportedDirective {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedDirective {}
+
+/// Whether a kernel error means the rule is already loaded (`EEXIST`).
+fn is_rule_exists_error(error: &anyhow::Error) -> bool {
+    let message = format!("{:#}", error);
+    message.contains("File exists") || message.contains("os error 17")
+}
+
+/// Shorthand for building an [`AuditRuleField`].
+fn field(name: &str, op: &str, value: &str) -> AuditRuleField {
+    AuditRuleField {
+        name: name.to_string(),
+        op: op.to_string(),
+        value: value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loads_rule_files_from_directory_in_sorted_order() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("50-identity.rules"),
+            "# identity changes\n\
+             -w /etc/passwd -p wa -k identity\n\
+             -a always,exit -F arch=b64 -S openat -F auid>=1000 -F auid!=unset -k access\n\
+             -b 320\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("10-base.rules"),
+            "-D\n-b 8192\n-f 1\n-w /etc/shadow -p wa -k identity\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("99-finalize.rules"),
+            "-w /etc/passwd -p wa -k identity\n-W /etc/shadow -p wa -k identity\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README"), "-x not a rules file\n").unwrap();
+
+        let set = load_audit_rules_dir(dir.path()).unwrap();
+        assert!(set.delete_all);
+        assert_eq!(
+            set.controls,
+            vec![
+                ("-b".to_string(), "320".to_string()),
+                ("-f".to_string(), "1".to_string()),
+            ]
+        );
+
+        // /etc/shadow was removed by -W and the duplicate /etc/passwd watch
+        // was dropped, leaving the first copy and the syscall rule.
+        let rules = set.rules();
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].1.ends_with("50-identity.rules:2"));
+        assert_eq!(
+            rules[0].0.to_string(),
+            "-a always,exit -F path=/etc/passwd -F perm=wa -k identity"
+        );
+        assert_eq!(rules[1].0.list, AuditRuleList::Exit);
+        assert_eq!(rules[1].0.syscalls.len(), 1);
+        assert_eq!(rules[1].0.keys().collect::<Vec<_>>(), vec!["access"]);
+        assert_eq!(rules[1].0.fields[1].op, ">=");
+    }
+
+    #[test]
+    fn malformed_file_is_reported_by_name() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("10-ok.rules"), "-w /etc/hosts -p wa\n").unwrap();
+        std::fs::write(
+            dir.path().join("20-bad.rules"),
+            "-w /etc/group -p wa\n-a always,exit -F bogus=1\n",
+        )
+        .unwrap();
+
+        let err = load_audit_rules_dir(dir.path()).unwrap_err().to_string();
+        assert!(err.contains("20-bad.rules:2"), "{err}");
+        assert!(err.contains("unsupported field 'bogus'"), "{err}");
+    }
+
+    #[test]
+    fn unsupported_auditctl_syntax_is_skipped() {
+        let mut set = AuditRuleSet::default();
+        set.add_rules(
+            "-c\n\
+             --loginuid-immutable\n\
+             -a always,exit -F arch=b64 -S openat -F exit=-EACCES -k access\n\
+             -a always,exit -F exe=/usr/bin/ping -F a0=2\n\
+             -a always,exit -S 2 -C uid!=euid\n\
+             -w /etc/hosts -p wa -k hosts\n",
+            Path::new("inline.rules"),
+        )
+        .unwrap();
+
+        let rules: Vec<String> = set.rules().iter().map(|(r, _)| r.to_string()).collect();
+        assert_eq!(
+            rules,
+            vec!["-a always,exit -F path=/etc/hosts -F perm=wa -k hosts"]
+        );
+        assert!(set.add_rules("-q\n", Path::new("inline.rules")).is_err());
+    }
+
+    #[test]
+    fn prepend_and_delete_by_key() {
+        let mut set = AuditRuleSet::default();
+        set.add_rules(
+            "-a always,exit -S 2 -k first\n\
+             -A never,exit -S 2\n\
+             -w /tmp/x -k drop\n\
+             -D -k drop\n",
+            Path::new("inline.rules"),
+        )
+        .unwrap();
+
+        let rules: Vec<String> = set.rules().iter().map(|(r, _)| r.to_string()).collect();
+        assert_eq!(
+            rules,
+            vec!["-a never,exit -S 2", "-a always,exit -S 2 -k first"]
+        );
+        assert!(!set.delete_all);
+    }
+}
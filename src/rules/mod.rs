@@ -6,13 +6,18 @@
 //!   listing, adding, updating, removing, importing, and dumping filters.
 //! - `watches` provides path-based rules backed by kernel netlink watch rules,
 //!   together with import/export helpers and interactive management.
+//! - `audit_rules` loads augenrules-style directories of auditctl `*.rules`
+//!   files (e.g. `/etc/audit/rules.d/`) into an `AuditRuleSet` that is applied
+//!   to the kernel at daemon startup when `audit_rules_dir` is configured.
 //! A `Rules` value combines both `Filters` and `Watches` and is used by the
 //! daemon state to enforce the current rule set.
 
+pub mod audit_rules;
 pub mod filters;
 pub mod kernel_watches;
 pub mod watches;
 
+pub use audit_rules::{apply_audit_rule_set, load_audit_rules_dir};
 pub use filters::*;
 pub use kernel_watches::apply_watch_kernel_rule;
 pub use watches::*;
@@ -30,3 +35,80 @@ pub struct Rules {
     /// The watches for the auditrs daemon.
     pub(crate) watches: Watches,
 }
+
+/// The combined rule set built from a directory of auditctl `*.rules` files,
+/// following `augenrules` semantics: files are read in sorted order, the last
+/// value of each status setting wins, and duplicate rules are dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditRuleSet {
+    /// Whether a `-D` directive asked for existing kernel rules to be flushed
+    /// before loading.
+    pub(crate) delete_all: bool,
+    /// Kernel status settings (`-b`, `-f`, `-r`, `-e`, `--backlog_wait_time`)
+    /// as `(option, value)`, in first-seen order with the last value kept.
+    pub(crate) controls: Vec<(String, String)>,
+    /// The rules to load, in order, each paired with its `file:line` origin.
+    pub(crate) rules: Vec<(AuditRule, String)>,
+}
+
+/// A single kernel audit rule, parsed from an auditctl `-a`, `-A`, `-d`, `-w`
+/// or `-W` line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRule {
+    /// The filter list the rule is attached to.
+    pub(crate) list: AuditRuleList,
+    /// `true` for `always`, `false` for `never`.
+    pub(crate) always: bool,
+    /// Syscall numbers to match; empty matches every syscall.
+    pub(crate) syscalls: Vec<u32>,
+    /// The `-F` comparisons (plus `-k` keys, stored as `key` fields).
+    pub(crate) fields: Vec<AuditRuleField>,
+}
+
+/// The kernel filter lists an audit rule can be attached to.
+#[derive(Debug, Clone, Copy, strum::EnumString, strum::Display, PartialEq, Eq)]
+#[strum(serialize_all = "lowercase")]
+pub enum AuditRuleList {
+    /// Syscall exit (`exit`), used by watches and most syscall rules.
+    Exit,
+    /// User-space messages (`user`).
+    User,
+    /// Task creation (`task`).
+    Task,
+    /// Record type exclusion (`exclude`).
+    Exclude,
+    /// Filesystem type filtering (`filesystem`).
+    Filesystem,
+}
+
+/// A single `-F <name><op><value>` comparison of an audit rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditRuleField {
+    /// The field name (e.g. `auid`, `path`, `key`).
+    pub(crate) name: String,
+    /// The comparison operator (`=`, `!=`, `<`, `>`, `<=`, `>=`, `&`, `&=`).
+    pub(crate) op: String,
+    /// The value to compare against, as written in the rules file.
+    pub(crate) value: String,
+}
+
+/// Error for valid auditctl syntax that auditrs cannot translate into a kernel
+/// rule (e.g. `-F exit=-EACCES` or `--loginuid-immutable`). Lines failing with
+/// it are skipped with a warning rather than failing the whole load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsupportedDirective(pub(crate) String);
+
+/// A single directive from an auditctl `*.rules` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditRuleDirective {
+    /// `-D`: flush existing kernel rules before loading.
+    DeleteAll,
+    /// `-D -k <key>`: drop previously loaded rules carrying `key`.
+    DeleteKey(String),
+    /// A kernel status setting such as `-b 8192`, as `(option, value)`.
+    Control(String, String),
+    /// `-a`/`-w` (append) or `-A` (prepend, when `true`) a rule.
+    Add(AuditRule, bool),
+    /// `-d`/`-W`: remove a previously loaded rule.
+    Delete(AuditRule),
+}
@@ -0,0 +1,1055 @@
+//! Batch conversion of existing audit logs into other auditrs output formats.
+//!
+//! `auditrs convert` streams an existing log (e.g. `/var/log/audit/
+//! audit.log`), regroups consecutive records sharing a `(timestamp, serial)`
+//! into events, and writes them in the requested format (JSON output is a
+//! single array document, closed once the input is exhausted). Because inputs
+//! can be very large, the run can optionally record its progress in a
+//! checkpoint file so an interrupted conversion resumes where it left off
+//! instead of starting over.
+//!
+//! The input may be auditd text, JSONL (one auditrs event per line) or a hex
+//! netlink capture, optionally gzip-compressed. Compression is recognized by
+//! its magic bytes and the format by the first line; when the first line fits
+//! none of the formats, the run stops and asks for `--input-format`.
+//!
+//! A capture is replayed through the daemon's pipeline instead of being
+//! regrouped line by line: each frame goes through the netlink decoder and the
+//! parser, and a [`Correlator`] groups the records, so records of interleaved
+//! events (as the kernel delivers them) still form whole events. This makes a
+//! capture of a live session reprocessable into any output format.
+//!
+//! With `--output-dir`, events are written to one file per UTC day instead,
+//! named from `--file-pattern` (`audit-%Y-%m-%d.log` by default); the run
+//! moves on to the next file at each day boundary.
+//!
+//! With `--merge`, several inputs (e.g. rotated logs) are read in full and
+//! their records correlated across files by `(timestamp, serial)` and `node=`
+//! host, so events that straddle a file boundary come out whole. Events are
+//! written sorted by `(timestamp, serial)` and then host, and records found in
+//! more than one input are written once. The host is written as a trailing
+//! `node=<host>` payload field, not as auditd's leading `node=` prefix.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use clap::ArgMatches;
+use flate2::read::GzDecoder;
+
+use crate::config::LogFormat;
+use crate::core::{
+    correlator::{AuditEvent, Correlator, NodeKeyExtractor},
+    netlink::RawAuditRecord,
+    parser::{
+        OffsetRecordReader,
+        ParseOptions,
+        ParsedAuditRecord,
+        RecordReader,
+        parser::split_node,
+    },
+    writer::{AuditLogWriter, JsonArrayWriter},
+};
+use crate::tools::{
+    Checkpoint,
+    ConvertOptions,
+    ConvertOutput,
+    ConvertSummary,
+    DailyOutput,
+    InputFormat,
+    MergeSummary,
+};
+use crate::utils::{
+    escape_invalid_utf8,
+    parse_legacy_primary_line,
+    systemtime_to_timestamp_string,
+};
+
+/// Number of events written between checkpoint updates when
+/// `--checkpoint-interval` is not given.
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 1000;
+
+/// File name pattern for `--output-dir` when `--file-pattern` is not given.
+pub const DEFAULT_DAILY_PATTERN: &str = "audit-%Y-%m-%d.log";
+
+/// Length of the `\n]\n` that closes a non-empty JSON array output.
+const JSON_ARRAY_CLOSE_LEN: u64 = 3;
+
+/// The first two bytes of every gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Length of a netlink message header; a capture line encodes at least this
+/// many bytes.
+const NETLINK_HEADER_LEN: usize = 16;
+
+/// Entry point for `auditrs convert`; builds [`ConvertOptions`] from the CLI
+/// arguments, runs the conversion, and prints a short summary.
+///
+/// **Parameters:**
+///
+/// * `matches`: The CLI arguments to the convert command.
+pub fn convert_logs(matches: &ArgMatches) -> Result<()> {
+    let inputs: Vec<PathBuf> = matches
+        .get_many::<String>("input")
+        .context("missing input")?
+        .map(PathBuf::from)
+        .collect();
+    let merge = matches.get_flag("merge");
+    if inputs.len() > 1 && !merge {
+        bail!("Converting several inputs requires --merge");
+    }
+    let options = ConvertOptions {
+        input: inputs[0].clone(),
+        input_format: matches
+            .get_one::<String>("input_format")
+            .map(|f| f.parse::<InputFormat>())
+            .transpose()?,
+        output: PathBuf::from(
+            matches
+                .get_one::<String>("output")
+                .or(matches.get_one::<String>("output_dir"))
+                .context("missing output")?,
+        ),
+        daily_pattern: matches.contains_id("output_dir").then(|| {
+            matches
+                .get_one::<String>("file_pattern")
+                .map_or(DEFAULT_DAILY_PATTERN, String::as_str)
+                .to_string()
+        }),
+        format: matches
+            .get_one::<String>("format")
+            .map(|f| f.parse::<LogFormat>())
+            .transpose()?
+            .unwrap_or(LogFormat::Legacy),
+        checkpoint: matches.get_one::<String>("checkpoint").map(PathBuf::from),
+        checkpoint_interval: matches
+            .get_one::<usize>("checkpoint_interval")
+            .copied()
+            .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+    };
+
+    if merge {
+        let summary = run_merge(&inputs, &options)?;
+        println!(
+            "Merged {} events from {} inputs into {}",
+            summary.events,
+            inputs.len(),
+            options.output.display()
+        );
+        if summary.duplicate_records > 0 {
+            println!(
+                "Dropped {} records found in more than one input",
+                summary.duplicate_records
+            );
+        }
+        if summary.serial_gaps > 0 {
+            eprintln!(
+                "warning: serials skip ahead {} times; events may be missing from the inputs",
+                summary.serial_gaps
+            );
+        }
+        if summary.skipped_lines > 0 {
+            eprintln!(
+                "warning: skipped {} unparseable lines",
+                summary.skipped_lines
+            );
+        }
+        return Ok(());
+    }
+
+    let summary = run_conversion(&options)?;
+    if let Some(offset) = summary.resumed_from {
+        println!("Resumed from checkpoint at input byte {offset}");
+    }
+    println!(
+        "Converted {} events into {}",
+        summary.events,
+        options.output.display()
+    );
+    if summary.skipped_lines > 0 {
+        eprintln!(
+            "warning: skipped {} unparseable lines",
+            summary.skipped_lines
+        );
+    }
+    Ok(())
+}
+
+/// Converts `options.input` into `options.output`.
+///
+/// When a checkpoint file is configured and already exists, the output is
+/// truncated back to the length recorded in the checkpoint and reading resumes
+/// at the recorded input offset, so events are neither lost nor duplicated.
+/// The checkpoint is rewritten every `checkpoint_interval` events and removed
+/// once the whole input has been converted.
+///
+/// **Parameters:**
+///
+/// * `options`: Input/output paths, output format, and checkpoint settings.
+pub fn run_conversion(options: &ConvertOptions) -> Result<ConvertSummary> {
+    if options.daily_pattern.is_some() && options.checkpoint.is_some() {
+        bail!("Checkpoints are not supported with daily output files");
+    }
+    let checkpoint = match &options.checkpoint {
+        Some(path) => load_checkpoint(path, &options.input)?,
+        None => None,
+    };
+    let (input_offset, output_offset) = checkpoint
+        .as_ref()
+        .map_or((0, 0), |c| (c.input_offset, c.output_offset));
+
+    let (input, input_format) = open_input(options, input_offset)?;
+    if input_format == InputFormat::Capture && options.checkpoint.is_some() {
+        bail!("Checkpoints are not supported for capture input");
+    }
+    let mut output = match &options.daily_pattern {
+        Some(pattern) => {
+            ConvertOutput::Daily(DailyOutput::new(&options.output, pattern, options.format)?)
+        }
+        // Anything written after the last checkpoint is regenerated below.
+        None => ConvertOutput::open(&options.output, output_offset, options.format)?,
+    };
+    if input_format == InputFormat::Capture {
+        return replay_capture(input, output);
+    }
+
+    let mut summary = ConvertSummary {
+        events: 0,
+        skipped_lines: 0,
+        resumed_from: checkpoint.map(|c| c.input_offset),
+    };
+    let mut pending: Vec<ParsedAuditRecord> = Vec::new();
+    let mut since_checkpoint = 0;
+
+    for result in input_records(input, input_format, input_offset) {
+        let (line_offset, record) = match result {
+            Ok(record) => record,
+            Err(e) if is_read_error(&e) => return Err(e),
+            Err(_) => {
+                summary.skipped_lines += 1;
+                continue;
+            }
+        };
+        // auditd writes the records of an event next to each other, so a new
+        // (timestamp, serial) means the pending event is complete.
+        if pending
+            .first()
+            .is_some_and(|first| first.identifier() != record.identifier())
+        {
+            let event = event_from_records(std::mem::take(&mut pending));
+            output.write_event(&event)?;
+            summary.events += 1;
+            since_checkpoint += 1;
+
+            if let Some(path) = &options.checkpoint
+                && since_checkpoint >= options.checkpoint_interval.max(1)
+            {
+                // The current line starts the next event, so it is where a
+                // resumed run has to pick up.
+                save_checkpoint(
+                    path,
+                    &Checkpoint {
+                        input: options.input.clone(),
+                        input_offset: line_offset,
+                        output_offset: output.position()?,
+                        last_event: Some(event_key(&event)?),
+                    },
+                )?;
+                since_checkpoint = 0;
+            }
+        }
+        pending.push(record);
+    }
+
+    if !pending.is_empty() {
+        let event = event_from_records(pending);
+        output.write_event(&event)?;
+        summary.events += 1;
+    }
+    output.finish()?;
+
+    if let Some(path) = &options.checkpoint
+        && path.exists()
+    {
+        fs::remove_file(path)
+            .with_context(|| format!("Could not remove checkpoint {}", path.display()))?;
+    }
+    Ok(summary)
+}
+
+/// Replays a netlink capture through the stages of the daemon's pipeline:
+/// each frame is decoded and parsed like a record read from the audit socket,
+/// and the records are grouped by a [`Correlator`]. The correlator holds every
+/// group until the capture is exhausted, so no event is split by the timeout;
+/// the events are then written sorted by `(timestamp, serial)`.
+///
+/// **Parameters:**
+///
+/// * `input`: The capture, positioned at its start.
+/// * `output`: Where the events are written.
+fn replay_capture(input: Box<dyn BufRead>, mut output: ConvertOutput) -> Result<ConvertSummary> {
+    let mut correlator = Correlator::new();
+    let mut summary = ConvertSummary {
+        events: 0,
+        skipped_lines: 0,
+        resumed_from: None,
+    };
+    for result in input_records(input, InputFormat::Capture, 0) {
+        match result {
+            Ok((_, record)) => correlator.push(record),
+            Err(e) if is_read_error(&e) => return Err(e),
+            Err(_) => summary.skipped_lines += 1,
+        }
+    }
+
+    for event in correlator.flush_all() {
+        output.write_event(&event)?;
+        summary.events += 1;
+    }
+    output.finish()?;
+    Ok(summary)
+}
+
+/// Merges `inputs` into `options.output` (or daily files) as one stream sorted
+/// by `(timestamp, serial)`, and by host for events sharing both.
+///
+/// Every input is read before anything is written, and the records of all
+/// inputs go through one [`Correlator`] with a [`NodeKeyExtractor`], so an
+/// event split across a rotation boundary is written whole and events of
+/// different hosts are never merged. A record identical
+/// to one already in its event is dropped. Serial gaps are counted per host.
+/// The format of each input is detected separately unless
+/// `options.input_format` is set; `options.input` is ignored.
+///
+/// **Parameters:**
+///
+/// * `inputs`: The audit logs to merge.
+/// * `options`: Output path, output format and input format; checkpoints are
+///   not supported.
+pub fn run_merge(inputs: &[PathBuf], options: &ConvertOptions) -> Result<MergeSummary> {
+    if options.checkpoint.is_some() {
+        bail!("Checkpoints are not supported when merging");
+    }
+    let mut summary = MergeSummary {
+        events: 0,
+        duplicate_records: 0,
+        skipped_lines: 0,
+        serial_gaps: 0,
+    };
+    let mut correlator = Correlator::new();
+    correlator.set_key_extractor(Box::new(NodeKeyExtractor::default()));
+    for input in inputs {
+        let input_options = ConvertOptions {
+            input: input.clone(),
+            ..options.clone()
+        };
+        let (reader, input_format) = open_input(&input_options, 0)?;
+        for result in input_records(reader, input_format, 0) {
+            match result {
+                Ok((_, record)) => correlator.push(record),
+                Err(e) if is_read_error(&e) => return Err(e),
+                Err(_) => summary.skipped_lines += 1,
+            }
+        }
+    }
+
+    let mut output = match &options.daily_pattern {
+        Some(pattern) => {
+            ConvertOutput::Daily(DailyOutput::new(&options.output, pattern, options.format)?)
+        }
+        None => ConvertOutput::open(&options.output, 0, options.format)?,
+    };
+    let mut previous_serials: HashMap<Option<String>, u16> = HashMap::new();
+    for event in correlator.flush_all() {
+        let mut records: Vec<ParsedAuditRecord> = Vec::with_capacity(event.records.len());
+        for record in event.records {
+            if records.contains(&record) {
+                summary.duplicate_records += 1;
+            } else {
+                records.push(record);
+            }
+        }
+        let node = records[0].get("node").map(str::to_string);
+        if let Some(previous) = previous_serials.insert(node, event.serial)
+            && event.serial > previous.saturating_add(1)
+        {
+            summary.serial_gaps += 1;
+        }
+        output.write_event(&event_from_records(records))?;
+        summary.events += 1;
+    }
+    output.finish()?;
+    Ok(summary)
+}
+
+/// Opens `options.input` positioned at `offset`, transparently decompressing
+/// gzip input, and resolves its format (from `options.input_format` or by
+/// [`detect_input_format`] on the first line). For gzip input, `offset` counts
+/// decompressed bytes.
+///
+/// **Parameters:**
+///
+/// * `options`: The input path and optional explicit format.
+/// * `offset`: Byte offset to resume reading from.
+fn open_input(options: &ConvertOptions, offset: u64) -> Result<(Box<dyn BufRead>, InputFormat)> {
+    let path = &options.input;
+    let mut plain = BufReader::new(
+        File::open(path).with_context(|| format!("Could not open {}", path.display()))?,
+    );
+    let resolve = |sample: &[u8]| -> Result<InputFormat> {
+        if let Some(format) = options.input_format {
+            return Ok(format);
+        }
+        detect_input_format(sample)
+            .with_context(|| format!("Could not detect the format of {}", path.display()))
+    };
+
+    if plain.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        let mut decoded = BufReader::new(GzDecoder::new(plain));
+        let format = resolve(decoded.fill_buf()?)?;
+        // A gzip stream cannot seek; skip already converted data instead.
+        io::copy(&mut (&mut decoded).take(offset), &mut io::sink())?;
+        Ok((Box::new(decoded), format))
+    } else {
+        let format = resolve(plain.fill_buf()?)?;
+        plain.seek(SeekFrom::Start(offset))?;
+        Ok((Box::new(plain), format))
+    }
+}
+
+/// Streams the records of an input in `format`, each with the byte offset of
+/// the line it was read from.
+///
+/// **Parameters:**
+///
+/// * `input`: The input, positioned at `offset`.
+/// * `format`: The input format.
+/// * `offset`: Byte offset the input is positioned at.
+fn input_records(
+    input: Box<dyn BufRead>,
+    format: InputFormat,
+    offset: u64,
+) -> OffsetRecordReader<Box<dyn BufRead>> {
+    RecordReader::new(input, ParseOptions::default())
+        .starting_at(offset)
+        .with_line_parser(Box::new(move |line| parse_input_line(format, line)))
+        .with_offsets()
+}
+
+/// Whether an error from [`input_records`] comes from reading the input,
+/// which stops the run, rather than from a line that could not be parsed,
+/// which is skipped.
+///
+/// **Parameters:**
+///
+/// * `error`: The error yielded by the reader.
+fn is_read_error(error: &anyhow::Error) -> bool {
+    error.is::<io::Error>()
+}
+
+/// Detects the input format from the start of an (uncompressed) input: auditd
+/// lines start with `type=` or `node=`, JSONL lines with `{`, and capture lines
+/// are hex encoding at least a netlink header. An empty input is treated as
+/// legacy. Anything else is an error so the caller can ask for an explicit
+/// format.
+///
+/// **Parameters:**
+///
+/// * `sample`: The first bytes of the input, including its first full line.
+pub fn detect_input_format(sample: &[u8]) -> Result<InputFormat> {
+    let text = escape_invalid_utf8(sample);
+    let Some(line) = text.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return Ok(InputFormat::Legacy);
+    };
+    if line.starts_with("type=") || line.starts_with("node=") {
+        Ok(InputFormat::Legacy)
+    } else if line.starts_with('{') {
+        Ok(InputFormat::Jsonl)
+    } else if line.len() >= NETLINK_HEADER_LEN * 2
+        && line.len().is_multiple_of(2)
+        && line.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        Ok(InputFormat::Capture)
+    } else {
+        bail!("first line is not legacy, JSONL or capture data; pass --input-format")
+    }
+}
+
+/// Parses one non-empty input line into the records it holds: one for legacy
+/// and capture lines, all records of the event for JSONL. The host of a
+/// `node=` prefixed legacy line is kept as the record's `node` field, which
+/// writers output as an ordinary trailing `node=<host>` payload field rather
+/// than as a prefix.
+///
+/// **Parameters:**
+///
+/// * `format`: The input format.
+/// * `line`: The trimmed line.
+fn parse_input_line(format: InputFormat, line: &str) -> Result<Vec<ParsedAuditRecord>> {
+    match format {
+        InputFormat::Legacy => {
+            // Keep the host of `node=` prefixed lines, so merging does not
+            // group events of different machines.
+            let (node, line) = split_node(line);
+            let mut record = parse_legacy_primary_line(line)?;
+            if let Some(node) = node {
+                record
+                    .fields
+                    .entry("node".to_string())
+                    .or_insert_with(|| node.to_string());
+            }
+            Ok(vec![record])
+        }
+        InputFormat::Jsonl => Ok(serde_json::from_str::<AuditEvent>(line)?.records),
+        InputFormat::Capture => {
+            let raw = RawAuditRecord::from_netlink_bytes(&hex::decode(line)?)?;
+            Ok(vec![ParsedAuditRecord::try_from(raw)?])
+        }
+    }
+}
+
+/// Loads the checkpoint at `path`, if any, and checks that it belongs to
+/// `input`.
+///
+/// **Parameters:**
+///
+/// * `path`: The checkpoint file.
+/// * `input`: The input file of the current run.
+fn load_checkpoint(path: &Path, input: &Path) -> Result<Option<Checkpoint>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Could not read checkpoint {}", path.display()))?;
+    let checkpoint: Checkpoint = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid checkpoint file {}", path.display()))?;
+    if checkpoint.input != input {
+        bail!(
+            "Checkpoint {} belongs to {}, not {}",
+            path.display(),
+            checkpoint.input.display(),
+            input.display()
+        );
+    }
+    Ok(Some(checkpoint))
+}
+
+/// Atomically replaces the checkpoint at `path` (write to a temporary file,
+/// then rename) so a crash never leaves a half-written checkpoint behind.
+///
+/// **Parameters:**
+///
+/// * `path`: The checkpoint file.
+/// * `checkpoint`: The progress to record.
+fn save_checkpoint(path: &Path, checkpoint: &Checkpoint) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, serde_json::to_string(checkpoint)?)
+        .with_context(|| format!("Could not write checkpoint {}", tmp.display()))?;
+    fs::rename(&tmp, path)
+        .with_context(|| format!("Could not replace checkpoint {}", path.display()))?;
+    Ok(())
+}
+
+/// Builds an [`AuditEvent`] from records that share a `(timestamp, serial)`.
+///
+/// **Parameters:**
+///
+/// * `records`: The non-empty list of records making up the event.
+fn event_from_records(records: Vec<ParsedAuditRecord>) -> AuditEvent {
+    AuditEvent {
+        timestamp: records[0].timestamp,
+        serial: records[0].serial,
+        record_count: records.len() as u16,
+        records,
+        amendment: false,
+    }
+}
+
+/// Returns the `<timestamp>:<serial>` key of an event.
+///
+/// **Parameters:**
+///
+/// * `event`: The event to describe.
+fn event_key(event: &AuditEvent) -> Result<String> {
+    Ok(format!(
+        "{}:{}",
+        systemtime_to_timestamp_string(event.timestamp)?,
+        event.serial
+    ))
+}
+
+impl ConvertOutput {
+    /// Opens the output file at `path`, truncated to `offset`, and continues
+    /// writing at its end.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `path`: The output file.
+    /// * `offset`: Length of the output to keep; `0` starts a new file.
+    /// * `format`: The output format.
+    fn open(path: &Path, offset: u64, format: LogFormat) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            // Cut to `offset` below instead.
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Could not open {}", path.display()))?;
+        file.set_len(offset)?;
+        file.seek(SeekFrom::Start(offset))?;
+        Ok(match format {
+            // Kept JSON output always ends right after an element.
+            LogFormat::Json => ConvertOutput::Json(JsonArrayWriter::resume(file, offset > 0)),
+            format => ConvertOutput::Lines(file, format),
+        })
+    }
+
+    /// Writes a single converted event.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to write.
+    fn write_event(&mut self, event: &AuditEvent) -> Result<()> {
+        let events = std::slice::from_ref(event);
+        match self {
+            ConvertOutput::Lines(file, LogFormat::Legacy) => {
+                AuditLogWriter::write_events_legacy(file, events)
+            }
+            ConvertOutput::Lines(file, LogFormat::Simple) => {
+                AuditLogWriter::write_events_simple(file, events)
+            }
+            ConvertOutput::Lines(file, LogFormat::Ecs) => {
+                AuditLogWriter::write_events_ecs(file, events)
+            }
+            ConvertOutput::Lines(_, LogFormat::Json) => {
+                unreachable!("JSON output always uses ConvertOutput::Json")
+            }
+            ConvertOutput::Json(writer) => writer.write_event(event),
+            ConvertOutput::Daily(daily) => daily.write_event(event),
+        }
+    }
+
+    /// Current length of the output, as recorded in checkpoints.
+    fn position(&mut self) -> Result<u64> {
+        let file = match self {
+            ConvertOutput::Lines(file, _) => file,
+            ConvertOutput::Json(writer) => writer.get_mut(),
+            ConvertOutput::Daily(daily) => {
+                match &mut daily.current {
+                    Some((_, output)) => return output.position(),
+                    None => return Ok(0),
+                }
+            }
+        };
+        Ok(file.stream_position()?)
+    }
+
+    /// Completes the output (closing the JSON array, if any) and syncs it to
+    /// disk.
+    fn finish(self) -> Result<()> {
+        match self {
+            ConvertOutput::Lines(file, _) => file.sync_data()?,
+            ConvertOutput::Json(mut writer) => {
+                writer.finish()?;
+                writer.get_mut().sync_data()?;
+            }
+            ConvertOutput::Daily(daily) => {
+                if let Some((_, output)) = daily.current {
+                    output.finish()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DailyOutput {
+    /// Creates the daily output, creating `dir` if it does not exist.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `dir`: The directory to write the daily files to.
+    /// * `pattern`: strftime pattern naming each file from the event date.
+    /// * `format`: The output format.
+    fn new(dir: &Path, pattern: &str, format: LogFormat) -> Result<Self> {
+        if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+            bail!("Invalid file name pattern {pattern:?}");
+        }
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Could not create directory {}", dir.display()))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            pattern: pattern.to_string(),
+            format,
+            current: None,
+            written: HashSet::new(),
+        })
+    }
+
+    /// Writes `event` to the file of its UTC day, closing the previous day's
+    /// file first when the day changed.
+    ///
+    /// **Parameters:**
+    ///
+    /// * `event`: The event to write.
+    fn write_event(&mut self, event: &AuditEvent) -> Result<()> {
+        let name = DateTime::<Utc>::from(event.timestamp)
+            .format(&self.pattern)
+            .to_string();
+        if let Some((current, output)) = &mut self.current
+            && *current == name
+        {
+            return output.write_event(event);
+        }
+
+        if let Some((_, output)) = self.current.take() {
+            output.finish()?;
+        }
+        let path = self.dir.join(&name);
+        // A day already written by this run (input slightly out of order around
+        // midnight) is continued rather than replaced.
+        let offset = if self.written.insert(name.clone()) {
+            0
+        } else {
+            let len = fs::metadata(&path)?.len();
+            match self.format {
+                LogFormat::Json => len.saturating_sub(JSON_ARRAY_CLOSE_LEN),
+                _ => len,
+            }
+        };
+        let mut output = ConvertOutput::open(&path, offset, self.format)?;
+        output.write_event(event)?;
+        self.current = Some((name, Box::new(output)));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // One field per record keeps the legacy output byte-for-byte stable.
+    const INPUT: &str = "\
+type=SYSCALL msg=audit(1700000000.100:1): syscall=59
+type=EXECVE msg=audit(1700000000.100:1): a0=\"ls\"
+type=SYSCALL msg=audit(1700000001.200:2): syscall=2
+type=PATH msg=audit(1700000001.200:2): name=\"/etc/shadow\"
+type=SYSCALL msg=audit(1700000002.300:3): syscall=59
+";
+
+    fn options(dir: &Path, checkpoint: bool) -> ConvertOptions {
+        ConvertOptions {
+            input: dir.join("audit.log"),
+            input_format: None,
+            output: dir.join("converted.log"),
+            daily_pattern: None,
+            format: LogFormat::Legacy,
+            checkpoint: checkpoint.then(|| dir.join("convert.checkpoint")),
+            checkpoint_interval: 1,
+        }
+    }
+
+    #[test]
+    fn converts_and_regroups_events() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("audit.log"), INPUT).unwrap();
+
+        let summary = run_conversion(&options(dir.path(), false)).unwrap();
+
+        assert_eq!(summary.events, 3);
+        assert_eq!(summary.skipped_lines, 0);
+        assert_eq!(summary.resumed_from, None);
+        let output = fs::read_to_string(dir.path().join("converted.log")).unwrap();
+        assert_eq!(output.lines().count(), 5);
+    }
+
+    #[test]
+    fn resumes_from_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("audit.log"), INPUT).unwrap();
+
+        // Reference output from an uninterrupted run.
+        run_conversion(&options(dir.path(), false)).unwrap();
+        let expected = fs::read_to_string(dir.path().join("converted.log")).unwrap();
+        let first_event_len = expected
+            .lines()
+            .take(2)
+            .map(|l| l.len() as u64 + 1)
+            .sum::<u64>();
+
+        // Simulate a run that checkpointed after the first event, then crashed
+        // half-way through writing the second one.
+        let second_event_offset = INPUT
+            .lines()
+            .take(2)
+            .map(|l| l.len() as u64 + 1)
+            .sum::<u64>();
+        let opts = options(dir.path(), true);
+        let checkpoint_path = opts.checkpoint.clone().unwrap();
+        save_checkpoint(
+            &checkpoint_path,
+            &Checkpoint {
+                input: opts.input.clone(),
+                input_offset: second_event_offset,
+                output_offset: first_event_len,
+                last_event: Some("1700000000.100:1".to_string()),
+            },
+        )
+        .unwrap();
+        let mut partial = expected[..first_event_len as usize].to_string();
+        partial.push_str("type=SYSCALL msg=audit(1700000001.2");
+        fs::write(&opts.output, partial).unwrap();
+
+        let summary = run_conversion(&opts).unwrap();
+
+        assert_eq!(summary.resumed_from, Some(second_event_offset));
+        assert_eq!(summary.events, 2);
+        assert_eq!(fs::read_to_string(&opts.output).unwrap(), expected);
+        assert!(!checkpoint_path.exists());
+    }
+
+    #[test]
+    fn converts_to_json_array() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("audit.log"), INPUT).unwrap();
+        let opts = ConvertOptions {
+            format: LogFormat::Json,
+            ..options(dir.path(), false)
+        };
+
+        let summary = run_conversion(&opts).unwrap();
+
+        assert_eq!(summary.events, 3);
+        let events: Vec<AuditEvent> =
+            serde_json::from_str(&fs::read_to_string(&opts.output).unwrap()).unwrap();
+        assert_eq!(
+            events.iter().map(|e| e.serial).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        assert_eq!(events[1].records.len(), 2);
+        assert_eq!(events[1].records[1].fields["name"], "/etc/shadow");
+    }
+
+    #[test]
+    fn rejects_checkpoint_for_other_input() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("audit.log"), INPUT).unwrap();
+        let opts = options(dir.path(), true);
+        save_checkpoint(
+            opts.checkpoint.as_ref().unwrap(),
+            &Checkpoint {
+                input: PathBuf::from("/var/log/audit/other.log"),
+                input_offset: 0,
+                output_offset: 0,
+                last_event: None,
+            },
+        )
+        .unwrap();
+
+        assert!(run_conversion(&opts).is_err());
+    }
+
+    #[test]
+    fn detects_each_input_format() {
+        let capture = fs::read_to_string("tests/test-source.log").unwrap();
+        let jsonl = r#"{"timestamp":"1700000000.100","serial":1,"records":[]}"#;
+
+        assert_eq!(
+            detect_input_format(INPUT.as_bytes()).unwrap(),
+            InputFormat::Legacy
+        );
+        assert_eq!(
+            detect_input_format(b"node=host type=SYSCALL msg=audit(1.000:1): a=b\n").unwrap(),
+            InputFormat::Legacy
+        );
+        assert_eq!(
+            detect_input_format(jsonl.as_bytes()).unwrap(),
+            InputFormat::Jsonl
+        );
+        assert_eq!(
+            detect_input_format(capture.as_bytes()).unwrap(),
+            InputFormat::Capture
+        );
+        assert!(detect_input_format(b"Oct 16 10:00:00 host sshd[1]: hello\n").is_err());
+        assert!(detect_input_format(b"deadbeef\n").is_err());
+    }
+
+    #[test]
+    fn converts_gzip_input_and_jsonl_input() {
+        use flate2::{Compression, write::GzEncoder};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut encoder = GzEncoder::new(
+            File::create(dir.path().join("audit.log")).unwrap(),
+            Compression::default(),
+        );
+        encoder.write_all(INPUT.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let mut opts = options(dir.path(), false);
+        let (_, format) = open_input(&opts, 0).unwrap();
+        assert_eq!(format, InputFormat::Legacy);
+        opts.format = LogFormat::Json;
+        assert_eq!(run_conversion(&opts).unwrap().events, 3);
+
+        // Feed the converted events back in, one per line.
+        let events: Vec<AuditEvent> =
+            serde_json::from_str(&fs::read_to_string(&opts.output).unwrap()).unwrap();
+        let jsonl: Vec<String> = events
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap())
+            .collect();
+        fs::write(&opts.input, jsonl.join("\n") + "\n").unwrap();
+        opts.format = LogFormat::Legacy;
+        let summary = run_conversion(&opts).unwrap();
+        assert_eq!(summary.events, 3);
+        assert_eq!(summary.skipped_lines, 0);
+        let output = fs::read_to_string(&opts.output).unwrap();
+        assert_eq!(output.lines().count(), 5);
+    }
+
+    #[test]
+    fn explicit_format_overrides_detection() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("audit.log"), "something else\n").unwrap();
+        let mut opts = options(dir.path(), false);
+        let err = run_conversion(&opts).unwrap_err();
+        assert!(format!("{err:#}").contains("--input-format"));
+
+        opts.input_format = Some(InputFormat::Legacy);
+        assert_eq!(run_conversion(&opts).unwrap().skipped_lines, 1);
+    }
+
+    #[test]
+    fn output_dir_writes_one_file_per_day() {
+        let dir = tempfile::tempdir().unwrap();
+        // Two days later, plus a late record from the first day.
+        let input = format!(
+            "{INPUT}type=SYSCALL msg=audit(1700172800.000:4): syscall=2\n\
+             type=SYSCALL msg=audit(1700000003.400:5): syscall=2\n"
+        );
+        fs::write(dir.path().join("audit.log"), input).unwrap();
+        let out_dir = dir.path().join("daily/audit");
+        let opts = ConvertOptions {
+            output: out_dir.clone(),
+            daily_pattern: Some(DEFAULT_DAILY_PATTERN.to_string()),
+            ..options(dir.path(), false)
+        };
+
+        assert_eq!(run_conversion(&opts).unwrap().events, 5);
+
+        let mut files: Vec<String> = fs::read_dir(&out_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["audit-2023-11-14.log", "audit-2023-11-16.log"]);
+        let first_day = fs::read_to_string(out_dir.join("audit-2023-11-14.log")).unwrap();
+        assert_eq!(first_day.lines().count(), 6);
+        let second_day = fs::read_to_string(out_dir.join("audit-2023-11-16.log")).unwrap();
+        assert!(second_day.starts_with("type=SYSCALL msg=audit(1700172800.000:4)"));
+
+        let opts = ConvertOptions {
+            daily_pattern: Some("audit-%Q.log".to_string()),
+            ..opts
+        };
+        assert!(run_conversion(&opts).is_err());
+    }
+
+    #[test]
+    fn merges_overlapping_files_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        // The older log ends half-way through event 3; the newer one repeats
+        // event 2, holds the rest of event 3 and skips serial 5.
+        let older = dir.path().join("audit.log.1");
+        fs::write(
+            &older,
+            "type=SYSCALL msg=audit(1700000000.100:1): syscall=59\n\
+             type=SYSCALL msg=audit(1700000001.200:2): syscall=2\n\
+             type=PATH msg=audit(1700000001.200:2): name=\"/etc/shadow\"\n\
+             type=SYSCALL msg=audit(1700000002.300:3): syscall=59\n",
+        )
+        .unwrap();
+        let newer = dir.path().join("audit.log");
+        fs::write(
+            &newer,
+            "type=SYSCALL msg=audit(1700000001.200:2): syscall=2\n\
+             type=PATH msg=audit(1700000001.200:2): name=\"/etc/shadow\"\n\
+             type=EXECVE msg=audit(1700000002.300:3): a0=\"ls\"\n\
+             type=SYSCALL msg=audit(1700000004.500:6): syscall=2\n\
+             type=SYSCALL msg=audit(1700000003.400:4): syscall=2\n",
+        )
+        .unwrap();
+
+        // Inputs are given newest first, as a shell glob would sort them.
+        let summary = run_merge(&[newer, older], &options(dir.path(), false)).unwrap();
+
+        assert_eq!(
+            summary,
+            MergeSummary {
+                events: 5,
+                duplicate_records: 2,
+                skipped_lines: 0,
+                serial_gaps: 1,
+            }
+        );
+        let output = fs::read_to_string(dir.path().join("converted.log")).unwrap();
+        let ids: Vec<&str> = output
+            .lines()
+            .map(|line| line.split(['(', ')']).nth(1).unwrap())
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                "1700000000.100:1",
+                "1700000001.200:2",
+                "1700000001.200:2",
+                "1700000002.300:3",
+                "1700000002.300:3",
+                "1700000003.400:4",
+                "1700000004.500:6",
+            ]
+        );
+    }
+
+    #[test]
+    fn merge_keeps_events_of_different_nodes_apart() {
+        let dir = tempfile::tempdir().unwrap();
+        let first = dir.path().join("host1.log");
+        fs::write(
+            &first,
+            "node=host1 type=SYSCALL msg=audit(1700000000.100:1): syscall=59\n",
+        )
+        .unwrap();
+        let second = dir.path().join("host2.log");
+        fs::write(
+            &second,
+            "node=host2 type=SYSCALL msg=audit(1700000000.100:1): syscall=2\n\
+             node=host2 type=SYSCALL msg=audit(1700000000.100:1): syscall=2\n\
+             node=host2 type=SYSCALL msg=audit(1700000001.100:2): syscall=2\n",
+        )
+        .unwrap();
+
+        let summary = run_merge(&[first, second], &options(dir.path(), false)).unwrap();
+
+        assert_eq!(
+            summary,
+            MergeSummary {
+                events: 3,
+                duplicate_records: 1,
+                skipped_lines: 0,
+                serial_gaps: 0,
+            }
+        );
+        let output = fs::read_to_string(dir.path().join("converted.log")).unwrap();
+        assert_eq!(output.matches("node=host1").count(), 1);
+        assert_eq!(output.matches("node=host2").count(), 2);
+        // Events sharing a (timestamp, serial) are ordered by host.
+        assert!(output.find("node=host1") < output.find("node=host2"));
+    }
+}
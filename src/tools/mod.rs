@@ -5,9 +5,24 @@
 //! - `search`: facilities for querying logs.
 //! - `report`: reporting and analysis helpers for generating human-readable
 //!   summaries.
+//! - `convert`: batch conversion of existing audit logs between output formats,
+//!   with optional checkpointing so long runs can be resumed. The input format
+//!   (legacy text, JSONL, hex netlink capture, any of them gzip-compressed) is
+//!   detected automatically unless given explicitly. The output is a single
+//!   file or, with `--output-dir`, one dated file per day. With `--merge`,
+//!   several inputs (e.g. rotated logs) are combined into one time-sorted
+//!   output.
 
-use std::collections::{BTreeSet, HashMap};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::path::PathBuf;
 
+use serde::{Deserialize, Serialize};
+
+use crate::config::LogFormat;
+use crate::core::writer::JsonArrayWriter;
+
+pub mod convert;
 pub mod report;
 pub mod search;
 
@@ -36,3 +51,106 @@ struct ForensicsAggregates {
     /// SYSCALL `comm` values (short command names) with occurrence counts.
     command_counts: HashMap<String, u32>,
 }
+
+/// Formats `auditrs convert` can read. Any of them may additionally be
+/// gzip-compressed, which is always detected from the file's magic bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum InputFormat {
+    /// auditd text log lines (`type=... msg=audit(...): ...`).
+    Legacy,
+    /// One auditrs JSON event object per line.
+    Jsonl,
+    /// Captured netlink frames, one hex-encoded frame per line (the format of
+    /// `tests/test-source.log`).
+    Capture,
+}
+
+/// Options for a batch `convert` run.
+#[derive(Debug, Clone)]
+pub struct ConvertOptions {
+    /// Audit log to read.
+    pub input: PathBuf,
+    /// Format of `input`; `None` detects it from the first line.
+    pub input_format: Option<InputFormat>,
+    /// File to write converted events to (truncated unless resuming), or the
+    /// directory for daily files when `daily_pattern` is set.
+    pub output: PathBuf,
+    /// strftime pattern naming one output file per UTC day of the events
+    /// (e.g. `audit-%Y-%m-%d.log`); `None` writes everything to `output`.
+    pub daily_pattern: Option<String>,
+    /// Output format for the converted events.
+    pub format: LogFormat,
+    /// Checkpoint file; when set, progress is recorded periodically and a
+    /// later run with the same checkpoint resumes where this one stopped.
+    pub checkpoint: Option<PathBuf>,
+    /// Number of events written between checkpoint updates.
+    pub checkpoint_interval: usize,
+}
+
+/// Outcome of a batch `convert` run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertSummary {
+    /// Events written during this run (excluding any written before a resumed
+    /// checkpoint).
+    pub events: usize,
+    /// Input lines that could not be parsed and were skipped.
+    pub skipped_lines: usize,
+    /// Input byte offset the run resumed from, if a checkpoint was used.
+    pub resumed_from: Option<u64>,
+}
+
+/// Outcome of a `convert --merge` run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeSummary {
+    /// Events written.
+    pub events: usize,
+    /// Records dropped because an input already held an identical record
+    /// (e.g. overlapping copies of the same log).
+    pub duplicate_records: usize,
+    /// Input lines that could not be parsed and were skipped.
+    pub skipped_lines: usize,
+    /// Places where the serial jumps forward by more than one between
+    /// consecutive events, i.e. events none of the inputs hold.
+    pub serial_gaps: usize,
+}
+
+/// Progress of a batch conversion as persisted to the checkpoint file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    /// The input file the checkpoint belongs to.
+    input: PathBuf,
+    /// Byte offset of the first input line that has not been converted yet.
+    input_offset: u64,
+    /// Length of the output file once every event before `input_offset` was
+    /// written; anything past it is discarded on resume.
+    output_offset: u64,
+    /// `<timestamp>:<serial>` key of the last event written, for operators.
+    last_event: Option<String>,
+}
+
+/// Destination for converted events.
+enum ConvertOutput {
+    /// Line-oriented output (legacy, simple, or ECS) written event by event.
+    Lines(File, LogFormat),
+    /// A single JSON array document, closed when the conversion finishes.
+    Json(JsonArrayWriter<File>),
+    /// One file per UTC day in a directory.
+    Daily(DailyOutput),
+}
+
+/// Converted events split into one file per UTC day, rotating to the next file
+/// when an event from another day arrives.
+struct DailyOutput {
+    /// Directory the daily files are written to.
+    dir: PathBuf,
+    /// strftime pattern the file names are built from.
+    pattern: String,
+    /// Output format of every file.
+    format: LogFormat,
+    /// Name and output of the file of the current day.
+    current: Option<(String, Box<ConvertOutput>)>,
+    /// Names of the files written by this run; returning to one of them
+    /// appends instead of truncating it.
+    written: HashSet<String>,
+}
@@ -24,6 +24,7 @@ use crate::{
     utils::{
         current_utc_string,
         parse_rfc3339_timestamp,
+        read_from_ecs,
         read_from_json,
         read_from_legacy,
         read_from_simple,
@@ -48,6 +49,7 @@ pub fn generate_report(state: &State, matches: &ArgMatches) -> Result<()> {
         LogFormat::Legacy => read_from_legacy(&primary_directory),
         LogFormat::Simple => read_from_simple(&primary_directory),
         LogFormat::Json => read_from_json(&primary_directory),
+        LogFormat::Ecs => read_from_ecs(&primary_directory),
     };
 
     events = apply_time_window(&matches, events)?;
@@ -455,6 +457,7 @@ fn write_report_body<W: Write>(w: &mut W, events: &[AuditEvent], format: LogForm
             let body = serde_json::to_string_pretty(events)?;
             write!(w, "{body}\n")?;
         }
+        LogFormat::Ecs => AuditLogWriter::write_events_ecs(w, events)?,
     }
     Ok(())
 }
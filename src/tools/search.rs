@@ -2,7 +2,7 @@
 //!
 //! Searches correlated audit events in the primary log directory with optional
 //! filters (time range, field, event category, user, syscall outcome) and
-//! prints matches with simple or JSON format.
+//! prints matches with simple, JSON or CSV format.
 
 use std::borrow::Cow;
 use std::fs::{OpenOptions, create_dir_all};
@@ -17,13 +17,14 @@ use crate::config::LogFormat;
 use crate::core::correlator::AuditEvent;
 use crate::core::parser::ParsedAuditRecord;
 use crate::core::parser::RecordType;
+use crate::core::writer::csv;
 use crate::state::State;
 use crate::utils::{
-    current_utc_string, parse_rfc3339_timestamp, read_from_json, read_from_legacy, read_from_simple, systemtime_to_utc_string
+    current_utc_string, parse_rfc3339_timestamp, read_from_ecs, read_from_json, read_from_legacy, read_from_simple, systemtime_to_utc_string
 };
 
 /// Loads primary logs, applies CLI filters and the query expression, and prints
-/// matching events in simple or JSON format.
+/// matching events in simple, JSON or CSV format.
 ///
 /// **Parameters:**
 ///
@@ -36,6 +37,7 @@ pub fn search_events(state: &State, matches: &ArgMatches) -> Result<()> {
         LogFormat::Legacy => read_from_legacy(&primary_directory),
         LogFormat::Simple => read_from_simple(&primary_directory),
         LogFormat::Json => read_from_json(&primary_directory),
+        LogFormat::Ecs => read_from_ecs(&primary_directory),
     };
 
     events = apply_time_window(matches, events)?;
@@ -116,6 +118,7 @@ pub fn search_events(state: &State, matches: &ArgMatches) -> Result<()> {
     match output_format {
         "json" => format_json(&matched, output_path)?,
         "simple" => format_simple(&matched, output_path)?,
+        "csv" => format_csv(&matched, output_path)?,
         other => anyhow::bail!("Unsupported output format: {:?}", other),
     }
 
@@ -368,31 +371,23 @@ fn user_matches_any_identity_field(event: &AuditEvent, needle: &str) -> bool {
     false
 }
 
-/// Returns whether the event satisfies `--result` by inspecting `success` on
-/// records (typically SYSCALL).
+/// Returns whether the event satisfies `--result` by inspecting `success` or
+/// `res` on records (see [`ParsedAuditRecord::succeeded`]).
 ///
 /// **Parameters:**
 ///
 /// * `event`: Event to test.
 /// * `want`: Either `success` or `failed` from the CLI.
 fn event_matches_result(event: &AuditEvent, want: &str) -> bool {
-    for record in &event.records {
-        if let Some(s) = record.fields.get("success") {
-            let ok = match want {
-                "success" => {
-                    s.eq_ignore_ascii_case("yes") || s.eq_ignore_ascii_case("true") || *s == "1"
-                }
-                "failed" => {
-                    s.eq_ignore_ascii_case("no") || s.eq_ignore_ascii_case("false") || *s == "0"
-                }
-                _ => return true,
-            };
-            if ok {
-                return true;
-            }
-        }
-    }
-    false
+    let want = match want {
+        "success" => true,
+        "failed" => false,
+        _ => return true,
+    };
+    event
+        .records
+        .iter()
+        .any(|record| record.succeeded() == Some(want))
 }
 
 /// Returns whether the event matches the main search query: empty query with
@@ -504,13 +499,44 @@ fn free_text_matches(event: &AuditEvent, needle: &str, restrict_field: Option<&s
     false
 }
 
-fn default_search_path(format: LogFormat) -> PathBuf {
+fn default_search_path(extension: &str) -> PathBuf {
     let ts = current_utc_string().replace(':', "-");
-    PathBuf::from(format!(
-        "./search/search_{}.{}",
-        ts,
-        format.get_extension()
-    ))
+    PathBuf::from(format!("./search/search_{}.{}", ts, extension))
+}
+
+/// Writes formatted search results to a file, replacing its contents.
+///
+/// **Parameters:**
+///
+/// * `body`: The formatted results, written as is.
+/// * `extension`: Extension of the default file name, also added to a given
+///   path that has none, e.g. `csv`.
+/// * `output_path`: Path to write to; an empty path writes a timestamped file
+///   under `./search`.
+fn write_search_output(body: &str, extension: &str, output_path: &str) -> Result<()> {
+    let mut path = if output_path.is_empty() {
+        default_search_path(extension)
+    } else {
+        PathBuf::from(output_path)
+    };
+
+    if let Some(parent) = path.parent() {
+        if !parent.exists() {
+            create_dir_all(parent)?;
+        }
+    }
+
+    if path.extension().is_none() {
+        path.set_extension(extension);
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    write!(file, "{body}")?;
+    Ok(())
 }
 
 /// Writes a short count line and each event using the simple (`Display`) format.
@@ -525,28 +551,7 @@ fn format_simple(events: &[AuditEvent], output_path: Option<&str>) -> Result<()>
     writeln!(out, "Found {} events \n", events.len())?;
     let payload = events.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n");
     if let Some(path) = output_path {
-        let mut path =  if path.is_empty() {
-            default_search_path(LogFormat::Simple)
-        } else {
-            PathBuf::from(path)
-        };
-
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                create_dir_all(parent)?;
-            }
-        }
-
-        if path.extension().is_none() {
-            path.set_extension(LogFormat::Simple.get_extension());
-        }
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
-        writeln!(file, "{payload}")?;
+        write_search_output(&format!("{payload}\n"), &LogFormat::Simple.get_extension(), path)?;
     }
     writeln!(out, "{payload}")?;
     Ok(())
@@ -585,30 +590,27 @@ fn format_json(events: &[AuditEvent], output_path: Option<&str>) -> Result<()> {
         .collect();
     let body = serde_json::to_string_pretty(&payload)?;
     if let Some(path) = output_path {
-        let mut path = if path.is_empty() {
-            default_search_path(LogFormat::Json)
-        } else {
-            PathBuf::from(path)
-        };
-
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                create_dir_all(parent)?;
-            }
-        }
-
-        if path.extension().is_none() {
-            path.set_extension(LogFormat::Json.get_extension());
-        }
-
-        let mut file = OpenOptions::new()
-            .create(true)
-            .write(true)
-            .truncate(true)
-            .open(path)?;
-        writeln!(file, "{body}")?;
+        write_search_output(&format!("{body}\n"), &LogFormat::Json.get_extension(), path)?;
     }
     writeln!(out, "Found {} events \n", events.len())?;
     writeln!(out, "{body}")?;
     Ok(())
 }
+
+/// Writes the events as CSV, one row per record; filtering to a single record
+/// type gives that type's template columns. The count line goes to stderr so
+/// stdout is plain CSV.
+///
+/// **Parameters:**
+///
+/// * `events`: Matched events to print to stdout.
+/// * `output_path`: Path to write the events to.
+fn format_csv(events: &[AuditEvent], output_path: Option<&str>) -> Result<()> {
+    let body = csv::format_csv(events);
+    if let Some(path) = output_path {
+        write_search_output(&body, "csv", path)?;
+    }
+    eprintln!("Found {} events", events.len());
+    write!(io::stdout().lock(), "{body}")?;
+    Ok(())
+}
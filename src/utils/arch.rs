@@ -0,0 +1,119 @@
+//! Audit arch constants and host syscall names, shared by interpretation,
+//! enrichment, the SECCOMP accessors and the ECS mapping.
+//!
+//! Syscall numbers differ between architectures and only the table of the
+//! architecture this binary was built for is known (via the `syscalls`
+//! crate), so a syscall is only named when its record's `arch` is the host's.
+
+#[cfg(target_arch = "arm")]
+use syscalls::arm;
+#[cfg(target_arch = "riscv32")]
+use syscalls::riscv32;
+#[cfg(target_arch = "riscv64")]
+use syscalls::riscv64;
+#[cfg(target_arch = "x86")]
+use syscalls::x86;
+#[cfg(target_arch = "x86_64")]
+use syscalls::x86_64;
+
+/// Audit arch constants (`AUDIT_ARCH_*`, lowercase hex) and their names.
+const AUDIT_ARCHES: &[(&str, &str)] = &[
+    ("c000003e", "x86_64"),
+    ("40000003", "i386"),
+    ("c00000b7", "aarch64"),
+    ("40000028", "arm"),
+    ("c00000f3", "riscv64"),
+    ("400000f3", "riscv32"),
+    ("80000016", "s390x"),
+    ("80000015", "ppc64"),
+    ("c0000015", "ppc64le"),
+];
+
+/// The audit arch constant of the architecture this binary was built for,
+/// whose syscall table [`syscall_name`] uses.
+#[cfg(target_arch = "x86_64")]
+pub(crate) const HOST_AUDIT_ARCH: &str = "c000003e";
+#[cfg(target_arch = "x86")]
+pub(crate) const HOST_AUDIT_ARCH: &str = "40000003";
+#[cfg(target_arch = "riscv64")]
+pub(crate) const HOST_AUDIT_ARCH: &str = "c00000f3";
+#[cfg(target_arch = "riscv32")]
+pub(crate) const HOST_AUDIT_ARCH: &str = "400000f3";
+#[cfg(target_arch = "arm")]
+pub(crate) const HOST_AUDIT_ARCH: &str = "40000028";
+
+/// Returns the name of an audit arch constant, e.g. `x86_64` for
+/// `c000003e`, or `None` if it is unknown.
+///
+/// **Parameters:**
+///
+/// * `arch`: The arch constant in hex, in either case.
+pub(crate) fn arch_name(arch: &str) -> Option<&'static str> {
+    AUDIT_ARCHES
+        .iter()
+        .find(|(raw, _)| raw.eq_ignore_ascii_case(arch))
+        .map(|(_, name)| *name)
+}
+
+/// Returns whether `arch` (an audit arch constant such as `c000003e`) is the
+/// host architecture. Records without an arch are assumed to be.
+///
+/// **Parameters:**
+///
+/// * `arch`: The record's `arch` field, if any.
+pub(crate) fn is_host_arch(arch: Option<&str>) -> bool {
+    arch.is_none_or(|arch| arch.eq_ignore_ascii_case(HOST_AUDIT_ARCH))
+}
+
+/// Returns the name of a syscall number for the host architecture.
+///
+/// **Parameters:**
+///
+/// * `syscall_id`: The numeric syscall id.
+pub(crate) fn syscall_name(syscall_id: u32) -> &'static str {
+    #[cfg(target_arch = "x86_64")]
+    let syscall_name = x86_64::Sysno::from(syscall_id).name();
+    #[cfg(target_arch = "x86")]
+    let syscall_name = x86::Sysno::from(syscall_id).name();
+    #[cfg(target_arch = "riscv64")]
+    let syscall_name = riscv64::Sysno::from(syscall_id).name();
+    #[cfg(target_arch = "riscv32")]
+    let syscall_name = riscv32::Sysno::from(syscall_id).name();
+    #[cfg(target_arch = "arm")]
+    let syscall_name = arm::Sysno::from(syscall_id).name();
+
+    syscall_name
+}
+
+/// Returns the name of a syscall number issued on `arch`, or `None` when
+/// `arch` is not the host architecture whose syscall table is known.
+///
+/// **Parameters:**
+///
+/// * `arch`: The record's `arch` field, if any.
+/// * `syscall_id`: The numeric syscall id.
+pub(crate) fn syscall_name_for_arch(arch: Option<&str>, syscall_id: u32) -> Option<&'static str> {
+    is_host_arch(arch).then(|| syscall_name(syscall_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syscalls_are_named_only_for_the_host_arch() {
+        assert_eq!(arch_name("C000003E"), Some("x86_64"));
+        assert_eq!(arch_name("deadbeef"), None);
+        assert_eq!(
+            syscall_name_for_arch(Some(HOST_AUDIT_ARCH), 59),
+            Some(syscall_name(59))
+        );
+        assert_eq!(syscall_name_for_arch(None, 59), Some(syscall_name(59)));
+        let foreign = if HOST_AUDIT_ARCH == "80000016" {
+            "c000003e"
+        } else {
+            "80000016"
+        };
+        assert_eq!(syscall_name_for_arch(Some(foreign), 59), None);
+    }
+}
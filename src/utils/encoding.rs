@@ -0,0 +1,155 @@
+//! Escaping that keeps every byte auditrs writes valid UTF-8.
+//!
+//! Audit data is mostly ASCII, but decoded hex fields (e.g. `proctitle`) and
+//! log lines read back from disk can contain arbitrary bytes. Rather than
+//! replacing those with U+FFFD, auditrs escapes them so that output stays
+//! valid, BOM-free UTF-8 and the original bytes can be recovered:
+//!
+//! | Input                                   | Written as |
+//! |-----------------------------------------|------------|
+//! | a byte that is not part of valid UTF-8  | `\xHH`     |
+//! | an ASCII control character (incl. `\n`) | `\xHH`     |
+//! | a backslash (only in [`escape_bytes`])  | `\\`       |
+//!
+//! [`escape_bytes`] is fully reversible with [`unescape_bytes`].
+//! [`escape_invalid_utf8`] and [`escape_control_chars`] leave backslashes
+//! alone so already-readable text is unchanged; they are reversible unless the
+//! input itself contained a literal `\xHH` sequence.
+
+use std::borrow::Cow;
+use std::fmt::Write;
+
+/// Escapes arbitrary bytes into valid UTF-8: invalid UTF-8 bytes and ASCII
+/// control characters become `\xHH` and backslashes become `\\`. Reverse with
+/// [`unescape_bytes`].
+///
+/// **Parameters:**
+///
+/// * `bytes`: The raw bytes to escape.
+pub fn escape_bytes(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                c if c.is_ascii_control() => push_hex(&mut out, c as u8),
+                c => out.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            push_hex(&mut out, *byte);
+        }
+    }
+    out
+}
+
+/// Escapes only the bytes that are not valid UTF-8 as `\xHH`, leaving valid
+/// text (including backslashes) untouched. Used for whole log lines, which
+/// must stay parseable.
+///
+/// **Parameters:**
+///
+/// * `bytes`: The raw bytes to escape.
+pub fn escape_invalid_utf8(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(bytes.len() + 8);
+    for chunk in bytes.utf8_chunks() {
+        out.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            push_hex(&mut out, *byte);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Escapes ASCII control characters as `\xHH` so a value cannot break the
+/// line structure of text logs.
+///
+/// **Parameters:**
+///
+/// * `text`: The value to escape.
+pub fn escape_control_chars(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(|c| c.is_ascii_control()) {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        if c.is_ascii_control() {
+            push_hex(&mut out, c as u8);
+        } else {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Reverses [`escape_bytes`]: `\xHH` becomes the byte `HH` and `\\` a single
+/// backslash. Malformed escapes are kept literally.
+///
+/// **Parameters:**
+///
+/// * `text`: The escaped text.
+pub fn unescape_bytes(text: &str) -> Vec<u8> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'\\' {
+            if bytes.get(i + 1) == Some(&b'\\') {
+                out.push(b'\\');
+                i += 2;
+                continue;
+            }
+            if bytes.get(i + 1) == Some(&b'x')
+                && let Some(hex) = text.get(i + 2..i + 4)
+                && let Ok(byte) = u8::from_str_radix(hex, 16)
+            {
+                out.push(byte);
+                i += 4;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Appends `\xHH` for `byte`.
+fn push_hex(out: &mut String, byte: u8) {
+    let _ = write!(out, "\\x{:02x}", byte);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_bytes_round_trips() {
+        let inputs: [&[u8]; 4] = [
+            b"/usr/bin/ls",
+            b"\xff\xfe/tmp\nname\x00",
+            b"back\\slash \\x41",
+            "caf\u{e9} \u{1f600}".as_bytes(),
+        ];
+        for input in inputs {
+            let escaped = escape_bytes(input);
+            assert!(!escaped.chars().any(|c| c.is_ascii_control()));
+            assert_eq!(unescape_bytes(&escaped), input);
+        }
+        assert_eq!(escape_bytes(b"\xff\xfe/tmp\n"), "\\xff\\xfe/tmp\\x0a");
+    }
+
+    #[test]
+    fn partial_escapes_leave_valid_text_alone() {
+        assert!(matches!(
+            escape_invalid_utf8(b"type=PATH name=/a\\b"),
+            Cow::Borrowed("type=PATH name=/a\\b")
+        ));
+        assert_eq!(escape_invalid_utf8(b"name=\xc3\x28"), "name=\\xc3(");
+        assert!(matches!(escape_control_chars("plain"), Cow::Borrowed(_)));
+        assert_eq!(escape_control_chars("a\r\nb\x7f"), "a\\x0d\\x0ab\\x7f");
+    }
+}
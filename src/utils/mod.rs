@@ -6,11 +6,17 @@
 //!   validators used by interactive commands.
 //! - `utils` provides general-purpose helpers (time formatting, string
 //!   manipulation, filesystem helpers, etc.).
+//! - `encoding` escapes raw bytes and control characters so all output stays
+//!   valid UTF-8 (see the module docs for the scheme).
 //! - `reading_utils` supports higher-level tools that need to scan or process
 //!   existing audit logs.
+//! - `arch` names audit arch constants and, for the host architecture, syscall
+//!   numbers.
 //! Keeping these utilities centralized avoids duplication between the CLI,
 //! daemon, and tools modules.
 
+mod arch;
+mod encoding;
 mod input_utils;
 mod reading_utils;
 mod utils;
@@ -19,6 +25,14 @@ mod utils;
 // SystemTime serialization.
 pub mod serde_systemtime;
 
+pub(crate) use arch::{
+    HOST_AUDIT_ARCH,
+    arch_name,
+    is_host_arch,
+    syscall_name,
+    syscall_name_for_arch,
+};
+pub use encoding::*;
 pub use input_utils::*;
 pub use reading_utils::*;
 pub use utils::*;
@@ -1,15 +1,14 @@
 use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
-use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Context;
 use strum::IntoEnumIterator;
 
 use crate::core::correlator::AuditEvent;
-use crate::core::netlink::RawAuditRecord;
-use crate::core::parser::{ParsedAuditRecord, RecordType};
+use crate::core::parser::parser::parse_line;
+use crate::core::parser::{ParseOptions, ParsedAuditRecord, RecordType};
 
 /// Reads audit events from JSON files in the primary directory.
 ///
@@ -25,14 +24,76 @@ pub fn read_from_json(primary_directory: &PathBuf) -> Vec<AuditEvent> {
             continue;
         }
         let content = fs::read_to_string(file.path()).unwrap();
-        let event: Vec<AuditEvent> = serde_json::from_str(&content)
-            .map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))
-            .unwrap();
-        events.extend(event.into_iter());
+        events.extend(parse_json_events(&content).unwrap());
     }
     events
 }
 
+/// Parses a JSON primary log file (a single top-level array of events) into a
+/// [`Vec<AuditEvent>`].
+///
+/// **Parameters:**
+///
+/// * `content`: The content of the JSON primary log file.
+pub fn parse_json_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
+    serde_json::from_str(content).map_err(|e| anyhow::anyhow!("Failed to parse JSON: {}", e))
+}
+
+/// Reads audit events from Elastic Common Schema primary files (`.ndjson`).
+///
+/// **Parameters:**
+///
+/// * `primary_directory`: The path to the primary directory.
+pub fn read_from_ecs(primary_directory: &PathBuf) -> Vec<AuditEvent> {
+    let mut paths: Vec<_> = fs::read_dir(primary_directory)
+        .unwrap()
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "ndjson"))
+        .collect();
+    paths.sort();
+    let mut events = Vec::new();
+    for path in paths {
+        let content = fs::read_to_string(&path).unwrap();
+        match parse_ecs_events(&content) {
+            Ok(mut ev) => events.append(&mut ev),
+            Err(e) => {
+                eprintln!(
+                    "warning: failed to parse ECS log {}: {:?}",
+                    path.display(),
+                    e
+                )
+            }
+        }
+    }
+    events
+}
+
+/// Parses an ECS primary log file (one document per line) into a
+/// [`Vec<AuditEvent>`].
+///
+/// The ECS mapping is lossy, so each event is rebuilt from the legacy records
+/// kept in its `event.original` field. Documents without one, such as
+/// pipeline warnings, are skipped.
+///
+/// **Parameters:**
+///
+/// * `content`: The content of the ECS primary log file.
+pub fn parse_ecs_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
+    let mut events = Vec::new();
+    for line in content.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let document: serde_json::Value = serde_json::from_str(line).context("ECS document")?;
+        let Some(original) = document["event"]["original"].as_str() else {
+            continue;
+        };
+        events.extend(parse_legacy_events(original)?);
+    }
+    Ok(events)
+}
+
 /// Reads audit events from simple-format primary files (`.slog`).
 ///
 /// Format matches [`std::fmt::Display`] on
@@ -86,7 +147,7 @@ pub fn read_from_legacy(primary_directory: &PathBuf) -> Vec<AuditEvent> {
     paths.sort();
     for path in paths {
         let content = fs::read_to_string(&path).unwrap();
-        for line in content.lines() {
+        for line in split_log_lines(&content) {
             let line = line.trim();
             if line.is_empty() {
                 continue;
@@ -100,34 +161,52 @@ pub fn read_from_legacy(primary_directory: &PathBuf) -> Vec<AuditEvent> {
     correlate_records(all_records)
 }
 
+/// Parses a legacy primary log file as written by the auditrs writer into a
+/// [`Vec<AuditEvent>`], grouping records like [`read_from_legacy`].
+///
+/// Unlike [`read_from_legacy`], which skips lines it cannot parse, this fails
+/// on the first such line.
+///
+/// **Parameters:**
+///
+/// * `content`: The content of the legacy primary log file.
+pub fn parse_legacy_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
+    let records = split_log_lines(content)
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_legacy_primary_line)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(correlate_records(records))
+}
+
+/// Splits log content into lines regardless of the record separator it was
+/// written with (LF, CRLF, or NUL).
+///
+/// **Parameters:**
+///
+/// * `content`: The content of a legacy or simple-format log file.
+fn split_log_lines(content: &str) -> impl Iterator<Item = &str> {
+    content
+        .split(['\n', '\0'])
+        .map(|line| line.trim_end_matches('\r'))
+}
+
 /// Parses a legacy primary log line as written by the auditrs writer into a
 /// [`ParsedAuditRecord`]: `type=RECORD_TYPE
 /// msg=audit(<seconds>.<millis>:<serial>): key=value ...`
 ///
-/// Strips the `type=` / `msg=` wrapper and delegates to
-/// [`ParsedAuditRecord::try_from`] ([`RawAuditRecord`]), matching the netlink
-/// path.
+/// Delegates to [`parse_line`] with strict [`ParseOptions`], so lines missing
+/// the leading `type=` / `msg=audit(...)` fields are rejected with a specific
+/// [`crate::core::parser::ParseError`].
 ///
 /// **Parameters:**
 ///
 /// * `line`: The line to parse.
-fn parse_legacy_primary_line(line: &str) -> anyhow::Result<ParsedAuditRecord> {
+pub fn parse_legacy_primary_line(line: &str) -> anyhow::Result<ParsedAuditRecord> {
     let line = line.trim();
     if line.is_empty() {
         anyhow::bail!("empty line");
     }
-    let rest = line
-        .strip_prefix("type=")
-        .ok_or_else(|| anyhow::anyhow!("legacy line missing leading type="))?;
-    let (type_str, after_type) = rest
-        .split_once(" msg=audit(")
-        .ok_or_else(|| anyhow::anyhow!("legacy line missing msg=audit( after type"))?;
-    let record_id = u16::from(
-        RecordType::from_str(type_str.trim())
-            .map_err(|_| anyhow::anyhow!("unknown record type string {:?}", type_str.trim()))?,
-    );
-    let data = format!("audit({}", after_type);
-    ParsedAuditRecord::try_from(RawAuditRecord::new(record_id, data))
+    Ok(parse_line(line, &ParseOptions::default())?)
 }
 
 /// Groups flat [`ParsedAuditRecord`]s into [`AuditEvent`]s using `(timestamp,
@@ -157,6 +236,7 @@ fn correlate_records(records: Vec<ParsedAuditRecord>) -> Vec<AuditEvent> {
                 serial: id.1,
                 record_count: n,
                 records,
+                amendment: false,
             }
         })
         .collect()
@@ -168,13 +248,12 @@ fn correlate_records(records: Vec<ParsedAuditRecord>) -> Vec<AuditEvent> {
 /// **Parameters:**
 ///
 /// * `content`: The content of the simple-format primary log file.
-fn parse_simple_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
+pub fn parse_simple_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
     // Parse of hell
     let mut events = Vec::new();
     let mut cur: Option<(SystemTime, u16, u16, Vec<ParsedAuditRecord>)> = None;
 
-    for line in content.lines() {
-        let line = line.trim_end_matches('\r');
+    for line in split_log_lines(content) {
         if line.trim().is_empty() {
             continue;
         }
@@ -185,6 +264,7 @@ fn parse_simple_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
                     serial: ser,
                     record_count: recs.len() as u16,
                     records: recs,
+                    amendment: false,
                 });
             }
             let rest = line.trim().strip_prefix('[').context("header [")?;
@@ -219,6 +299,7 @@ fn parse_simple_events(content: &str) -> anyhow::Result<Vec<AuditEvent>> {
             serial: ser,
             record_count: n,
             records: recs,
+            amendment: false,
         });
     }
 
@@ -284,6 +365,9 @@ fn parse_simple_record(line: &str) -> anyhow::Result<ParsedAuditRecord> {
         timestamp: UNIX_EPOCH + Duration::new(tv_sec.context("tv_sec")?, tv_nsec.unwrap_or(0)),
         serial,
         fields,
+        quoted: Default::default(),
+        truncated: Default::default(),
+        nested: Default::default(),
     })
 }
 
@@ -7,27 +7,36 @@ use anyhow::Result;
 use chrono::{DateTime, Utc};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-/// Convert an `<seconds>.<millis>` timestamp string into a `SystemTime`.
+/// Convert an `<seconds>.<fraction>` timestamp string into a `SystemTime`.
 ///
 /// The input is expected to be in the same format used by the Linux audit
-/// subsystem (e.g. `"1234567890.123"`). Only the first three fractional
-/// digits are interpreted as milliseconds; any additional precision is
-/// ignored.
+/// subsystem (e.g. `"1234567890.123"`, or `"1234567890.123456"` on kernels
+/// with higher precision). The fraction is interpreted by its digit count, so
+/// `.5`, `.500` and `.500000` are all half a second; digits beyond nanosecond
+/// precision are ignored. Timestamps that do not fit in a `SystemTime` are an
+/// error.
 ///
 /// **Parameters:**
 ///
-/// * `secs_micros_str`: String of the form `<seconds>.<fraction>` representing
-///   a UNIX timestamp in seconds plus fractional seconds.
-pub fn timestamp_string_to_systemtime(secs_micros_str: &str) -> Result<SystemTime> {
-    let (secs_str, micros_str) = secs_micros_str
+/// * `secs_fraction_str`: String of the form `<seconds>.<fraction>`
+///   representing a UNIX timestamp in seconds plus fractional seconds.
+pub fn timestamp_string_to_systemtime(secs_fraction_str: &str) -> Result<SystemTime> {
+    let (secs_str, fraction_str) = secs_fraction_str
         .split_once('.')
-        .ok_or(anyhow::anyhow!("Invalid timestamp format"))?;
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp format"))?;
 
     let seconds: u64 = secs_str.parse()?;
 
-    let millis: u64 = micros_str.get(0..3).unwrap_or(micros_str).parse()?;
+    if fraction_str.is_empty() || !fraction_str.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!("Invalid timestamp fraction '{}'", fraction_str);
+    }
+    let digits = &fraction_str[..fraction_str.len().min(9)];
+    let nanos = digits.parse::<u32>()? * 10u32.pow(9 - digits.len() as u32);
 
-    Ok(UNIX_EPOCH + Duration::from_secs(seconds) + Duration::from_millis(millis))
+    Duration::from_secs(seconds)
+        .checked_add(Duration::from_nanos(nanos.into()))
+        .and_then(|offset| UNIX_EPOCH.checked_add(offset))
+        .ok_or_else(|| anyhow::anyhow!("Timestamp out of range"))
 }
 
 /// Render a `SystemTime` as an RFC3339-like UTC timestamp string.
@@ -52,10 +61,12 @@ pub fn current_utc_string() -> String {
     dt.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
 }
 
-/// Convert a `SystemTime` into a `<seconds>.<millis>` timestamp string.
+/// Convert a `SystemTime` into a `<seconds>.<fraction>` timestamp string.
 ///
-/// This is the inverse of [`timestamp_string_to_systemtime`] with
-/// millisecond precision.
+/// This is the inverse of [`timestamp_string_to_systemtime`]. The fraction has
+/// three digits (milliseconds) unless the time has a sub-millisecond part, in
+/// which case it has six (microseconds) or nine (nanoseconds), so timestamps
+/// of higher-precision kernels survive a round-trip.
 ///
 /// **Parameters:**
 ///
@@ -63,11 +74,14 @@ pub fn current_utc_string() -> String {
 ///   timestamp.
 pub fn systemtime_to_timestamp_string(systime: SystemTime) -> Result<String> {
     let duration = systime.duration_since(UNIX_EPOCH)?;
-    Ok(format!(
-        "{}.{:03}",
-        duration.as_secs(),
-        duration.subsec_millis()
-    ))
+    let (secs, nanos) = (duration.as_secs(), duration.subsec_nanos());
+    Ok(if nanos.is_multiple_of(1_000_000) {
+        format!("{}.{:03}", secs, nanos / 1_000_000)
+    } else if nanos.is_multiple_of(1_000) {
+        format!("{}.{:06}", secs, nanos / 1_000)
+    } else {
+        format!("{}.{:09}", secs, nanos)
+    })
 }
 
 /// Capitalize the first Unicode character in a string.
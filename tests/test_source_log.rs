@@ -224,3 +224,27 @@ fn source_log_to_readable_helper() {
         );
     }
 }
+
+#[test]
+fn source_log_subj_parses_as_selinux_context() {
+    use auditrs::core::netlink::RawAuditRecord;
+    use auditrs::core::parser::ParsedAuditRecord;
+
+    let path = Path::new(SOURCE_LOG);
+    let file = std::io::BufReader::new(
+        std::fs::File::open(&path).expect("test-source.log should be readable"),
+    );
+    let context = file
+        .lines()
+        .filter_map(Result::ok)
+        .filter_map(|line| hex_decode(&line).ok())
+        .filter_map(|bytes| RawAuditRecord::from_netlink_bytes(&bytes).ok())
+        .filter_map(|raw| ParsedAuditRecord::try_from(raw).ok())
+        .find_map(|record| record.selinux_context("subj"))
+        .expect("sample log should contain a record with a subj= context");
+
+    assert_eq!(context.user(), "unconfined_u");
+    assert_eq!(context.role(), "unconfined_r");
+    assert_eq!(context.type_(), "unconfined_t");
+    assert_eq!(context.range(), Some("s0-s0:c0.c1023"));
+}